@@ -0,0 +1,34 @@
+use super::{Adapter, BoxFuture, ReqwestAdapter};
+use crate::EchoError;
+
+impl ReqwestAdapter {
+    /// Uses your own client, e.g. one with a proxy or custom TLS settings.
+    /// Build it with `redirect::Policy::none()` to keep the redirect chain on responses.
+    pub fn new(client: reqwest::Client) -> Self {
+        ReqwestAdapter { client }
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+impl Default for ReqwestAdapter {
+    /// Redirects are left to `Echo`, so they can be recorded on the response.
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("failed to build the http client");
+        ReqwestAdapter { client }
+    }
+}
+
+impl Adapter for ReqwestAdapter {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+        Box::pin(async move { Ok(self.client.execute(request).await?) })
+    }
+}
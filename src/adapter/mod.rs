@@ -0,0 +1,45 @@
+#[allow(clippy::module_inception)]
+pub mod adapter;
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::EchoError;
+
+/// A boxed, sendable future, the return type of `Adapter::send`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// `adapter`, the transport that actually sends requests.
+///
+/// It gets the fully built request (url with params, headers, encoded and transformed body,
+/// timeout) and returns the raw response, redirects are followed by `Echo` itself.
+/// Set one on the config to send requests anywhere else, e.g. to an in-memory fake in tests.
+/// ```rs
+/// #[derive(Debug)]
+/// struct Offline;
+///
+/// impl Adapter for Offline {
+///     fn send(&self, request: reqwest::Request) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+///         Box::pin(async move {
+///             let response = http::Response::builder()
+///                 .status(200)
+///                 .url(request.url().clone()) // reqwest::ResponseBuilderExt
+///                 .body(r#"{ "id": 1 }"#)
+///                 .unwrap();
+///             Ok(response.into())
+///         })
+///     }
+/// }
+/// ```
+pub trait Adapter: std::fmt::Debug + Send + Sync {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>>;
+}
+
+/// The default adapter, sends requests with a `reqwest::Client`.
+#[derive(Debug, Clone)]
+pub struct ReqwestAdapter {
+    client: reqwest::Client,
+}
@@ -0,0 +1,157 @@
+use super::{BaseUrls, BaseUrlsState, EndpointState, Strategy};
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+impl BaseUrls {
+    /// Round robin over `urls`, ejecting unreachable ones for 30 seconds.
+    pub fn new<I, S>(urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let urls: Vec<String> = urls.into_iter().map(Into::into).collect();
+        let state = BaseUrlsState {
+            next: 0,
+            // never zero, xorshift would get stuck there
+            seed: RandomState::new().build_hasher().finish() | 1,
+            endpoints: vec![EndpointState::default(); urls.len()],
+        };
+        BaseUrls {
+            urls,
+            strategy: Strategy::default(),
+            eject_for: Duration::from_secs(30),
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// How long a base url that couldn't be connected to is skipped.
+    pub fn eject_for(mut self, eject_for: Duration) -> Self {
+        self.eject_for = eject_for;
+        self
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// The base urls skipped right now.
+    pub fn ejected(&self) -> Vec<&str> {
+        let now = Instant::now();
+        let state = self.state();
+        self.urls
+            .iter()
+            .zip(&state.endpoints)
+            .filter(|(_, endpoint)| endpoint.is_ejected(now))
+            .map(|(url, _)| url.as_str())
+            .collect()
+    }
+
+    /// The first base url that isn't ejected, what `Echo::get_full_url` resolves against.
+    pub fn preferred(&self) -> Option<&str> {
+        let now = Instant::now();
+        let state = self.state();
+        let index = (0..self.urls.len())
+            .find(|&index| !state.endpoints[index].is_ejected(now))
+            .unwrap_or(0);
+        self.urls.get(index).map(String::as_str)
+    }
+
+    /// Picks a base url other than the `tried` ones, ejected ones only when nothing else is left.
+    pub(crate) fn pick(&self, tried: &[usize]) -> Option<Endpoint<'_>> {
+        let now = Instant::now();
+        let mut state = self.state();
+        let untried: Vec<usize> = (0..self.urls.len())
+            .filter(|index| !tried.contains(index))
+            .collect();
+        let healthy: Vec<usize> = untried
+            .iter()
+            .copied()
+            .filter(|&index| !state.endpoints[index].is_ejected(now))
+            .collect();
+        let pool = if healthy.is_empty() { untried } else { healthy };
+
+        let index = match self.strategy {
+            Strategy::RoundRobin => {
+                let next = state.next;
+                let index = *pool
+                    .iter()
+                    .find(|&&index| index >= next)
+                    .or_else(|| pool.first())?;
+                state.next = index + 1;
+                index
+            }
+            Strategy::Random => {
+                let random = state.random();
+                *pool.get(random as usize % pool.len().max(1))?
+            }
+            Strategy::LeastInFlight => *pool
+                .iter()
+                .min_by_key(|&&index| state.endpoints[index].in_flight)?,
+            Strategy::Failover => *pool.first()?,
+        };
+        state.endpoints[index].in_flight += 1;
+        Some(Endpoint {
+            base_urls: self,
+            index,
+        })
+    }
+
+    /// Skips the base url at `index` for `eject_for`.
+    pub(crate) fn eject(&self, index: usize) {
+        let until = Instant::now().checked_add(self.eject_for);
+        self.state().endpoints[index].ejected_until = until;
+    }
+
+    fn state(&self) -> MutexGuard<'_, BaseUrlsState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl BaseUrlsState {
+    /// xorshift64, plenty for spreading requests
+    fn random(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+impl EndpointState {
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.is_some_and(|until| now < until)
+    }
+}
+
+/// A base url picked for a request, counted as in flight until dropped.
+#[derive(Debug)]
+pub(crate) struct Endpoint<'b> {
+    base_urls: &'b BaseUrls,
+    index: usize,
+}
+
+impl<'b> Endpoint<'b> {
+    pub(crate) fn url(&self) -> &'b str {
+        &self.base_urls.urls[self.index]
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Drop for Endpoint<'_> {
+    fn drop(&mut self) {
+        self.base_urls.state().endpoints[self.index].in_flight -= 1;
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[allow(clippy::module_inception)]
+pub mod base_urls;
+
+/// `base_urls`, replicas of the same api that requests with a relative `url` are spread over.
+///
+/// Each request goes to the base url picked by the `Strategy`. One that can't be connected to
+/// is ejected for a while (`eject_for`), and idempotent requests (`GET`, `HEAD`, `OPTIONS`, `PUT`,
+/// `DELETE`, `TRACE`) are retried on another one, as are requests its circuit breaker turned away.
+/// ```rs
+/// let replicas = BaseUrls::new(["https://eu.api.example.com", "https://us.api.example.com"])
+///     .strategy(Strategy::LeastInFlight)
+///     .eject_for(Duration::from_secs(10));
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     base_urls: Some(Arc::new(replicas)),
+///     ..Default::default()
+/// }));
+///
+/// let user = echo.get::<User>("/users/1").await?;
+/// println!("served by {:?}", user.config.base_url);
+/// ```
+#[derive(Debug)]
+pub struct BaseUrls {
+    urls: Vec<String>,
+    strategy: Strategy,
+    eject_for: Duration,
+    state: Mutex<BaseUrlsState>,
+}
+
+/// How `BaseUrls` picks the base url of a request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// each in turn
+    #[default]
+    RoundRobin,
+    Random,
+    /// the one with the fewest requests in flight, the first of them on a tie
+    LeastInFlight,
+    /// the first one, unless it's ejected
+    Failover,
+}
+
+#[derive(Debug)]
+struct BaseUrlsState {
+    /// where round robin goes next
+    next: usize,
+    /// xorshift state for `Strategy::Random`
+    seed: u64,
+    endpoints: Vec<EndpointState>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct EndpointState {
+    in_flight: usize,
+    ejected_until: Option<Instant>,
+}
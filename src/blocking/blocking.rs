@@ -0,0 +1,238 @@
+use super::{BlockingAdapter, Echo};
+use crate::adapter::BoxFuture;
+use crate::echo_errors::ErrorData;
+use crate::{Adapter, EchoError, ErrorCode, RequestConfig, Response, ResponseUnknown};
+
+use reqwest::ResponseBuilderExt;
+use std::sync::Arc;
+
+impl<'a> Echo<'a> {
+    /// Create a blocking Echo instance, see `echo_http::Echo::configure`.
+    /// Fails when the http client or the runtime driving the pipeline can't be set up.
+    /// ```rs
+    /// let echo = echo_http::blocking::Echo::configure(None)?;
+    /// let res = echo.get::<User>("https://jsonplaceholder.typicode.com/users/1")?;
+    /// ```
+    pub fn configure(config: Option<RequestConfig<'a>>) -> Result<Self, EchoError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| EchoError::Network {
+                code: ErrorCode::Network,
+                message: format!("failed to build the tokio runtime: {err}"),
+            })?;
+        let blocking = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let adapter = Arc::new(BlockingAdapter::new(blocking));
+        Ok(Echo {
+            inner: crate::Echo::with_adapter(config, adapter, client),
+            runtime,
+        })
+    }
+
+    /// The instance config, what `echo.config` is on the async `Echo`.
+    pub fn config(&self) -> &RequestConfig<'a> {
+        &self.inner.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut RequestConfig<'a> {
+        &mut self.inner.config
+    }
+
+    /// get request for an unknown endpoint
+    pub fn get_unknown(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.runtime.block_on(self.inner.get_unknown(url))
+    }
+
+    /// get request
+    /// ```rs
+    /// let res = echo.get::<User>("/users/1")?;
+    /// ```
+    pub fn get<T>(&self, url: &str) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.get(url))
+    }
+
+    /// post request
+    pub fn post<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.post(url, data))
+    }
+
+    /// post request with no data
+    pub fn post_no(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.runtime.block_on(self.inner.post_no(url))
+    }
+
+    /// put request
+    pub fn put<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.put(url, data))
+    }
+
+    /// patch request
+    pub fn patch<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.patch(url, data))
+    }
+
+    /// delete request
+    pub fn delete(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.runtime.block_on(self.inner.delete(url))
+    }
+
+    /// head request
+    pub fn head(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.runtime.block_on(self.inner.head(url))
+    }
+
+    /// request with a per-request config, see `echo_http::Echo::request`
+    pub fn request<'r, T>(&'r self, config: RequestConfig<'r>) -> Result<Response<'r, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.request(config))
+    }
+
+    /// same as `request`, but for an unknown response type
+    pub fn request_unknown<'r>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<ResponseUnknown<'r>, EchoError> {
+        self.runtime.block_on(self.inner.request_unknown(config))
+    }
+
+    /// get request that decodes error responses into `E`, see `echo_http::Echo::get_with_error`
+    pub fn get_with_error<T, E>(&self, url: &str) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.runtime
+            .block_on(self.inner.get_with_error::<T, E>(url))
+    }
+
+    /// post request that decodes error responses into `E`
+    pub fn post_with_error<T, E>(
+        &self,
+        url: &str,
+        data: Option<T>,
+    ) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.runtime
+            .block_on(self.inner.post_with_error::<T, E>(url, data))
+    }
+
+    /// put request that decodes error responses into `E`
+    pub fn put_with_error<T, E>(
+        &self,
+        url: &str,
+        data: Option<T>,
+    ) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.runtime
+            .block_on(self.inner.put_with_error::<T, E>(url, data))
+    }
+
+    /// `request` that decodes error responses into `E`
+    pub fn request_with_error<'r, T, E>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<Response<'r, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.runtime
+            .block_on(self.inner.request_with_error::<T, E>(config))
+    }
+}
+
+impl BlockingAdapter {
+    /// Uses your own client, build it with `redirect::Policy::none()`
+    /// to keep the redirect chain on responses.
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        BlockingAdapter { client }
+    }
+
+    pub fn client(&self) -> &reqwest::blocking::Client {
+        &self.client
+    }
+}
+
+impl Default for BlockingAdapter {
+    /// Redirects are left to `Echo`, so they can be recorded on the response.
+    fn default() -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("failed to build the http client");
+        BlockingAdapter { client }
+    }
+}
+
+impl Adapter for BlockingAdapter {
+    /// Sends the request on tokio's blocking pool, so the runtime keeps driving timers meanwhile.
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let mut blocking = client
+                .request(request.method().clone(), request.url().clone())
+                .headers(request.headers().clone());
+            if let Some(timeout) = request.timeout() {
+                blocking = blocking.timeout(*timeout);
+            }
+            if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+                blocking = blocking.body(body.to_vec());
+            }
+
+            tokio::task::spawn_blocking(move || {
+                let response = blocking.send()?;
+                let status = response.status();
+                let version = response.version();
+                let url = response.url().clone();
+                let headers = response.headers().clone();
+                let body = response.bytes()?;
+
+                let mut rebuilt = http::Response::builder()
+                    .status(status)
+                    .version(version)
+                    .url(url);
+                if let Some(rebuilt_headers) = rebuilt.headers_mut() {
+                    *rebuilt_headers = headers;
+                }
+                let rebuilt = rebuilt.body(body).map_err(|err| EchoError::Network {
+                    code: ErrorCode::BadResponse,
+                    message: format!("invalid response: {err}"),
+                })?;
+                Ok(rebuilt.into())
+            })
+            .await
+            .map_err(|err| EchoError::Network {
+                code: ErrorCode::Network,
+                message: format!("the blocking request did not finish: {err}"),
+            })?
+        })
+    }
+}
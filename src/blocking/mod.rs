@@ -0,0 +1,31 @@
+#[allow(clippy::module_inception)]
+pub mod blocking;
+
+/// The synchronous twin of `echo_http::Echo`, for build scripts and small tools without an async runtime.
+///
+/// Same methods, same `RequestConfig`, `Response` and `EchoError`, minus the `.await`.
+/// Requests are sent with reqwest's blocking client, so like it, this must not be used
+/// from inside an async runtime.
+/// ```rs
+/// use echo_http::blocking::Echo;
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     base_url: Some("https://jsonplaceholder.typicode.com".to_string()),
+///     ..Default::default()
+/// }))?;
+///
+/// let user = echo.get::<User>("/users/1")?;
+/// ```
+/// The rest of the pipeline (retries, queues, caches, ..) runs on a current-thread tokio runtime
+/// owned by the instance, so adapters set on the config may be async ones too.
+pub struct Echo<'a> {
+    inner: crate::Echo<'a>,
+    /// runs every request to completion on the calling thread
+    runtime: tokio::runtime::Runtime,
+}
+
+/// Sends requests with a `reqwest::blocking::Client`, the default adapter of `blocking::Echo`.
+#[derive(Debug, Clone)]
+pub struct BlockingAdapter {
+    client: reqwest::blocking::Client,
+}
@@ -0,0 +1,455 @@
+use super::{CacheMode, CacheStore, CachedResponse, DiskCache, MemoryCache, MemoryEntries};
+
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use reqwest::{Method, ResponseBuilderExt};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Statuses that may be stored without explicit freshness, RFC 9110 section 15.1.
+const HEURISTICALLY_CACHEABLE: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Heuristic freshness is 10% of the time since `Last-Modified`, at most this long.
+const MAX_HEURISTIC_FRESHNESS: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Hop-by-hop headers, never stored.
+const NOT_STORED: &[&str] = &["connection", "keep-alive", "transfer-encoding"];
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        MemoryCache::default()
+    }
+
+    /// Evicts the oldest response once `max_entries` are stored.
+    pub fn bounded(max_entries: usize) -> Self {
+        MemoryCache {
+            max_entries: Some(max_entries),
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        *self.entries() = MemoryEntries::default();
+    }
+
+    fn entries(&self) -> MutexGuard<'_, MemoryEntries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.entries().responses.get(key).cloned()
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let mut entries = self.entries();
+        entries.order.retain(|stored| stored != key);
+        entries.order.push_back(key.to_string());
+        entries.responses.insert(key.to_string(), response);
+
+        while self
+            .max_entries
+            .is_some_and(|max| entries.responses.len() > max)
+        {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            entries.responses.remove(&oldest);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let mut entries = self.entries();
+        entries.order.retain(|stored| stored != key);
+        entries.responses.remove(key);
+    }
+}
+
+impl DiskCache {
+    /// Stores responses in `dir`, which is created when the first response is written.
+    ///
+    /// Failing to read or write a file is treated as a cache miss, never as a request error.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DiskCache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The metadata and body files of `key`.
+    fn paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        let name = format!("{:016x}", fnv1a(key.as_bytes()));
+        (
+            self.dir.join(format!("{name}.json")),
+            self.dir.join(format!("{name}.body")),
+        )
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let (meta, body) = self.paths(key);
+        let mut response: CachedResponse = serde_json::from_slice(&fs::read(meta).ok()?).ok()?;
+        // a different key that hashed to the same file
+        if response.key != key {
+            return None;
+        }
+        response.body = fs::read(body).ok()?.into();
+        Some(response)
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let (meta, body) = self.paths(key);
+        let Ok(json) = serde_json::to_vec(&response) else {
+            return;
+        };
+        // the body goes first, so the metadata never points to a missing one
+        let _ = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&body, &response.body))
+            .and_then(|_| fs::write(&meta, json));
+    }
+
+    fn remove(&self, key: &str) {
+        let (meta, body) = self.paths(key);
+        let _ = fs::remove_file(meta);
+        let _ = fs::remove_file(body);
+    }
+}
+
+impl CachedResponse {
+    /// The stored response to a request, `None` if it may not or need not be stored.
+    pub(crate) fn new(
+        key: String,
+        request: &HeaderMap,
+        status: u16,
+        headers: &HeaderMap,
+        body: Bytes,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) -> Option<Self> {
+        if status < 200 || status == 206 || status == 304 {
+            return None;
+        }
+
+        let vary = header_values(headers, "vary");
+        if vary.iter().any(|name| name == "*") {
+            return None;
+        }
+
+        let response = CachedResponse {
+            key,
+            status,
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !NOT_STORED.contains(&name.as_str()))
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.as_str().to_string(), value)
+                })
+                .collect(),
+            vary: vary
+                .into_iter()
+                .map(|name| {
+                    let value = request_header(request, &name);
+                    (name, value)
+                })
+                .collect(),
+            request_time: millis(request_time),
+            response_time: millis(response_time),
+            body,
+        };
+
+        let directives = response.cache_control();
+        if has(&directives, "no-store") {
+            return None;
+        }
+        if request.contains_key(AUTHORIZATION) && !response.allows_credentials() {
+            return None;
+        }
+        let explicit = value(&directives, "max-age").is_some()
+            || response.header("expires").is_some()
+            || has(&directives, "public");
+        if !explicit && !HEURISTICALLY_CACHEABLE.contains(&status) {
+            return None;
+        }
+        // nothing to gain from a response that is stale right away and can't be revalidated
+        if response.freshness_lifetime().is_zero() && !response.has_validators() {
+            return None;
+        }
+        Some(response)
+    }
+
+    /// The first value of a stored header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether it can be served without revalidation.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        !has(&self.cache_control(), "no-cache") && self.freshness_lifetime() > self.age(now)
+    }
+
+    /// How long the response may be served without revalidation, RFC 9111 section 4.2.1.
+    pub fn freshness_lifetime(&self) -> Duration {
+        let directives = self.cache_control();
+        if let Some(max_age) = value(&directives, "max-age").and_then(|secs| secs.parse().ok()) {
+            return Duration::from_secs(max_age);
+        }
+
+        let date = self.date();
+        if let Some(expires) = self.header("expires") {
+            // an invalid date, like `0`, means already expired
+            return httpdate::parse_http_date(expires)
+                .ok()
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_default();
+        }
+
+        if HEURISTICALLY_CACHEABLE.contains(&self.status) {
+            if let Some(last_modified) = self
+                .header("last-modified")
+                .and_then(|value| httpdate::parse_http_date(value).ok())
+            {
+                let since = date.duration_since(last_modified).unwrap_or_default();
+                return (since / 10).min(MAX_HEURISTIC_FRESHNESS);
+            }
+        }
+        Duration::ZERO
+    }
+
+    /// How old the response is, RFC 9111 section 4.2.3.
+    pub fn age(&self, now: SystemTime) -> Duration {
+        let request_time = time(self.request_time);
+        let response_time = time(self.response_time);
+
+        let apparent_age = response_time
+            .duration_since(self.date())
+            .unwrap_or_default();
+        let response_delay = response_time
+            .duration_since(request_time)
+            .unwrap_or_default();
+        let age_value = self
+            .header("age")
+            .and_then(|age| age.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        let corrected_initial_age = apparent_age.max(age_value + response_delay);
+        let resident_time = now.duration_since(response_time).unwrap_or_default();
+        corrected_initial_age + resident_time
+    }
+
+    /// Whether the request sends the same values for the headers named by `Vary`,
+    /// and may be answered with this response if it carries `Authorization`.
+    pub(crate) fn matches(&self, request: &HeaderMap) -> bool {
+        (!request.contains_key(AUTHORIZATION) || self.allows_credentials())
+            && self
+                .vary
+                .iter()
+                .all(|(name, value)| request_header(request, name) == *value)
+    }
+
+    /// Adds `If-None-Match` / `If-Modified-Since`, unless the request is conditional already.
+    /// Returns whether the request now revalidates this response.
+    pub(crate) fn add_validators(&self, request: &mut HeaderMap) -> bool {
+        if request.contains_key(IF_NONE_MATCH) || request.contains_key(IF_MODIFIED_SINCE) {
+            return false;
+        }
+        let mut added = false;
+        for (name, header) in [
+            (IF_NONE_MATCH, self.header("etag")),
+            (IF_MODIFIED_SINCE, self.header("last-modified")),
+        ] {
+            if let Some(value) = header.and_then(|value| HeaderValue::from_str(value).ok()) {
+                request.insert(name, value);
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// Takes the headers of a `304 Not Modified` over, RFC 9111 section 4.3.4.
+    pub(crate) fn refresh(
+        &mut self,
+        not_modified: &HeaderMap,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) {
+        // the stored `Age` was relative to the old response time
+        self.headers.retain(|(key, _)| key != "age");
+        for name in not_modified.keys() {
+            if NOT_STORED.contains(&name.as_str()) || name == "content-length" {
+                continue;
+            }
+            self.headers.retain(|(key, _)| key != name.as_str());
+            for value in not_modified.get_all(name) {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                self.headers.push((name.as_str().to_string(), value));
+            }
+        }
+        self.request_time = millis(request_time);
+        self.response_time = millis(response_time);
+    }
+
+    /// The response to hand out, with its current `Age`.
+    pub(crate) fn to_response(&self, url: Url, now: SystemTime) -> reqwest::Response {
+        let mut response = http::Response::builder().status(self.status).url(url);
+        for (name, value) in &self.headers {
+            // the stored `Age` is the upstream one, replaced by the current age
+            if name != "age" {
+                response = response.header(name.as_str(), value.as_str());
+            }
+        }
+        response = response.header("age", self.age(now).as_secs().to_string());
+        response
+            .body(self.body.clone())
+            .unwrap_or_else(|_| http::Response::new(Bytes::new()))
+            .into()
+    }
+
+    /// Whether the response may be stored for and served to requests with `Authorization`,
+    /// RFC 9111 section 3.5.
+    fn allows_credentials(&self) -> bool {
+        let directives = self.cache_control();
+        has(&directives, "public")
+            || has(&directives, "must-revalidate")
+            || value(&directives, "s-maxage").is_some()
+    }
+
+    fn has_validators(&self) -> bool {
+        self.header("etag").is_some() || self.header("last-modified").is_some()
+    }
+
+    fn cache_control(&self) -> Vec<(String, Option<String>)> {
+        directives(
+            self.headers
+                .iter()
+                .filter(|(name, _)| name == "cache-control")
+                .map(|(_, value)| value.as_str()),
+        )
+    }
+
+    /// `Date`, or when the response arrived if it has none.
+    fn date(&self) -> SystemTime {
+        self.header("date")
+            .and_then(|date| httpdate::parse_http_date(date).ok())
+            .unwrap_or_else(|| time(self.response_time))
+    }
+}
+
+/// What responses to `method` on `url` are stored under.
+pub(crate) fn cache_key(method: &Method, url: &Url) -> String {
+    format!("{method} {url}")
+}
+
+/// The mode a request uses, `Cache-Control: no-store` / `no-cache` request headers
+/// tighten a default mode.
+pub(crate) fn request_mode(mode: CacheMode, request: &HeaderMap) -> CacheMode {
+    if mode != CacheMode::Default {
+        return mode;
+    }
+    let directives = directives(
+        request
+            .get_all("cache-control")
+            .iter()
+            .filter_map(|value| value.to_str().ok()),
+    );
+    let pragma_no_cache = header_values(request, "pragma").contains(&"no-cache".to_string());
+    if has(&directives, "no-store") {
+        CacheMode::NoStore
+    } else if has(&directives, "no-cache")
+        || value(&directives, "max-age") == Some("0")
+        || pragma_no_cache
+    {
+        CacheMode::NoCache
+    } else {
+        mode
+    }
+}
+
+/// `Cache-Control` directives, names lowercased and values unquoted.
+fn directives<'v>(values: impl Iterator<Item = &'v str>) -> Vec<(String, Option<String>)> {
+    values
+        .flat_map(|value| value.split(','))
+        .filter_map(|directive| {
+            let directive = directive.trim();
+            if directive.is_empty() {
+                return None;
+            }
+            Some(match directive.split_once('=') {
+                Some((name, value)) => (
+                    name.trim().to_ascii_lowercase(),
+                    Some(value.trim().trim_matches('"').to_string()),
+                ),
+                None => (directive.to_ascii_lowercase(), None),
+            })
+        })
+        .collect()
+}
+
+fn has(directives: &[(String, Option<String>)], name: &str) -> bool {
+    directives.iter().any(|(directive, _)| directive == name)
+}
+
+fn value<'d>(directives: &'d [(String, Option<String>)], name: &str) -> Option<&'d str> {
+    directives
+        .iter()
+        .find(|(directive, _)| directive == name)
+        .and_then(|(_, value)| value.as_deref())
+}
+
+/// The comma separated, lowercased values of a header.
+fn header_values(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// All values of a request header, joined like they would be on the wire.
+fn request_header(request: &HeaderMap, name: &str) -> Option<String> {
+    let values: Vec<&str> = request
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn time(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `DefaultHasher`, so file names survive upgrades.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
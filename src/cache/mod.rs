@@ -0,0 +1,103 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+#[allow(clippy::module_inception)]
+pub mod cache;
+
+/// `cache`, where responses to `GET` and `HEAD` requests are kept, following RFC 9111
+/// as a private (browser like) cache.
+///
+/// Fresh responses are served without a request, stale ones are revalidated with
+/// `If-None-Match` / `If-Modified-Since` and a `304` is answered from the store.
+/// Successful unsafe requests (`POST`, `PUT`, `DELETE`, ..) drop the stored responses for their url.
+/// ```rs
+/// let echo = Echo::configure(Some(RequestConfig {
+///     cache: Some(Arc::new(MemoryCache::new())),
+///     ..Default::default()
+/// }));
+///
+/// let countries = echo.get::<Vec<Country>>("/countries").await?;
+/// println!("{:?}", countries.cache); // Some(CacheStatus::Miss), then Some(CacheStatus::Hit)
+/// ```
+/// Implement it to keep responses anywhere else, e.g. in redis.
+pub trait CacheStore: std::fmt::Debug + Send + Sync {
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    fn put(&self, key: &str, response: CachedResponse);
+    fn remove(&self, key: &str);
+}
+
+/// Keeps responses in memory, optionally bounded to a number of entries.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    /// `None` for unbounded
+    max_entries: Option<usize>,
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    responses: HashMap<String, CachedResponse>,
+    /// keys from oldest to newest write, the oldest is evicted first
+    order: VecDeque<String>,
+}
+
+/// Keeps responses in a directory, two files per response: the metadata as JSON and the raw body.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+/// A stored response, with what's needed to tell whether it's still fresh.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// `METHOD url`, what the response was stored under
+    pub key: String,
+    pub status: u16,
+    /// lowercase names
+    pub headers: Vec<(String, String)>,
+    /// the request headers named by `Vary`, with the values they had
+    pub vary: Vec<(String, Option<String>)>,
+    /// when the request was sent, in milliseconds since the unix epoch
+    pub request_time: u64,
+    /// when the response arrived, in milliseconds since the unix epoch
+    pub response_time: u64,
+    /// kept out of the metadata, `DiskCache` writes it to a file of its own
+    #[serde(skip)]
+    pub body: Bytes,
+}
+
+/// How a request uses the cache, the `cache` option of `fetch()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// fresh responses are served, stale ones revalidated
+    #[default]
+    Default,
+    /// the cache is neither read nor written, also what a `Cache-Control: no-store` request header does
+    NoStore,
+    /// the cache is not read, the response is stored
+    Reload,
+    /// stored responses are always revalidated, also what a `Cache-Control: no-cache` request header does
+    NoCache,
+    /// any stored response is served, however stale, the network is only used when there is none
+    ForceCache,
+    /// any stored response is served, without one the response is a `504 Gateway Timeout`
+    OnlyIfCached,
+}
+
+/// Where a response came from, see `Response::cache`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheStatus {
+    /// served from the cache without a request
+    Hit,
+    /// came from the network
+    Miss,
+    /// the server answered `304 Not Modified`, the body came from the cache
+    Revalidated,
+    /// `CacheMode::NoStore`, the cache was skipped
+    Bypass,
+}
@@ -0,0 +1,56 @@
+use tokio_util::sync::CancellationToken;
+
+/// A handle used to abort in-flight requests, similar to axios' `AbortController`.
+///
+/// Clones share the same state, so a single token can be handed to many requests
+/// and cancelling it aborts all of them with `EchoError::Cancelled`.
+/// ```rs
+/// use echo_http::{echo, CancelToken, RequestConfig};
+///
+/// let token = CancelToken::new();
+///
+/// let mut config = RequestConfig::default();
+/// config.url = Some("https://jsonplaceholder.typicode.com/posts".to_string());
+/// config.cancel_token = Some(token.clone());
+///
+/// // somewhere else
+/// token.cancel();
+///
+/// let res = echo.request::<Vec<Post>>(config).await; // Err(EchoError::Cancelled)
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: CancellationToken,
+}
+
+impl CancelToken {
+    /// Creates a new, not yet cancelled, `CancelToken`.
+    pub fn new() -> Self {
+        CancelToken {
+            inner: CancellationToken::new(),
+        }
+    }
+
+    /// Cancels every request using this token (or one of its children).
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Returns `true` once `cancel()` has been called on this token or one of its parents.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Creates a child token. Cancelling the parent cancels the child,
+    /// cancelling the child leaves the parent untouched.
+    pub fn child_token(&self) -> Self {
+        CancelToken {
+            inner: self.inner.child_token(),
+        }
+    }
+
+    /// Resolves once the token has been cancelled.
+    pub async fn cancelled(&self) {
+        self.inner.cancelled().await
+    }
+}
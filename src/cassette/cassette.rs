@@ -0,0 +1,398 @@
+use super::{
+    Cassette, CassetteBody, CassetteRequest, CassetteResponse, CassetteState, Interaction,
+    MatchRules, RecordMode,
+};
+use crate::adapter::BoxFuture;
+use crate::redact::{is_sensitive, redact, REDACTED};
+use crate::{Adapter, EchoError, ReqwestAdapter};
+
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING};
+use reqwest::ResponseBuilderExt;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use url::{form_urlencoded, Url};
+
+impl Cassette {
+    /// Loads the cassette at `path`, requests are recorded with a default `ReqwestAdapter`.
+    ///
+    /// Fails if the file cannot be parsed, or with `ReplayOnly` if it does not exist.
+    pub fn open(path: impl Into<PathBuf>, mode: RecordMode) -> Result<Self, EchoError> {
+        let path = path.into();
+        let (interactions, recording) = match mode {
+            RecordMode::Passthrough => (Vec::new(), false),
+            _ if path.exists() => (read_interactions(&path)?, false),
+            RecordMode::RecordOnce => (Vec::new(), true),
+            RecordMode::ReplayOnly => {
+                return Err(cassette_error(&path, "the file does not exist"));
+            }
+        };
+
+        Ok(Cassette {
+            path,
+            mode,
+            adapter: Arc::new(ReqwestAdapter::default()),
+            rules: MatchRules::default(),
+            redact_headers: Vec::new(),
+            redact_request: None,
+            redact_response: None,
+            state: Mutex::new(CassetteState {
+                used: vec![false; interactions.len()],
+                interactions,
+                recording,
+            }),
+        })
+    }
+
+    /// Records and passes through requests with `adapter` instead.
+    pub fn with_adapter(mut self, adapter: Arc<dyn Adapter>) -> Self {
+        self.adapter = adapter;
+        self
+    }
+
+    pub fn match_on(mut self, rules: MatchRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Also writes this header as `[REDACTED]`, in requests and responses.
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redact_headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Runs on every request before it is written or matched, e.g. to blank out a signed url.
+    pub fn redact_request<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&mut CassetteRequest) + Send + Sync + 'static,
+    {
+        self.redact_request = Some(Arc::new(redact));
+        self
+    }
+
+    /// Runs on every response before it is written.
+    pub fn redact_response<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&mut CassetteResponse) + Send + Sync + 'static,
+    {
+        self.redact_response = Some(Arc::new(redact));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn mode(&self) -> RecordMode {
+        self.mode
+    }
+
+    /// Whether requests go out and get written, `record_once` on a new file.
+    pub fn is_recording(&self) -> bool {
+        self.state().recording
+    }
+
+    /// The recorded interactions, loaded and new.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state().interactions.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, CassetteState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn error(&self, message: impl fmt::Display) -> EchoError {
+        cassette_error(&self.path, message)
+    }
+
+    /// The first unused recording that matches, or the last matching one once all were used.
+    fn replay(&self, request: &CassetteRequest) -> Option<CassetteResponse> {
+        let mut state = self.state();
+        let matching: Vec<usize> = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| self.rules.matches(&interaction.request, request))
+            .map(|(at, _)| at)
+            .collect();
+        let at = matching
+            .iter()
+            .copied()
+            .find(|&at| !state.used[at])
+            .or_else(|| matching.last().copied())?;
+        state.used[at] = true;
+        Some(state.interactions[at].response.clone())
+    }
+
+    fn append(&self, interaction: Interaction) -> Result<(), EchoError> {
+        let line = serde_json::to_string(&interaction).map_err(|err| self.error(err))?;
+
+        let mut state = self.state();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| self.error(err))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| self.error(err))?;
+        writeln!(file, "{line}").map_err(|err| self.error(err))?;
+
+        state.interactions.push(interaction);
+        state.used.push(true);
+        Ok(())
+    }
+
+    fn record_request(&self, request: &reqwest::Request) -> CassetteRequest {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        let mut recorded = CassetteRequest {
+            method: request.method().to_string(),
+            url: redact_url(request.url()),
+            headers: self.header_list(request.headers()),
+            body: cassette_body(request.headers(), body),
+        };
+        if let Some(redact) = &self.redact_request {
+            redact(&mut recorded);
+        }
+        recorded
+    }
+
+    /// Reads the body of a live response, returning it rebuilt along with its recording.
+    async fn record_response(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<(reqwest::Response, CassetteResponse), EchoError> {
+        let status = response.status();
+        let version = response.version();
+        let url = response.url().clone();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        let mut recorded = CassetteResponse {
+            status: status.as_u16(),
+            headers: self.header_list(&headers),
+            body: cassette_body(&headers, &body),
+        };
+        if let Some(redact) = &self.redact_response {
+            redact(&mut recorded);
+        }
+
+        let mut rebuilt = http::Response::builder()
+            .status(status)
+            .version(version)
+            .url(url);
+        if let Some(rebuilt_headers) = rebuilt.headers_mut() {
+            *rebuilt_headers = headers;
+        }
+        let rebuilt = rebuilt.body(body).map_err(|err| self.error(err))?;
+        Ok((rebuilt.into(), recorded))
+    }
+
+    fn replay_response(
+        &self,
+        response: CassetteResponse,
+        url: Url,
+    ) -> Result<reqwest::Response, EchoError> {
+        let mut rebuilt = http::Response::builder().status(response.status).url(url);
+        for (name, value) in &response.headers {
+            rebuilt = rebuilt.header(name.as_str(), value.as_str());
+        }
+        let body = response.body.as_ref().map(CassetteBody::to_bytes);
+        let rebuilt = rebuilt
+            .body(body.unwrap_or_default())
+            .map_err(|err| self.error(err))?;
+        Ok(rebuilt.into())
+    }
+
+    /// Lowercase name and value pairs, secrets redacted and framing headers dropped.
+    fn header_list(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .filter(|(name, _)| **name != CONTENT_LENGTH && **name != TRANSFER_ENCODING)
+            .map(|(name, value)| {
+                let name = name.as_str();
+                let value = if is_sensitive(name) || self.redact_headers.iter().any(|h| h == name) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+}
+
+impl Adapter for Cassette {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+        Box::pin(async move {
+            if self.mode == RecordMode::Passthrough {
+                return self.adapter.send(request).await;
+            }
+
+            let recorded = self.record_request(&request);
+            if !self.is_recording() {
+                return match self.replay(&recorded) {
+                    Some(response) => self.replay_response(response, request.url().clone()),
+                    None => Err(self.error(format!(
+                        "no recording matches {} {}",
+                        recorded.method, recorded.url
+                    ))),
+                };
+            }
+
+            let response = self.adapter.send(request).await?;
+            let (response, recorded_response) = self.record_response(response).await?;
+            self.append(Interaction {
+                request: recorded,
+                response: recorded_response,
+            })?;
+            Ok(response)
+        })
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("mode", &self.mode)
+            .field("adapter", &self.adapter)
+            .field("rules", &self.rules)
+            .field("redact_headers", &self.redact_headers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            method: true,
+            url: true,
+            body: false,
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl MatchRules {
+    fn matches(&self, recorded: &CassetteRequest, request: &CassetteRequest) -> bool {
+        let header = |request: &CassetteRequest, name: &str| {
+            request
+                .headers
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+                .collect::<Vec<_>>()
+        };
+
+        (!self.method || recorded.method.eq_ignore_ascii_case(&request.method))
+            && (!self.url || recorded.url == request.url)
+            && (!self.body || recorded.body == request.body)
+            && self
+                .headers
+                .iter()
+                .all(|name| header(recorded, name) == header(request, name))
+    }
+}
+
+impl CassetteBody {
+    pub fn to_bytes(&self) -> Bytes {
+        match self {
+            CassetteBody::Json(value) => serde_json::to_vec(value).unwrap_or_default().into(),
+            CassetteBody::Text(text) => Bytes::copy_from_slice(text.as_bytes()),
+            CassetteBody::Bytes(bytes) => Bytes::copy_from_slice(bytes),
+        }
+    }
+}
+
+/// JSON bodies are kept as JSON with secret looking keys redacted,
+/// form bodies get the values of secret looking fields redacted.
+fn cassette_body(headers: &HeaderMap, body: &[u8]) -> Option<CassetteBody> {
+    if body.is_empty() {
+        return None;
+    }
+
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default();
+    if content_type.contains("json") {
+        if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+            redact(&mut value);
+            return Some(CassetteBody::Json(value));
+        }
+    }
+    if content_type.contains("x-www-form-urlencoded") {
+        let pairs = redact_pairs(form_urlencoded::parse(body));
+        let form = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+        return Some(CassetteBody::Text(form));
+    }
+
+    Some(match std::str::from_utf8(body) {
+        Ok(text) => CassetteBody::Text(text.to_string()),
+        Err(_) => CassetteBody::Bytes(body.to_vec()),
+    })
+}
+
+/// The url with the values of secret looking query params redacted.
+fn redact_url(url: &Url) -> String {
+    if !url.query_pairs().any(|(key, _)| is_sensitive(&key)) {
+        return url.to_string();
+    }
+
+    let pairs = redact_pairs(url.query_pairs());
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+/// `key=value` pairs, as in a query string or a form body, with secret looking values redacted.
+fn redact_pairs<'p>(
+    pairs: impl Iterator<Item = (Cow<'p, str>, Cow<'p, str>)>,
+) -> Vec<(String, String)> {
+    pairs
+        .map(|(key, value)| {
+            let value = if is_sensitive(&key) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (key.into_owned(), value)
+        })
+        .collect()
+}
+
+fn read_interactions(path: &Path) -> Result<Vec<Interaction>, EchoError> {
+    let contents = fs::read_to_string(path).map_err(|err| cassette_error(path, err))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(at, line)| {
+            serde_json::from_str(line)
+                .map_err(|err| cassette_error(path, format!("line {}: {err}", at + 1)))
+        })
+        .collect()
+}
+
+fn cassette_error(path: &Path, message: impl fmt::Display) -> EchoError {
+    EchoError::Cassette {
+        path: path.display().to_string(),
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Adapter;
+
+#[allow(clippy::module_inception)]
+pub mod cassette;
+
+/// An `Adapter` that records real exchanges to a JSON lines file and replays them later,
+/// VCR style, so suites can run without network access.
+/// ```rs
+/// let cassette = Cassette::open("tests/fixtures/users.jsonl", RecordMode::RecordOnce)?
+///     .match_on(MatchRules { body: true, ..Default::default() })
+///     .redact_header("x-request-id");
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     base_url: Some("https://jsonplaceholder.typicode.com".to_string()),
+///     adapter: Some(Arc::new(cassette)),
+///     ..Default::default()
+/// }));
+///
+/// // the first run hits the api and writes the file, later runs are served from it
+/// let user = echo.get::<User>("/users/1").await?;
+/// ```
+/// Secret looking headers, query params, form fields and JSON keys are written as `[REDACTED]`.
+/// Incoming requests are redacted the same way before matching, so they still find their recording.
+pub struct Cassette {
+    path: PathBuf,
+    mode: RecordMode,
+    /// sends the requests that are recorded or passed through
+    adapter: Arc<dyn Adapter>,
+    rules: MatchRules,
+    /// redacted on top of the secret looking ones
+    redact_headers: Vec<String>,
+    redact_request: Option<Arc<RedactRequestFn>>,
+    redact_response: Option<Arc<RedactResponseFn>>,
+    state: Mutex<CassetteState>,
+}
+
+type RedactRequestFn = dyn Fn(&mut CassetteRequest) + Send + Sync;
+type RedactResponseFn = dyn Fn(&mut CassetteResponse) + Send + Sync;
+
+struct CassetteState {
+    interactions: Vec<Interaction>,
+    /// whether each interaction was replayed already
+    used: Vec<bool>,
+    /// `record_once` on a cassette that did not exist yet
+    recording: bool,
+}
+
+/// What a `Cassette` does with requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    /// records every request if the file does not exist yet, otherwise behaves like `ReplayOnly`
+    RecordOnce,
+    /// answers from the file only, requests without a recording fail
+    ReplayOnly,
+    /// sends every request, the file is neither read nor written
+    Passthrough,
+}
+
+/// Which parts of a request have to be equal to its recording, after redaction.
+#[derive(Debug, Clone)]
+pub struct MatchRules {
+    /// `true` by default
+    pub method: bool,
+    /// the full url, params included, `true` by default
+    pub url: bool,
+    /// `false` by default
+    pub body: bool,
+    /// header names, none by default
+    pub headers: Vec<String>,
+}
+
+/// One recorded request and its response, a line in the cassette file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub url: String,
+    /// lowercase names, in the order they were sent
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<CassetteBody>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    /// lowercase names, without `content-length` and `transfer-encoding`
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<CassetteBody>,
+}
+
+/// A body as written to the cassette: JSON stays readable, anything else is kept as text or bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteBody {
+    Json(Value),
+    Text(String),
+    Bytes(Vec<u8>),
+}
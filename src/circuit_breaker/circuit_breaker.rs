@@ -0,0 +1,274 @@
+use super::{Circuit, CircuitBreaker, CircuitState};
+use crate::{Echo, EchoError};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, MutexGuard};
+use std::time::{Duration, Instant};
+use url::Url;
+
+impl CircuitBreaker {
+    /// Opens after 5 failures in a row, for 30 seconds, then lets a single probe through.
+    pub fn new() -> Self {
+        CircuitBreaker {
+            consecutive_failures: 5,
+            failure_rate: None,
+            cooldown: Duration::from_secs(30),
+            half_open_probes: 1,
+            per_base_url: false,
+            hooks: Vec::new(),
+            circuits: Default::default(),
+        }
+    }
+
+    pub fn consecutive_failures(mut self, failures: u32) -> Self {
+        self.consecutive_failures = failures.max(1);
+        self
+    }
+
+    /// Also opens once `rate` (0.0 to 1.0) of the last `window` requests failed.
+    pub fn failure_rate(mut self, rate: f64, window: usize) -> Self {
+        self.failure_rate = Some((rate, window.max(1)));
+        self
+    }
+
+    /// How long the circuit stays open before probing.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn half_open_probes(mut self, probes: u32) -> Self {
+        self.half_open_probes = probes.max(1);
+        self
+    }
+
+    /// One circuit per `base_url` instead of per host, requests without one still go by host.
+    pub fn per_base_url(mut self) -> Self {
+        self.per_base_url = true;
+        self
+    }
+
+    /// Called with the circuit's key, the old and the new state on every change, e.g. for alerting.
+    pub fn on_state_change<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// The state of `key`'s circuit, an open circuit whose cooldown is over reads as half-open.
+    pub fn state(&self, key: &str) -> CircuitState {
+        let circuits = self.circuits();
+        match circuits.get(key) {
+            Some(circuit) if circuit.cooldown_over(Instant::now()) => CircuitState::HalfOpen,
+            Some(circuit) => circuit.state,
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Closes `key`'s circuit and forgets its failures.
+    pub fn reset(&self, key: &str) {
+        let from = self.circuits().remove(key).map(|circuit| circuit.state);
+        if let Some(from) = from.filter(|from| *from != CircuitState::Closed) {
+            self.notify(key, from, CircuitState::Closed);
+        }
+    }
+
+    /// The circuit a request to `url` goes through, `base_url` is the one it was resolved against.
+    pub(crate) fn key(&self, base_url: Option<&str>, url: &Url) -> String {
+        match base_url {
+            Some(base_url) if self.per_base_url => base_url.to_string(),
+            _ => Echo::host_key(url),
+        }
+    }
+
+    /// Lets a request through `key`'s circuit, or fails it with `EchoError::CircuitOpen`.
+    pub(crate) fn enter(&self, key: &str) -> Result<CircuitCall<'_>, EchoError> {
+        let now = Instant::now();
+        let mut circuits = self.circuits();
+        let circuit = circuits.entry(key.to_string()).or_default();
+
+        let mut change = None;
+        if circuit.cooldown_over(now) {
+            change = circuit.set_state(CircuitState::HalfOpen);
+            circuit.open_until = None;
+            circuit.probes_in_flight = 0;
+            circuit.probe_successes = 0;
+        }
+
+        let result = match circuit.state {
+            CircuitState::Closed => Ok(false),
+            CircuitState::HalfOpen if circuit.probes_in_flight < self.half_open_probes => {
+                circuit.probes_in_flight += 1;
+                Ok(true)
+            }
+            _ => Err(EchoError::CircuitOpen {
+                key: key.to_string(),
+                retry_after: circuit
+                    .open_until
+                    .map(|until| until.saturating_duration_since(now))
+                    .unwrap_or_default(),
+            }),
+        };
+        drop(circuits);
+
+        if let Some((from, to)) = change {
+            self.notify(key, from, to);
+        }
+        result.map(|probe| CircuitCall {
+            breaker: self,
+            key: key.to_string(),
+            probe,
+            finished: false,
+        })
+    }
+
+    /// Counts the outcome of a request that went through `key`'s circuit.
+    fn record(&self, key: &str, probe: bool, failed: bool) {
+        let now = Instant::now();
+        let mut circuits = self.circuits();
+        let circuit = circuits.entry(key.to_string()).or_default();
+
+        let change = match circuit.state {
+            CircuitState::Closed if !probe => {
+                circuit.consecutive_failures = if failed {
+                    circuit.consecutive_failures + 1
+                } else {
+                    0
+                };
+                circuit.outcomes.push_back(failed);
+                let window = self.failure_rate.map_or(0, |(_, window)| window);
+                while circuit.outcomes.len() > window {
+                    circuit.outcomes.pop_front();
+                }
+
+                let too_many = circuit.consecutive_failures >= self.consecutive_failures;
+                let too_often = self.failure_rate.is_some_and(|(rate, window)| {
+                    let failures = circuit.outcomes.iter().filter(|failed| **failed).count();
+                    circuit.outcomes.len() >= window
+                        && failures as f64 >= rate * circuit.outcomes.len() as f64
+                });
+                (too_many || too_often)
+                    .then(|| self.open(circuit, now))
+                    .flatten()
+            }
+            CircuitState::HalfOpen if probe => {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+                if failed {
+                    self.open(circuit, now)
+                } else {
+                    circuit.probe_successes += 1;
+                    if circuit.probe_successes >= self.half_open_probes {
+                        *circuit = Circuit::default();
+                        Some((CircuitState::HalfOpen, CircuitState::Closed))
+                    } else {
+                        None
+                    }
+                }
+            }
+            // it was sent before the circuit changed, too late to count
+            _ => None,
+        };
+        drop(circuits);
+
+        if let Some((from, to)) = change {
+            self.notify(key, from, to);
+        }
+    }
+
+    /// Gives back the probe slot of a request that ended without an outcome, e.g. cancelled.
+    fn abandon(&self, key: &str) {
+        let mut circuits = self.circuits();
+        if let Some(circuit) = circuits.get_mut(key) {
+            if circuit.state == CircuitState::HalfOpen {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+            }
+        }
+    }
+
+    fn open(&self, circuit: &mut Circuit, now: Instant) -> Option<(CircuitState, CircuitState)> {
+        circuit.open_until = Some(now + self.cooldown);
+        circuit.consecutive_failures = 0;
+        circuit.outcomes.clear();
+        circuit.set_state(CircuitState::Open)
+    }
+
+    fn notify(&self, key: &str, from: CircuitState, to: CircuitState) {
+        for hook in &self.hooks {
+            hook(key, from, to);
+        }
+    }
+
+    fn circuits(&self) -> MutexGuard<'_, HashMap<String, Circuit>> {
+        self.circuits
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        CircuitBreaker::new()
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("consecutive_failures", &self.consecutive_failures)
+            .field("failure_rate", &self.failure_rate)
+            .field("cooldown", &self.cooldown)
+            .field("half_open_probes", &self.half_open_probes)
+            .field("per_base_url", &self.per_base_url)
+            .field("hooks", &self.hooks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Circuit {
+    fn cooldown_over(&self, now: Instant) -> bool {
+        self.state == CircuitState::Open && self.open_until.is_some_and(|until| now >= until)
+    }
+
+    fn set_state(&mut self, state: CircuitState) -> Option<(CircuitState, CircuitState)> {
+        let from = std::mem::replace(&mut self.state, state);
+        (from != state).then_some((from, state))
+    }
+}
+
+/// A request let through a circuit, its outcome is counted with `finish`.
+/// Dropped without one, e.g. when cancelled, it doesn't count either way.
+#[derive(Debug)]
+pub(crate) struct CircuitCall<'b> {
+    breaker: &'b CircuitBreaker,
+    key: String,
+    probe: bool,
+    finished: bool,
+}
+
+impl CircuitCall<'_> {
+    /// Counts a response, `5xx` statuses are failures.
+    pub(crate) fn response(mut self, status: u16) {
+        self.finished = true;
+        self.breaker.record(&self.key, self.probe, status >= 500);
+    }
+
+    /// Counts an error, connection failures and timeouts are failures, anything else
+    /// (the client side rate limit, a bad request body, ..) doesn't count.
+    pub(crate) fn error(mut self, err: &EchoError) {
+        if err.is_connection_failure() {
+            self.finished = true;
+            self.breaker.record(&self.key, self.probe, true);
+        }
+    }
+}
+
+impl Drop for CircuitCall<'_> {
+    fn drop(&mut self) {
+        if !self.finished && self.probe {
+            self.breaker.abandon(&self.key);
+        }
+    }
+}
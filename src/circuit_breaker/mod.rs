@@ -0,0 +1,68 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+#[allow(clippy::module_inception)]
+pub mod circuit_breaker;
+
+type StateChangeFn = dyn Fn(&str, CircuitState, CircuitState) + Send + Sync;
+
+/// `circuit_breaker`, stops sending requests to an upstream that keeps failing.
+///
+/// Connection failures, timeouts and `5xx` responses count as failures. Too many of them
+/// in a row, or too high a share of the recent requests, open the circuit: requests fail
+/// right away with `EchoError::CircuitOpen` for the `cooldown`. After that a few probe requests
+/// go through (half-open), if they all succeed the circuit closes again, one failure reopens it.
+/// Circuits are per host, or per `base_url` (the one picked from `base_urls`) after `per_base_url()`.
+/// Only requests that go out count, responses served by the `cache` or shared by `single_flight` don't.
+/// ```rs
+/// let breaker = CircuitBreaker::new()
+///     .consecutive_failures(5)
+///     .failure_rate(0.5, 20)
+///     .cooldown(Duration::from_secs(10))
+///     .on_state_change(|host, from, to| alert!("{host}: {from:?} -> {to:?}"));
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     circuit_breaker: Some(Arc::new(breaker)),
+///     ..Default::default()
+/// }));
+/// ```
+pub struct CircuitBreaker {
+    /// failures in a row that open the circuit
+    consecutive_failures: u32,
+    /// the share of failures among the last `window` requests that opens the circuit
+    failure_rate: Option<(f64, usize)>,
+    cooldown: Duration,
+    /// probe requests let through while half-open, all of them have to succeed
+    half_open_probes: u32,
+    per_base_url: bool,
+    hooks: Vec<Arc<StateChangeFn>>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+/// Where a circuit stands, see `CircuitBreaker::state`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// requests go through
+    #[default]
+    Closed,
+    /// requests fail with `EchoError::CircuitOpen`
+    Open,
+    /// a few probe requests go through, the others fail
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// `true` for a failure, the most recent last
+    outcomes: VecDeque<bool>,
+    /// when an open circuit lets probes through
+    open_until: Option<Instant>,
+    probes_in_flight: u32,
+    probe_successes: u32,
+}
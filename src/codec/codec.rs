@@ -0,0 +1,234 @@
+use super::{Codec, CodecError, JsonCodec, Visit};
+use crate::RequestConfig;
+
+use erased_serde::Deserializer;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+#[cfg(feature = "cbor")]
+use super::CborCodec;
+#[cfg(feature = "msgpack")]
+use super::MsgPackCodec;
+#[cfg(feature = "xml")]
+use super::XmlCodec;
+#[cfg(feature = "yaml")]
+use super::YamlCodec;
+
+/// Every codec compiled in, tried in order against a response's `Content-Type`.
+static CODECS: &[&dyn Codec] = &[
+    &JsonCodec,
+    #[cfg(feature = "msgpack")]
+    &MsgPackCodec,
+    #[cfg(feature = "cbor")]
+    &CborCodec,
+    #[cfg(feature = "yaml")]
+    &YamlCodec,
+    #[cfg(feature = "xml")]
+    &XmlCodec { root: None },
+];
+
+/// The codec that encodes request bodies, `JsonCodec` unless the config says otherwise.
+pub(crate) fn request_codec<'c>(config: &'c RequestConfig<'_>) -> &'c dyn Codec {
+    match &config.codec {
+        Some(codec) => codec.as_ref(),
+        None => &JsonCodec,
+    }
+}
+
+/// Picks the codec for a response: the configured one or any built-in one that accepts
+/// its `Content-Type`, otherwise the configured one anyway.
+pub(crate) fn response_codec<'c>(
+    config: &'c RequestConfig<'_>,
+    headers: &HeaderMap,
+) -> &'c dyn Codec {
+    let codec = request_codec(config);
+    let media_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase());
+
+    let Some(media_type) = media_type else {
+        return codec;
+    };
+    if codec.accepts(&media_type) {
+        return codec;
+    }
+    CODECS
+        .iter()
+        .copied()
+        .find(|builtin| builtin.accepts(&media_type))
+        .unwrap_or(codec)
+}
+
+impl Codec for JsonCodec {
+    fn content_type(&self) -> &str {
+        "application/json"
+    }
+
+    /// `application/json`, `text/json` and any `+json` type such as `application/problem+json`
+    fn accepts(&self, media_type: &str) -> bool {
+        matches!(media_type, "application/json" | "text/json") || media_type.ends_with("+json")
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        let mut deserializer = serde_json::Deserializer::from_slice(body);
+        visit(&mut <dyn Deserializer>::erase(&mut deserializer))?;
+        // trailing characters after the document
+        deserializer.end()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Codec for MsgPackCodec {
+    fn content_type(&self) -> &str {
+        "application/msgpack"
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        matches!(
+            media_type,
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack"
+        )
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        Ok(rmp_serde::to_vec_named(value)?)
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        let mut deserializer = rmp_serde::Deserializer::from_read_ref(body);
+        visit(&mut <dyn Deserializer>::erase(&mut deserializer))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    fn content_type(&self) -> &str {
+        "application/cbor"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        let mut body = Vec::new();
+        ciborium::into_writer(value, &mut body)?;
+        Ok(body)
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        // ciborium only hands its deserializer to `DeserializeOwned` types,
+        // so `CborVisit` picks `visit` up from the thread it is decoded on
+        let _slot = CborVisitSlot::fill(visit);
+        ciborium::from_reader::<CborVisit, _>(body)?;
+        Ok(())
+    }
+}
+
+/// The `visit` of the `CborCodec::decode` running on this thread.
+#[cfg(feature = "cbor")]
+type CborVisitFn = dyn for<'a> FnMut(&mut dyn Deserializer<'a>) -> Result<(), erased_serde::Error>;
+
+#[cfg(feature = "cbor")]
+thread_local! {
+    static CBOR_VISIT: std::cell::Cell<Option<*mut CborVisitFn>> = const { std::cell::Cell::new(None) };
+}
+
+/// Keeps `visit` in `CBOR_VISIT` while ciborium decodes, restoring the previous one on drop.
+#[cfg(feature = "cbor")]
+struct CborVisitSlot(Option<*mut CborVisitFn>);
+
+#[cfg(feature = "cbor")]
+impl CborVisitSlot {
+    fn fill(visit: Visit<'_>) -> Self {
+        let visit: *mut (dyn for<'a> FnMut(&mut dyn Deserializer<'a>) -> Result<(), erased_serde::Error>
+             + '_) = visit;
+        // SAFETY: only the lifetime is erased, the slot is emptied again before `visit` goes out of scope
+        let visit: *mut CborVisitFn = unsafe { std::mem::transmute(visit) };
+        CborVisitSlot(CBOR_VISIT.with(|slot| slot.replace(Some(visit))))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl Drop for CborVisitSlot {
+    fn drop(&mut self) {
+        CBOR_VISIT.with(|slot| slot.set(self.0));
+    }
+}
+
+/// Hands ciborium's own deserializer to the pending `visit`, so byte strings
+/// and non-string map keys reach `T` as they are.
+#[cfg(feature = "cbor")]
+struct CborVisit;
+
+#[cfg(feature = "cbor")]
+impl<'de> serde::Deserialize<'de> for CborVisit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let visit = CBOR_VISIT
+            .with(|slot| slot.take())
+            .ok_or_else(|| D::Error::custom("CBOR body decoded outside of CborCodec::decode"))?;
+        // SAFETY: the pointer came from the `CborVisitSlot` of the `decode` call still on the stack,
+        // and taking it out of the slot makes this the only use of it
+        let visit = unsafe { &mut *visit };
+        visit(&mut <dyn Deserializer>::erase(deserializer)).map_err(D::Error::custom)?;
+        Ok(CborVisit)
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Codec for YamlCodec {
+    fn content_type(&self) -> &str {
+        "application/yaml"
+    }
+
+    fn accepts(&self, media_type: &str) -> bool {
+        matches!(
+            media_type,
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml"
+        )
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_yaml::to_string(value)?.into_bytes())
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        let deserializer = serde_yaml::Deserializer::from_slice(body);
+        visit(&mut <dyn Deserializer>::erase(deserializer))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "xml")]
+impl Codec for XmlCodec {
+    fn content_type(&self) -> &str {
+        "application/xml"
+    }
+
+    /// `application/xml`, `text/xml` and any `+xml` type
+    fn accepts(&self, media_type: &str) -> bool {
+        matches!(media_type, "application/xml" | "text/xml") || media_type.ends_with("+xml")
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        let body = match &self.root {
+            Some(root) => quick_xml::se::to_string_with_root(root, value)?,
+            None => quick_xml::se::to_string(value)?,
+        };
+        Ok(body.into_bytes())
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        let mut deserializer = quick_xml::de::Deserializer::from_str(std::str::from_utf8(body)?);
+        visit(&mut <dyn Deserializer>::erase(&mut deserializer))?;
+        Ok(())
+    }
+}
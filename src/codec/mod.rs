@@ -0,0 +1,81 @@
+#[allow(clippy::module_inception)]
+pub mod codec;
+
+/// Re-exported so custom codecs can name the types in `Codec`'s signatures.
+pub use erased_serde;
+
+/// Whatever went wrong inside a codec.
+pub type CodecError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Receives the deserializer a codec built over a response body, see `Codec::decode`.
+pub type Visit<'v> =
+    &'v mut dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> Result<(), erased_serde::Error>;
+
+/// Turns request data into a body and response bodies back into data.
+///
+/// The codec set on the config encodes the request body and is sent as both
+/// `Content-Type` and `Accept`. Responses are decoded by whichever codec matches
+/// their `Content-Type`, falling back to the configured one (JSON by default).
+/// ```rs
+/// #[derive(Debug)]
+/// struct Csv;
+///
+/// impl Codec for Csv {
+///     fn content_type(&self) -> &str {
+///         "text/csv"
+///     }
+///
+///     fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+///         ..
+///     }
+///
+///     fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+///         let mut deserializer = csv::Deserializer::from_reader(body);
+///         visit(&mut <dyn erased_serde::Deserializer>::erase(&mut deserializer))?;
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Codec: std::fmt::Debug + Send + Sync {
+    /// the media type written by `encode`, e.g. `application/msgpack`
+    fn content_type(&self) -> &str;
+
+    /// Whether a response with this media type (parameters like `charset` stripped,
+    /// lowercased) can be decoded. Defaults to an exact match on `content_type()`.
+    fn accepts(&self, media_type: &str) -> bool {
+        media_type.eq_ignore_ascii_case(self.content_type())
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError>;
+
+    /// Builds a deserializer over `body` and hands it to `visit`, exactly once.
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError>;
+}
+
+/// `application/json`, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+/// `application/msgpack`, maps are written with field names.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPackCodec;
+
+/// `application/cbor`
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborCodec;
+
+/// `application/yaml`
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlCodec;
+
+/// `application/xml`
+#[cfg(feature = "xml")]
+#[derive(Debug, Clone, Default)]
+pub struct XmlCodec {
+    /// `root` names the root element of request bodies,
+    /// structs use their own name when it is not set
+    pub root: Option<String>,
+}
@@ -0,0 +1,113 @@
+use super::{ApiErrorResponse, EchoError, ErrorData, Problem};
+
+use reqwest::header::HeaderMap;
+
+impl<E> ErrorData for E
+where
+    E: std::any::Any + std::fmt::Debug + Send + Sync,
+{
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl ApiErrorResponse {
+    pub(crate) fn new<E>(
+        status: u16,
+        status_text: String,
+        headers: HeaderMap,
+        url: String,
+        data: E,
+    ) -> Self
+    where
+        E: ErrorData,
+    {
+        ApiErrorResponse {
+            status,
+            status_text,
+            headers,
+            url,
+            data: Box::new(data),
+        }
+    }
+
+    /// The decoded error body, `None` when `E` is not the type it was decoded into.
+    pub fn data<E>(&self) -> Option<&E>
+    where
+        E: 'static,
+    {
+        (*self.data).as_any().downcast_ref::<E>()
+    }
+
+    /// Takes the decoded error body, handing `self` back when `E` is not the type it was decoded into.
+    /// ```rs
+    /// if let EchoError::Api(api) = err.into_kind() {
+    ///     let problem: Problem = api.into_data::<Problem>().unwrap();
+    /// }
+    /// ```
+    pub fn into_data<E>(self: Box<Self>) -> Result<E, Box<Self>>
+    where
+        E: 'static,
+    {
+        if (*self.data).as_any().is::<E>() {
+            let data = self
+                .data
+                .into_any()
+                .downcast::<E>()
+                .expect("type was checked");
+            Ok(*data)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl std::fmt::Debug for ApiErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiErrorResponse")
+            .field("status", &self.status)
+            .field("status_text", &self.status_text)
+            .field("headers", &self.headers)
+            .field("url", &self.url)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for ApiErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request failed with status {} {}: {:?}",
+            self.status, self.status_text, self.data
+        )
+    }
+}
+
+impl Problem {
+    pub(crate) fn default_type() -> String {
+        "about:blank".to_string()
+    }
+}
+
+impl EchoError {
+    /// The failing response of a `get_with_error::<T, E>` style request.
+    pub fn api_error(&self) -> Option<&ApiErrorResponse> {
+        match self.kind() {
+            EchoError::Api(api) => Some(api),
+            _ => None,
+        }
+    }
+
+    /// Shortcut for `api_error()` followed by `data::<E>()`.
+    pub fn api_data<E>(&self) -> Option<&E>
+    where
+        E: 'static,
+    {
+        self.api_error().and_then(|api| api.data::<E>())
+    }
+}
@@ -0,0 +1,181 @@
+use super::{DecodeError, EchoError};
+use crate::codec::{Codec, CodecError};
+use crate::redact::{redact, redact_text};
+
+use bytes::Bytes;
+use serde_json::Value;
+
+/// Longest excerpt kept on a `DecodeError`, in characters.
+const EXCERPT_LEN: usize = 512;
+
+impl DecodeError {
+    pub(crate) fn new(
+        path: String,
+        source: CodecError,
+        type_name: &'static str,
+        status: u16,
+        url: &str,
+        body: Bytes,
+    ) -> Self {
+        DecodeError {
+            path,
+            type_name,
+            status,
+            url: url.to_string(),
+            excerpt: excerpt(&body),
+            source,
+            body,
+        }
+    }
+
+    /// The raw, untouched response body.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// The raw body as (lossy) utf-8 text.
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to deserialize {} at `{}` from {} {}: {}, body: {}",
+            self.type_name, self.path, self.status, self.url, self.source, self.excerpt
+        )
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+impl From<DecodeError> for EchoError {
+    fn from(err: DecodeError) -> Self {
+        EchoError::DecodeError(Box::new(err))
+    }
+}
+
+/// Deserializes a body with `codec`, keeping track of the path so failures can point at the broken field.
+pub(crate) fn decode_body<T>(
+    codec: &dyn Codec,
+    body: Bytes,
+    status: u16,
+    url: &str,
+) -> Result<T, EchoError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut data = None;
+    let mut path = None;
+    let result = codec.decode(
+        &body,
+        &mut |deserializer| match serde_path_to_error::deserialize(deserializer) {
+            Ok(decoded) => {
+                data = Some(decoded);
+                Ok(())
+            }
+            Err(err) => {
+                path = Some(json_path(err.path()));
+                Err(err.into_inner())
+            }
+        },
+    );
+
+    match (result, data) {
+        (Ok(()), Some(data)) => Ok(data),
+        (result, _) => {
+            // codec errors outside of `T`, e.g. trailing characters, are reported at the root
+            let source = result
+                .err()
+                .unwrap_or_else(|| "the codec did not decode anything".into());
+            let path = path.unwrap_or_else(|| "$".to_string());
+            Err(
+                DecodeError::new(path, source, std::any::type_name::<T>(), status, url, body)
+                    .into(),
+            )
+        }
+    }
+}
+
+/// Deserializes an already parsed JSON value, `root` is the JSON pointer it was taken from.
+pub(crate) fn decode_value<T>(
+    value: Value,
+    root: &str,
+    status: u16,
+    url: &str,
+) -> Result<T, EchoError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    // keep the bytes around for the excerpt, the value is consumed while deserializing
+    let body = Bytes::from(value.to_string());
+
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let path = join_path(&pointer_path(root), &json_path(err.path()));
+        DecodeError::new(
+            path,
+            err.into_inner().into(),
+            std::any::type_name::<T>(),
+            status,
+            url,
+            body,
+        )
+        .into()
+    })
+}
+
+/// `/data/0/id` => `$.data[0].id`
+fn pointer_path(pointer: &str) -> String {
+    let mut path = "$".to_string();
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        if !token.is_empty() && token.bytes().all(|byte| byte.is_ascii_digit()) {
+            path.push_str(&format!("[{}]", token));
+        } else {
+            path.push_str(&format!(".{}", token));
+        }
+    }
+    path
+}
+
+/// `$.data` + `$[0].id` => `$.data[0].id`
+fn join_path(root: &str, path: &str) -> String {
+    format!("{}{}", root, path.trim_start_matches('$'))
+}
+
+/// `data[0].id` => `$.data[0].id`
+fn json_path(path: &serde_path_to_error::Path) -> String {
+    match path.to_string().as_str() {
+        "." => "$".to_string(),
+        path if path.starts_with('[') => format!("${}", path),
+        path => format!("$.{}", path),
+    }
+}
+
+/// The start of `body`, secrets redacted. Binary bodies, e.g. MessagePack or CBOR,
+/// can't be redacted reliably and are left out.
+fn excerpt(body: &[u8]) -> String {
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        Err(_) => match std::str::from_utf8(body) {
+            Ok(text) if !text.contains(|c: char| c.is_control() && !c.is_whitespace()) => {
+                redact_text(text)
+            }
+            _ => return format!("({} bytes of binary data, not shown)", body.len()),
+        },
+    };
+
+    match text.char_indices().nth(EXCERPT_LEN) {
+        Some((end, _)) => format!("{}... ({} bytes total)", &text[..end], body.len()),
+        None => text,
+    }
+}
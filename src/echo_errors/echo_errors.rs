@@ -0,0 +1,259 @@
+use super::{DecodeError, EchoError, ErrorCode, ErrorContext, ErrorResponse, ErrorSummary};
+
+use std::error::Error as _;
+use std::io::ErrorKind;
+
+impl ErrorCode {
+    /// The axios / node style code, e.g. `"ERR_NETWORK"` or `"ETIMEDOUT"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Network => "ERR_NETWORK",
+            ErrorCode::ConnectionAborted => "ECONNABORTED",
+            ErrorCode::ConnectionReset => "ECONNRESET",
+            ErrorCode::ConnectionRefused => "ECONNREFUSED",
+            ErrorCode::HostNotFound => "ENOTFOUND",
+            ErrorCode::Tls => "ERR_TLS",
+            ErrorCode::TimedOut => "ETIMEDOUT",
+            ErrorCode::BadResponse => "ERR_BAD_RESPONSE",
+            ErrorCode::BadRequest => "ERR_BAD_REQUEST",
+            ErrorCode::Canceled => "ERR_CANCELED",
+            ErrorCode::TooManyRedirects => "ERR_FR_TOO_MANY_REDIRECTS",
+            ErrorCode::InvalidUrl => "ERR_INVALID_URL",
+            ErrorCode::BadOptionValue => "ERR_BAD_OPTION_VALUE",
+            ErrorCode::NotSupported => "ERR_NOT_SUPPORT",
+            ErrorCode::RateLimited => "ERR_RATE_LIMITED",
+            ErrorCode::CircuitOpen => "ERR_CIRCUIT_OPEN",
+        }
+    }
+
+    /// Connection level failures that are usually worth retrying.
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::Network
+                | ErrorCode::ConnectionAborted
+                | ErrorCode::ConnectionReset
+                | ErrorCode::ConnectionRefused
+                | ErrorCode::TimedOut
+                | ErrorCode::RateLimited
+                | ErrorCode::CircuitOpen
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&reqwest::Response> for ErrorResponse {
+    fn from(response: &reqwest::Response) -> Self {
+        ErrorResponse {
+            status: response.status().as_u16(),
+            status_text: response
+                .status()
+                .canonical_reason()
+                .unwrap_or("")
+                .to_string(),
+            headers: response.headers().clone(),
+        }
+    }
+}
+
+impl EchoError {
+    /// The error without its request context.
+    /// ```rs
+    /// match echo.get::<User>("/users/1").await {
+    ///     Err(err) if matches!(err.kind(), EchoError::Cancelled) => { .. }
+    ///     ..
+    /// }
+    /// ```
+    pub fn kind(&self) -> &EchoError {
+        match self {
+            EchoError::Request { source, .. } => source.kind(),
+            other => other,
+        }
+    }
+
+    /// Like `kind()`, but takes the error apart, dropping its request context.
+    pub fn into_kind(self) -> EchoError {
+        match self {
+            EchoError::Request { source, .. } => source.into_kind(),
+            other => other,
+        }
+    }
+
+    /// Method, url, attempt and response of the failed request, if the error came from one.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            EchoError::Request { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The response, when the server answered before things went wrong.
+    pub fn response(&self) -> Option<&ErrorResponse> {
+        self.context().and_then(|context| context.response.as_ref())
+    }
+
+    /// Status code of the response, if there was one.
+    pub fn status(&self) -> Option<u16> {
+        let status = match self.kind() {
+            EchoError::HttpError(err) => err.status().map(|status| status.as_u16()),
+            EchoError::Api(api) => Some(api.status),
+            EchoError::Status(status) => Some(*status),
+            _ => None,
+        };
+        status.or_else(|| self.response().map(|response| response.status))
+    }
+
+    /// Details of a body that could not be deserialized, including the raw bytes.
+    pub fn decode_error(&self) -> Option<&DecodeError> {
+        match self.kind() {
+            EchoError::DecodeError(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// axios style code describing what went wrong.
+    pub fn code(&self) -> ErrorCode {
+        match self.kind() {
+            EchoError::HttpError(err) => classify_reqwest_error(err),
+            EchoError::JsonError(_)
+            | EchoError::DecodeError(_)
+            | EchoError::TextParseError(_)
+            | EchoError::BinaryParseError(_) => ErrorCode::BadResponse,
+            EchoError::UnsupportedResponseType(_) => ErrorCode::NotSupported,
+            EchoError::EncodeError { .. } | EchoError::RequestTransformError(_) => {
+                ErrorCode::BadRequest
+            }
+            EchoError::ResponseTransformError(_) => ErrorCode::BadResponse,
+            EchoError::UrlError { .. } => ErrorCode::InvalidUrl,
+            EchoError::InvalidParams(_) => ErrorCode::BadOptionValue,
+            EchoError::Cancelled => ErrorCode::Canceled,
+            EchoError::Network { code, .. } => *code,
+            EchoError::Cassette { .. } => ErrorCode::NotSupported,
+            EchoError::RateLimited { .. } => ErrorCode::RateLimited,
+            EchoError::QueueTimeout(_) => ErrorCode::TimedOut,
+            EchoError::CircuitOpen { .. } => ErrorCode::CircuitOpen,
+            EchoError::TooManyRedirects(_) => ErrorCode::TooManyRedirects,
+            EchoError::Api(api) if api.status < 500 => ErrorCode::BadRequest,
+            EchoError::Api(_) => ErrorCode::BadResponse,
+            EchoError::Status(status) if *status < 500 => ErrorCode::BadRequest,
+            EchoError::Status(_) => ErrorCode::BadResponse,
+            EchoError::Request { .. } => unreachable!("kind() never returns a Request"),
+        }
+    }
+
+    /// Whether sending the same request again could succeed:
+    /// connection failures, timeouts, the client side rate limit, an open circuit and `408`, `425`, `429`, `500`, `502`, `503`, `504` responses.
+    pub fn is_retryable(&self) -> bool {
+        if let Some(status) = self.status() {
+            return matches!(status, 408 | 425 | 429 | 500 | 502 | 503 | 504);
+        }
+        self.code().is_transient()
+    }
+
+    /// Whether the request never got a response because of the upstream: it couldn't connect,
+    /// the connection broke or it timed out. Waiting in the `queue` for too long doesn't count.
+    pub(crate) fn is_connection_failure(&self) -> bool {
+        let failed = matches!(
+            self.code(),
+            ErrorCode::Network
+                | ErrorCode::ConnectionAborted
+                | ErrorCode::ConnectionReset
+                | ErrorCode::ConnectionRefused
+                | ErrorCode::HostNotFound
+                | ErrorCode::Tls
+                | ErrorCode::TimedOut
+        );
+        failed && !matches!(self.kind(), EchoError::QueueTimeout(_))
+    }
+
+    /// A serializable summary, e.g. for structured logging.
+    /// ```rs
+    /// if let Err(err) = echo.get::<User>("/users/1").await {
+    ///     log::error!("{}", serde_json::to_string(&err.summary())?);
+    /// }
+    /// ```
+    pub fn summary(&self) -> ErrorSummary {
+        let context = self.context();
+        ErrorSummary {
+            code: self.code(),
+            message: self.kind().to_string(),
+            method: context.map(|context| context.method.to_string()),
+            url: context.map(|context| context.url.clone()),
+            attempt: context.map(|context| context.attempt),
+            status: self.status(),
+            retryable: self.is_retryable(),
+        }
+    }
+
+    /// Attaches the request context, errors that already carry one are left untouched.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            EchoError::Request { .. } => self,
+            source => EchoError::Request {
+                context: Box::new(context),
+                source: Box::new(source),
+            },
+        }
+    }
+}
+
+/// Tells DNS, TLS, refused and reset connections apart by walking the source chain.
+fn classify_reqwest_error(err: &reqwest::Error) -> ErrorCode {
+    if err.is_timeout() {
+        return ErrorCode::TimedOut;
+    }
+    if err.is_redirect() {
+        return ErrorCode::TooManyRedirects;
+    }
+    if err.is_builder() {
+        return ErrorCode::BadRequest;
+    }
+    if let Some(status) = err.status() {
+        return if status.is_client_error() {
+            ErrorCode::BadRequest
+        } else {
+            ErrorCode::BadResponse
+        };
+    }
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                ErrorKind::TimedOut => return ErrorCode::TimedOut,
+                ErrorKind::ConnectionRefused => return ErrorCode::ConnectionRefused,
+                ErrorKind::ConnectionReset | ErrorKind::BrokenPipe => {
+                    return ErrorCode::ConnectionReset
+                }
+                ErrorKind::ConnectionAborted | ErrorKind::UnexpectedEof => {
+                    return ErrorCode::ConnectionAborted
+                }
+                _ => {}
+            }
+        }
+
+        let message = cause.to_string().to_lowercase();
+        if message.contains("dns error") || message.contains("failed to lookup address") {
+            return ErrorCode::HostNotFound;
+        }
+        if message.contains("certificate") || message.contains("tls") || message.contains("ssl") {
+            return ErrorCode::Tls;
+        }
+        if message.contains("connection closed before message completed") {
+            return ErrorCode::ConnectionReset;
+        }
+
+        source = cause.source();
+    }
+
+    if err.is_body() || err.is_decode() {
+        ErrorCode::BadResponse
+    } else {
+        ErrorCode::Network
+    }
+}
@@ -1,7 +1,16 @@
+use crate::codec::CodecError;
 use crate::request_config::ResponseType;
+use crate::transform::TransformError;
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
 
-// pub mod echo_errors;
+pub mod api_error;
+pub mod decode_error;
+#[allow(clippy::module_inception)]
+pub mod echo_errors;
 
 #[derive(Error, Debug)]
 pub enum EchoError {
@@ -11,15 +20,263 @@ pub enum EchoError {
     #[error("Failed to deserialize JSON response: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("{0}")]
+    DecodeError(Box<DecodeError>),
+
     #[error("Failed to parse text response: {0}")]
     TextParseError(String),
 
     #[error("Failed to parse binary response: {0}")]
     BinaryParseError(String),
 
+    #[error("Request transform failed: {0}")]
+    RequestTransformError(TransformError),
+
+    #[error("Response transform failed: {0}")]
+    ResponseTransformError(TransformError),
+
     #[error("Unsupported response type: {0:?}")]
     UnsupportedResponseType(ResponseType),
 
-    #[error("URL construction failed")]
-    UrlError,
+    #[error("URL construction failed for `{url}`: {reason}")]
+    UrlError { url: String, reason: UrlErrorReason },
+
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error("Request was cancelled")]
+    Cancelled,
+
+    /// A connection level failure reported by an adapter other than reqwest,
+    /// e.g. `MockAdapter::network_error`.
+    #[error("Network error ({code}): {message}")]
+    Network { code: ErrorCode, message: String },
+
+    /// A cassette file could not be read or written, or has no recording for a request.
+    #[error("Cassette `{path}`: {message}")]
+    Cassette { path: String, message: String },
+
+    /// The `rate_limit` had no token left and was set to `fail_fast()`.
+    #[error("Rate limit for `{key}` reached, retry in {retry_after:?}")]
+    RateLimited { key: String, retry_after: Duration },
+
+    /// The request waited in the `queue` for longer than its `max_wait`.
+    #[error("Request waited {0:?} in the queue without being sent")]
+    QueueTimeout(Duration),
+
+    /// The `circuit_breaker` is open for the request's host, it wasn't sent.
+    #[error("Circuit for `{key}` is open, retry in {retry_after:?}")]
+    CircuitOpen { key: String, retry_after: Duration },
+
+    #[error("Failed to encode the request body as {content_type}: {source}")]
+    EncodeError {
+        content_type: String,
+        source: CodecError,
+    },
+
+    /// A non-2xx response turned into an error by `Response::error_for_status`.
+    #[error("Request failed with status code {0}")]
+    Status(u16),
+
+    #[error("Maximum number of redirects ({0}) exceeded")]
+    TooManyRedirects(usize),
+
+    /// A failing status whose body was decoded into the caller's error type,
+    /// see `Echo::get_with_error`.
+    #[error("{0}")]
+    Api(Box<ApiErrorResponse>),
+
+    /// Any of the errors above, raised while sending a request.
+    /// Use `kind()` to get to the underlying error and `context()` for the request details.
+    #[error("{} {} failed (attempt {}): {source}", context.method, context.url, context.attempt)]
+    Request {
+        context: Box<ErrorContext>,
+        source: Box<EchoError>,
+    },
+}
+
+/// A non-2xx response, with its body decoded into the error type `E` of `get_with_error::<T, E>`.
+///
+/// `EchoError` is not generic, use `data::<E>()` or `into_data::<E>()` to get the typed body back.
+pub struct ApiErrorResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: HeaderMap,
+    /// the url the response came from
+    pub url: String,
+    data: Box<dyn ErrorData>,
+}
+
+/// Implemented for every error body type, lets `ApiErrorResponse` stay debuggable.
+pub trait ErrorData: std::any::Any + std::fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn std::any::Any;
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any>;
+}
+
+/// RFC 7807 `application/problem+json` body, a ready made error type for `get_with_error`.
+/// ```rs
+/// let res = echo.get_with_error::<User, Problem>("/users/1").await;
+///
+/// if let Err(err) = res {
+///     if let Some(problem) = err.api_data::<Problem>() {
+///         println!("{}: {:?}", problem.title.as_deref().unwrap_or(""), problem.detail);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct Problem {
+    /// a URI identifying the problem type, `about:blank` when absent
+    #[serde(rename = "type", default = "Problem::default_type")]
+    pub problem_type: String,
+    /// short, human-readable summary of the problem type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// the status code set by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// explanation specific to this occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// a URI identifying this occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// any extension members
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A response body that could not be deserialized into the requested type.
+#[derive(Debug)]
+pub struct DecodeError {
+    /// where in the document deserializing failed, e.g. `$.data[0].id`
+    pub path: String,
+    /// the type that was requested, e.g. `Vec<my_crate::User>`
+    pub type_name: &'static str,
+    pub status: u16,
+    /// the url the response came from
+    pub url: String,
+    /// the start of the body, with secrets redacted, safe to log, binary bodies are left out
+    pub excerpt: String,
+    pub source: CodecError,
+    body: bytes::Bytes,
+}
+
+/// axios style error codes, see `EchoError::code`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ErrorCode {
+    /// the request could not be sent or the connection failed for an unknown reason
+    #[serde(rename = "ERR_NETWORK")]
+    Network,
+    /// the connection was aborted while the request was in flight
+    #[serde(rename = "ECONNABORTED")]
+    ConnectionAborted,
+    /// the connection was reset by the peer
+    #[serde(rename = "ECONNRESET")]
+    ConnectionReset,
+    /// nothing is listening on the remote address
+    #[serde(rename = "ECONNREFUSED")]
+    ConnectionRefused,
+    /// the host name could not be resolved
+    #[serde(rename = "ENOTFOUND")]
+    HostNotFound,
+    /// the TLS handshake or certificate validation failed
+    #[serde(rename = "ERR_TLS")]
+    Tls,
+    /// `timeout` elapsed before the request completed
+    #[serde(rename = "ETIMEDOUT")]
+    TimedOut,
+    /// the response could not be read or decoded, or the server answered with a 5xx
+    #[serde(rename = "ERR_BAD_RESPONSE")]
+    BadResponse,
+    /// the request could not be built, or the server answered with a 4xx
+    #[serde(rename = "ERR_BAD_REQUEST")]
+    BadRequest,
+    /// the request was cancelled with a `CancelToken`
+    #[serde(rename = "ERR_CANCELED")]
+    Canceled,
+    /// the redirect limit was reached
+    #[serde(rename = "ERR_FR_TOO_MANY_REDIRECTS")]
+    TooManyRedirects,
+    /// the url could not be built
+    #[serde(rename = "ERR_INVALID_URL")]
+    InvalidUrl,
+    /// an option in the config has an invalid value
+    #[serde(rename = "ERR_BAD_OPTION_VALUE")]
+    BadOptionValue,
+    /// the requested feature is not supported
+    #[serde(rename = "ERR_NOT_SUPPORT")]
+    NotSupported,
+    /// the client side `rate_limit` was reached
+    #[serde(rename = "ERR_RATE_LIMITED")]
+    RateLimited,
+    /// the `circuit_breaker` is open for the host
+    #[serde(rename = "ERR_CIRCUIT_OPEN")]
+    CircuitOpen,
+}
+
+/// What was being requested when an error happened.
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    pub method: Method,
+    /// full url of the request, `base_url` and params included
+    pub url: String,
+    /// 1 for the first try
+    pub attempt: u32,
+    /// the response, when the server answered
+    pub response: Option<ErrorResponse>,
+}
+
+/// The part of a response that is kept on errors.
+#[derive(Debug, Clone)]
+pub struct ErrorResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: HeaderMap,
+}
+
+/// A serializable summary of an `EchoError`, meant for logging.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorSummary {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub retryable: bool,
+}
+
+/// Why a url could not be built, carried by `EchoError::UrlError`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UrlErrorReason {
+    #[error("no url was given")]
+    MissingUrl,
+
+    #[error("no value for path params: {}", .0.join(", "))]
+    MissingPathParams(Vec<String>),
+
+    #[error("path params not used by the url: {}", .0.join(", "))]
+    UnusedPathParams(Vec<String>),
+
+    #[error("path param `{0}` must be a string, number or bool")]
+    InvalidPathParam(String),
+
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+
+    #[error("invalid base url: {0}")]
+    InvalidBaseUrl(String),
+
+    #[error("relative url without a base_url")]
+    RelativeUrlWithoutBase,
+
+    #[error("absolute urls are not allowed with a base_url when allow_absolute_urls is false")]
+    AbsoluteUrlNotAllowed,
+
+    #[error("unsupported scheme `{0}`, only http and https are supported")]
+    UnsupportedScheme(String),
 }
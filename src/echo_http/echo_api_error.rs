@@ -0,0 +1,79 @@
+use bytes::Bytes;
+
+use crate::codec::codec::response_codec;
+use crate::echo_errors::decode_error::decode_body;
+use crate::echo_errors::{ApiErrorResponse, ErrorData};
+use crate::echo_http::Exchange;
+use crate::{Echo, EchoError, RequestConfig, Response};
+
+impl<'a> Echo<'a> {
+    /// Like `parse_response`, but a non-2xx body is decoded into `E` and returned as `EchoError::Api`.
+    async fn parse_response_with_error<'r, T, E>(
+        config: &RequestConfig<'r>,
+        response: reqwest::Response,
+        url: &str,
+        exchange: Exchange,
+    ) -> Result<Response<'r, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        if response.status().is_success() {
+            return Self::parse_response(config, response, url, exchange).await;
+        }
+
+        let status = response.status().as_u16();
+        let status_text = response
+            .status()
+            .canonical_reason()
+            .unwrap_or("")
+            .to_string();
+        let mut headers = response.headers().clone();
+        let response_url = response.url().to_string();
+
+        let bytes = response.bytes().await?;
+        let bytes = Self::transform_response(config, bytes, &mut headers)?;
+        // an empty error body still has to decode, e.g. into `Option<E>` or `serde_json::Value`
+        let bytes = if bytes.is_empty() {
+            Bytes::from_static(b"null")
+        } else {
+            bytes
+        };
+        let codec = response_codec(config, &headers);
+        let data = decode_body::<E>(codec, bytes, status, &response_url)?;
+
+        Err(EchoError::Api(Box::new(ApiErrorResponse::new(
+            status,
+            status_text,
+            headers,
+            response_url,
+            data,
+        ))))
+    }
+
+    pub(crate) async fn send_request_with_error<'r, T, U, E>(
+        &self,
+        config: &RequestConfig<'r>,
+        dedupe_key: Option<&str>,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<T>,
+    ) -> Result<Response<'r, U>, EchoError>
+    where
+        T: serde::Serialize,
+        U: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.execute(
+            config,
+            dedupe_key,
+            method,
+            url,
+            body,
+            |response, exchange| {
+                Self::parse_response_with_error::<U, E>(config, response, url, exchange)
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,109 @@
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::{Echo, EchoError, RequestConfig, Response, Settled};
+
+impl<'a> Echo<'a> {
+    /// Sends every request, at most `concurrency` at a time, and returns the responses
+    /// in the order of `requests`. The first error is returned right away and the requests
+    /// still running are cancelled, its `context()` tells which url failed.
+    /// ```rs
+    /// let users = echo
+    ///     .all::<User, _>((1..=20).map(|id| RequestConfig {
+    ///         url: Some(format!("/users/{id}")),
+    ///         ..Default::default()
+    ///     }), 4)
+    ///     .await?;
+    /// ```
+    pub async fn all<'r, T, I>(
+        &'r self,
+        requests: I,
+        concurrency: usize,
+    ) -> Result<Vec<Response<'r, T>>, EchoError>
+    where
+        T: serde::de::DeserializeOwned + 'r,
+        I: IntoIterator<Item = RequestConfig<'r>>,
+        I::IntoIter: 'r,
+    {
+        let mut settled = std::pin::pin!(self.stream_all::<T, _>(requests, concurrency));
+        let mut responses = Vec::new();
+        while let Some(next) = settled.next().await {
+            responses.push((next.index, next.result?));
+        }
+        responses.sort_by_key(|(index, _)| *index);
+        Ok(responses
+            .into_iter()
+            .map(|(_, response)| response)
+            .collect())
+    }
+
+    /// Like `all`, but never stops early: every request gets a `Settled`, in the order of `requests`.
+    /// ```rs
+    /// for settled in echo.all_settled::<User, _>(requests, 4).await {
+    ///     if let Err(err) = settled.result {
+    ///         eprintln!("{} {} failed: {err}", settled.method, settled.url);
+    ///     }
+    /// }
+    /// ```
+    pub async fn all_settled<'r, T, I>(
+        &'r self,
+        requests: I,
+        concurrency: usize,
+    ) -> Vec<Settled<'r, T>>
+    where
+        T: serde::de::DeserializeOwned + 'r,
+        I: IntoIterator<Item = RequestConfig<'r>>,
+        I::IntoIter: 'r,
+    {
+        let mut settled: Vec<Settled<'r, T>> =
+            self.stream_all(requests, concurrency).collect().await;
+        settled.sort_by_key(|settled| settled.index);
+        settled
+    }
+
+    /// Sends every request, at most `concurrency` at a time, yielding each one as soon as it's done.
+    /// `Settled::index` tells which of `requests` it was.
+    /// ```rs
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut settled = std::pin::pin!(echo.stream_all::<User, _>(requests, 8));
+    /// while let Some(settled) = settled.next().await {
+    ///     println!("#{} {}: {:?}", settled.index, settled.url, settled.result.map(|res| res.status));
+    /// }
+    /// ```
+    pub fn stream_all<'r, T, I>(
+        &'r self,
+        requests: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = Settled<'r, T>> + 'r
+    where
+        T: serde::de::DeserializeOwned + 'r,
+        I: IntoIterator<Item = RequestConfig<'r>>,
+        I::IntoIter: 'r,
+    {
+        stream::iter(requests.into_iter().enumerate())
+            .map(move |(index, config)| self.settle(index, config))
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    /// Sends a single request of a batch with `request`, tagged with where it ended up.
+    async fn settle<'r, T>(&'r self, index: usize, config: RequestConfig<'r>) -> Settled<'r, T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let method = config.method.clone();
+        let result = self.request(config).await;
+        let url = match &result {
+            Ok(response) => response.request.clone(),
+            Err(err) => err
+                .context()
+                .map(|context| context.url.clone())
+                .unwrap_or_default(),
+        };
+        Settled {
+            index,
+            method,
+            url,
+            result,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, ResponseBuilderExt, StatusCode, Version};
+use std::time::SystemTime;
+use url::Url;
+
+use crate::cache::cache::{cache_key, request_mode};
+use crate::cache::{CacheMode, CacheStatus, CachedResponse};
+use crate::queue::queue::QueuePermit;
+use crate::{Adapter, Echo, EchoError, RequestConfig};
+
+impl<'a> Echo<'a> {
+    /// Sends `request` like `send_hedged`, going through the config's `cache` if there is one.
+    pub(crate) async fn send_cached<'c>(
+        config: &'c RequestConfig<'_>,
+        adapter: &dyn Adapter,
+        mut request: reqwest::Request,
+        base_url: Option<&str>,
+    ) -> Result<
+        (
+            reqwest::Response,
+            Option<CacheStatus>,
+            Option<QueuePermit<'c>>,
+        ),
+        EchoError,
+    > {
+        let Some(store) = config.cache.as_deref() else {
+            let (response, permit) = Self::send_hedged(config, adapter, request, base_url).await?;
+            return Ok((response, None, permit));
+        };
+
+        let method = request.method().clone();
+        let url = request.url().clone();
+        if !matches!(method, Method::GET | Method::HEAD) {
+            let (response, permit) = Self::send_hedged(config, adapter, request, base_url).await?;
+            // a successful unsafe request makes the stored responses outdated
+            if response.status().is_success() || response.status().is_redirection() {
+                store.remove(&cache_key(&Method::GET, &url));
+                store.remove(&cache_key(&Method::HEAD, &url));
+            }
+            return Ok((response, None, permit));
+        }
+
+        let mode = request_mode(config.cache_mode.unwrap_or_default(), request.headers());
+        if mode == CacheMode::NoStore {
+            let (response, permit) = Self::send_hedged(config, adapter, request, base_url).await?;
+            return Ok((response, Some(CacheStatus::Bypass), permit));
+        }
+
+        let key = cache_key(&method, &url);
+        let stored = store
+            .get(&key)
+            .filter(|stored| stored.matches(request.headers()));
+        if let Some(stored) = &stored {
+            let serve = match mode {
+                CacheMode::ForceCache | CacheMode::OnlyIfCached => true,
+                CacheMode::Default => stored.is_fresh(SystemTime::now()),
+                _ => false,
+            };
+            if serve {
+                let response = stored.to_response(url, SystemTime::now());
+                return Ok((response, Some(CacheStatus::Hit), None));
+            }
+        }
+        if mode == CacheMode::OnlyIfCached {
+            let response = rebuild(
+                StatusCode::GATEWAY_TIMEOUT,
+                Version::default(),
+                HeaderMap::new(),
+                url,
+                Bytes::new(),
+            );
+            return Ok((response, Some(CacheStatus::Miss), None));
+        }
+
+        let revalidating = match &stored {
+            Some(stored) if matches!(mode, CacheMode::Default | CacheMode::NoCache) => {
+                stored.add_validators(request.headers_mut())
+            }
+            _ => false,
+        };
+        let request_headers = request.headers().clone();
+        let request_time = SystemTime::now();
+        let (response, permit) = Self::send_hedged(config, adapter, request, base_url).await?;
+        let response_time = SystemTime::now();
+
+        if let (true, 304, Some(mut stored)) = (revalidating, response.status().as_u16(), stored) {
+            stored.refresh(response.headers(), request_time, response_time);
+            store.put(&key, stored.clone());
+            let response = stored.to_response(url, response_time);
+            return Ok((response, Some(CacheStatus::Revalidated), permit));
+        }
+
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let response_url = response.url().clone();
+        let body = response.bytes().await?;
+        if let Some(cached) = CachedResponse::new(
+            key,
+            &request_headers,
+            status.as_u16(),
+            &headers,
+            body.clone(),
+            request_time,
+            response_time,
+        ) {
+            store.put(&cached.key.clone(), cached);
+        }
+
+        // the body was read to store it, hand out a copy
+        let response = rebuild(status, version, headers, response_url, body);
+        Ok((response, Some(CacheStatus::Miss), permit))
+    }
+}
+
+pub(crate) fn rebuild(
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    url: Url,
+    body: Bytes,
+) -> reqwest::Response {
+    let mut response = http::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url);
+    if let Some(response_headers) = response.headers_mut() {
+        *response_headers = headers;
+    }
+    response
+        .body(body)
+        .expect("typed response parts are valid")
+        .into()
+}
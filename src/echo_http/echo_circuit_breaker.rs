@@ -0,0 +1,28 @@
+use crate::{Adapter, Echo, EchoError, RequestConfig};
+
+impl<'a> Echo<'a> {
+    /// Sends `request` like `send_limited`, through the config's `circuit_breaker` if there is one.
+    /// Only requests that go out are counted, `base_url` is the one the url was resolved against.
+    pub(crate) async fn send_guarded(
+        config: &RequestConfig<'_>,
+        adapter: &dyn Adapter,
+        request: reqwest::Request,
+        base_url: Option<&str>,
+    ) -> Result<reqwest::Response, EchoError> {
+        let Some(breaker) = config.circuit_breaker.as_deref() else {
+            return Self::send_limited(config, adapter, request).await;
+        };
+
+        let call = breaker.enter(&breaker.key(base_url, request.url()))?;
+        match Self::send_limited(config, adapter, request).await {
+            Ok(response) => {
+                call.response(response.status().as_u16());
+                Ok(response)
+            }
+            Err(err) => {
+                call.error(&err);
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::pin::pin;
+use std::time::Instant;
+use tokio::time::sleep;
+
+use crate::queue::queue::QueuePermit;
+use crate::{Adapter, Echo, EchoError, RequestConfig};
+
+/// A response and, for a hedged copy, the queue slot it took, held until its body is read.
+type Sent<'q> = Result<(reqwest::Response, Option<QueuePermit<'q>>), EchoError>;
+
+impl<'a> Echo<'a> {
+    /// Sends `request` like `send_guarded`, plus a copy of it when the config's `hedging`
+    /// finds it too slow. The first successful response wins, the other request is dropped.
+    /// The copy waits for a slot of its own in the config's `queue`, returned with its response.
+    pub(crate) async fn send_hedged<'c>(
+        config: &'c RequestConfig<'_>,
+        adapter: &dyn Adapter,
+        request: reqwest::Request,
+        base_url: Option<&str>,
+    ) -> Sent<'c> {
+        let hedging = config
+            .hedging
+            .as_deref()
+            .filter(|_| request.method().is_idempotent());
+        // bodies built from `data` are plain bytes, so this only fails for streams
+        let Some((hedging, copy)) =
+            hedging.and_then(|hedging| Some((hedging, request.try_clone()?)))
+        else {
+            let response = Self::send_guarded(config, adapter, request, base_url).await?;
+            return Ok((response, None));
+        };
+
+        let delay = hedging.start();
+        let sent = Instant::now();
+        let observe = |result: &Sent<'_>, since: Instant, won: bool| {
+            if result.is_ok() {
+                hedging.observe(since.elapsed(), won);
+            }
+        };
+
+        let mut primary = pin!(async {
+            let response = Self::send_guarded(config, adapter, request, base_url).await?;
+            Ok((response, None))
+        });
+        tokio::select! {
+            biased;
+            result = &mut primary => {
+                observe(&result, sent, false);
+                return result;
+            }
+            _ = sleep(delay) => {}
+        }
+        if !hedging.try_hedge() {
+            let result = primary.await;
+            observe(&result, sent, false);
+            return result;
+        }
+
+        let hedged = Instant::now();
+        let mut copy = pin!(async {
+            let permit = match &config.queue {
+                Some(queue) => {
+                    let host = Self::host_key(copy.url());
+                    let priority = config.priority.unwrap_or_default();
+                    Some(queue.enter(&host, priority).await?.0)
+                }
+                None => None,
+            };
+            let response = Self::send_guarded(config, adapter, copy, base_url).await?;
+            Ok((response, permit))
+        });
+        let (first, copy_first) = tokio::select! {
+            result = &mut primary => (result, false),
+            result = &mut copy => (result, true),
+        };
+        let (since, other_since) = if copy_first {
+            (hedged, sent)
+        } else {
+            (sent, hedged)
+        };
+        if is_success(&first) {
+            observe(&first, since, copy_first);
+            return first;
+        }
+
+        let second = if copy_first {
+            primary.await
+        } else {
+            copy.await
+        };
+        if is_success(&second) {
+            observe(&second, other_since, !copy_first);
+            return second;
+        }
+        observe(&first, since, false);
+        first
+    }
+}
+
+/// A response that settles the race, errors and `5xx` give the other request a chance.
+fn is_success(result: &Sent<'_>) -> bool {
+    matches!(result, Ok((response, _)) if !response.status().is_server_error())
+}
@@ -1,4 +1,11 @@
-use crate::{Echo, EchoError, Nope, RequestConfig, Response, ResponseUnknown};
+use reqwest::Method;
+use std::sync::Arc;
+
+use crate::adapter::ReqwestAdapter;
+use crate::echo_errors::{ErrorContext, ErrorData};
+use crate::{
+    Adapter, Echo, EchoError, Nope, RequestConfig, Response, ResponseUnknown, UrlErrorReason,
+};
 
 impl<'a> Echo<'a> {
     /// Create an Echo instance with the `configure()` method.
@@ -22,11 +29,21 @@ impl<'a> Echo<'a> {
     /// ```
     ///
     pub fn configure(config: Option<RequestConfig<'a>>) -> Self {
-        let config = config.unwrap_or_default();
+        let adapter = ReqwestAdapter::default();
+        let client = adapter.client().clone();
+        Self::with_adapter(config, Arc::new(adapter), client)
+    }
 
+    /// An instance that sends with `adapter` unless the config brings its own.
+    pub(crate) fn with_adapter(
+        config: Option<RequestConfig<'a>>,
+        adapter: Arc<dyn Adapter>,
+        client: reqwest::Client,
+    ) -> Self {
         Echo {
-            config,
-            client: reqwest::Client::new(),
+            config: config.unwrap_or_default(),
+            adapter,
+            client,
         }
     }
 
@@ -39,10 +56,9 @@ impl<'a> Echo<'a> {
     ///
     /// let response = echo.get_unknown("/users/1").await?;
     /// ```
-    pub async fn get_unknown(&self, url: &str) -> Result<ResponseUnknown, EchoError> {
-        let full_url = self.get_full_url(url);
-        let request = self.client.get(&full_url);
-        self.send_request_unknown(request, url, Nope).await
+    pub async fn get_unknown(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.send_request_unknown(&self.config, None, Method::GET, url, Nope)
+            .await
     }
 
     /// get request
@@ -50,13 +66,12 @@ impl<'a> Echo<'a> {
     /// let echo = Echo::configure(None);
     /// let res = echo.get::<T>("https://jsonplaceholder.typicode.com/").await?;
     /// ```
-    pub async fn get<T>(&self, url: &str) -> Result<Response<T>, EchoError>
+    pub async fn get<T>(&self, url: &str) -> Result<Response<'_, T>, EchoError>
     where
         T: serde::Serialize + serde::de::DeserializeOwned,
     {
-        let full_url = self.get_full_url(url);
-        let request = self.client.get(&full_url);
-        self.send_request(request, url, Nope).await
+        self.send_request(&self.config, None, Method::GET, url, Nope)
+            .await
     }
 
     /// post request
@@ -65,13 +80,12 @@ impl<'a> Echo<'a> {
     ///
     /// let res = echo.post::<User>("/users", Some(new_user)).await?;
     /// ```
-    pub async fn post<T>(&self, url: &str, data: Option<T>) -> Result<Response<T>, EchoError>
+    pub async fn post<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
     where
         T: serde::Serialize + serde::de::DeserializeOwned,
     {
-        let full_url = self.get_full_url(url);
-        let request = self.client.post(&full_url);
-        self.send_request(request, url, data).await
+        self.send_request(&self.config, None, Method::POST, url, data)
+            .await
     }
 
     /// post request with no data
@@ -81,10 +95,8 @@ impl<'a> Echo<'a> {
     ///
     /// post_no is used when you want to send a post request with no data
     /// ```
-    pub async fn post_no(&self, url: &str) -> Result<ResponseUnknown, EchoError> {
-        let full_url = self.get_full_url(url);
-        let request = self.client.post(&full_url);
-        self.send_request_unknown::<serde_json::Value>(request, url, None)
+    pub async fn post_no(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.send_request_unknown::<serde_json::Value>(&self.config, None, Method::POST, url, None)
             .await
     }
 
@@ -109,13 +121,24 @@ impl<'a> Echo<'a> {
     ///
     /// let put = echo.put::<Post>("https://jsonplaceholder.typicode.com/posts/1", Some(updated_post)).await?;
     /// ```
-    pub async fn put<T>(&self, url: &str, data: Option<T>) -> Result<Response<T>, EchoError>
+    pub async fn put<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
     where
         T: serde::Serialize + serde::de::DeserializeOwned,
     {
-        let full_url = self.get_full_url(url);
-        let request = self.client.put(&full_url);
-        self.send_request(request, url, data).await
+        self.send_request(&self.config, None, Method::PUT, url, data)
+            .await
+    }
+
+    /// patch request
+    /// ```rs
+    /// let patched = echo.patch::<Post>("/posts/1", Some(changes)).await?;
+    /// ```
+    pub async fn patch<T>(&self, url: &str, data: Option<T>) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.send_request(&self.config, None, Method::PATCH, url, data)
+            .await
     }
 
     /// delete request
@@ -124,9 +147,185 @@ impl<'a> Echo<'a> {
     /// let deleted = echo.delete("https://jsonplaceholder.typicode.com/posts/1").await?;
     /// ```
     /// `response.data` should return an empty object.
-    pub async fn delete(&self, url: &str) -> Result<ResponseUnknown, EchoError> {
-        let full_url = self.get_full_url(url);
-        let request = self.client.delete(&full_url);
-        self.send_request_unknown(request, url, Nope).await
+    pub async fn delete(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.send_request_unknown(&self.config, None, Method::DELETE, url, Nope)
+            .await
+    }
+
+    /// head request, only the status and headers are of interest
+    /// ```rs
+    /// let head = echo.head("/files/report.pdf").await?;
+    /// let size = head.headers.get("content-length");
+    /// ```
+    pub async fn head(&self, url: &str) -> Result<ResponseUnknown<'_>, EchoError> {
+        self.send_request_unknown(&self.config, None, Method::HEAD, url, Nope)
+            .await
+    }
+
+    /// request with a per-request config, like `axios.request(config)`
+    ///
+    /// the given config is layered on top of the instance config:
+    /// `url` and `method` are taken from it, headers and params are merged,
+    /// and any other option that is set wins over the instance value.
+    /// ```rs
+    /// let echo = Echo::configure(Some(config));
+    ///
+    /// let token = CancelToken::new();
+    ///
+    /// let res = echo
+    ///     .request::<Post>(RequestConfig {
+    ///         url: Some("/posts/1".to_string()),
+    ///         cancel_token: Some(token.clone()),
+    ///         ..Default::default()
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn request<'r, T>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<Response<'r, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let (config, url) = self.merge_request(config)?;
+        let method = config.method.clone();
+        let body = config.data.clone();
+        self.send_request(&config, config.dedupe_key.as_deref(), method, &url, body)
+            .await
+    }
+
+    /// same as `request`, but for an unknown response type
+    /// ```rs
+    /// let res = echo
+    ///     .request_unknown(RequestConfig {
+    ///         url: Some("/users/1".to_string()),
+    ///         ..Default::default()
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn request_unknown<'r>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<ResponseUnknown<'r>, EchoError> {
+        let (config, url) = self.merge_request(config)?;
+        let method = config.method.clone();
+        let body = config.data.clone();
+        self.send_request_unknown(&config, config.dedupe_key.as_deref(), method, &url, body)
+            .await
+    }
+
+    /// get request that decodes error responses too
+    ///
+    /// a 2xx body is decoded into `T`, any other status into `E` and returned
+    /// as `EchoError::Api`, use `err.api_data::<E>()` to get it back.
+    /// `Problem` can be used for RFC 7807 `application/problem+json` bodies.
+    /// ```rs
+    /// #[derive(Debug, Deserialize)]
+    /// struct ApiError {
+    ///     code: String,
+    ///     message: String,
+    /// }
+    ///
+    /// match echo.get_with_error::<User, ApiError>("/users/1").await {
+    ///     Ok(res) => println!("{:#?}", res.data),
+    ///     Err(err) => match err.api_data::<ApiError>() {
+    ///         Some(api_error) => println!("{}: {}", api_error.code, api_error.message),
+    ///         None => println!("{}", err),
+    ///     },
+    /// }
+    /// ```
+    pub async fn get_with_error<T, E>(&self, url: &str) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.send_request_with_error::<_, _, E>(&self.config, None, Method::GET, url, Nope)
+            .await
+    }
+
+    /// post request that decodes error responses into `E`, see `get_with_error`
+    pub async fn post_with_error<T, E>(
+        &self,
+        url: &str,
+        data: Option<T>,
+    ) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.send_request_with_error::<_, _, E>(&self.config, None, Method::POST, url, data)
+            .await
+    }
+
+    /// put request that decodes error responses into `E`, see `get_with_error`
+    pub async fn put_with_error<T, E>(
+        &self,
+        url: &str,
+        data: Option<T>,
+    ) -> Result<Response<'_, T>, EchoError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        self.send_request_with_error::<_, _, E>(&self.config, None, Method::PUT, url, data)
+            .await
+    }
+
+    /// `request` that decodes error responses into `E`, see `get_with_error`
+    pub async fn request_with_error<'r, T, E>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<Response<'r, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned + ErrorData,
+    {
+        let (config, url) = self.merge_request(config)?;
+        let method = config.method.clone();
+        let body = config.data.clone();
+        self.send_request_with_error::<_, _, E>(
+            &config,
+            config.dedupe_key.as_deref(),
+            method,
+            &url,
+            body,
+        )
+        .await
+    }
+
+    /// Merges a request's `config` over the instance's and picks its url.
+    /// Unused `path_params` are only an error when the request sets them itself,
+    /// the instance's are shared by every request.
+    pub(crate) fn merge_request<'r>(
+        &'r self,
+        config: RequestConfig<'r>,
+    ) -> Result<(RequestConfig<'r>, String), EchoError> {
+        let path_params = config.path_params.clone();
+        let config = self.config.merge(config);
+        let url = Self::config_url(&config)?;
+        Self::check_path_params(&url, path_params.as_ref()).map_err(|err| {
+            err.with_context(ErrorContext {
+                method: config.method.clone(),
+                url: url.clone(),
+                attempt: 1,
+                response: None,
+            })
+        })?;
+        Ok((config, url))
+    }
+
+    pub(crate) fn config_url(config: &RequestConfig) -> Result<String, EchoError> {
+        config.url.clone().ok_or_else(|| {
+            EchoError::UrlError {
+                url: String::new(),
+                reason: UrlErrorReason::MissingUrl,
+            }
+            .with_context(ErrorContext {
+                method: config.method.clone(),
+                url: String::new(),
+                attempt: 1,
+                response: None,
+            })
+        })
     }
 }
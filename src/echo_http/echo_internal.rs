@@ -1,26 +1,43 @@
-use crate::{request_config::ResponseType, Echo, EchoError, Response};
+use bytes::Bytes;
+use reqwest::header::{
+    HeaderMap, ACCEPT, AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE,
+    LOCATION, PROXY_AUTHORIZATION, WWW_AUTHENTICATE,
+};
+use reqwest::Method;
+use std::time::{Duration, Instant};
+use url::Url;
 
-impl<'a> Echo<'a> {
-    fn parse_url(url: &str) -> String {
-        let url = url.trim_start_matches("/").trim_end_matches("/");
+use crate::adapter::Adapter;
+use crate::base_urls::base_urls::Endpoint;
+use crate::codec::codec::{request_codec, response_codec};
+use crate::echo_errors::decode_error::decode_body;
+use crate::echo_errors::{ErrorContext, ErrorResponse};
+use crate::echo_http::echo_url::is_absolute_url;
+use crate::echo_http::Exchange;
+use crate::queue::queue::QueuePermit;
+use crate::request_config::ResponseType;
+use crate::{
+    CacheStatus, Echo, EchoError, Redirect, RequestConfig, RequestInfo, Response, Timings,
+};
 
-        url.to_string()
-    }
+/// Used when `max_redirects` is not set, same as reqwest's default policy.
+const DEFAULT_MAX_REDIRECTS: usize = 10;
 
-    pub(crate) fn get_full_url(&self, url: &str) -> String {
-        if let Some(base_url) = &self.config.base_url {
-            let parsed_endpoint = Self::parse_url(url);
-            format!("{}/{}", base_url, parsed_endpoint)
-        } else {
-            url.to_string()
-        }
-    }
+/// The last response of an attempt, its cache status, the redirects that led to it
+/// and the queue slot of a hedged copy that sent it.
+type SentAttempt<'q> = (
+    reqwest::Response,
+    Option<CacheStatus>,
+    Vec<Redirect>,
+    Option<QueuePermit<'q>>,
+);
 
+impl<'a> Echo<'a> {
     pub(crate) fn apply_headers(
-        &self,
+        config: &RequestConfig,
         mut request: reqwest::RequestBuilder,
     ) -> reqwest::RequestBuilder {
-        if let Some(headers) = &self.config.headers {
+        if let Some(headers) = &config.headers {
             let header_map: reqwest::header::HeaderMap = headers.clone().into();
             request = request.headers(header_map);
         }
@@ -28,149 +45,438 @@ impl<'a> Echo<'a> {
     }
 
     pub(crate) fn apply_timeout(
-        &self,
+        config: &RequestConfig,
         mut request: reqwest::RequestBuilder,
     ) -> reqwest::RequestBuilder {
-        if let Some(timeout) = self.config.timeout {
+        if let Some(timeout) = config.timeout {
             request = request.timeout(std::time::Duration::from_secs(timeout))
         }
         request
     }
 
-    pub(crate) fn apply_params(
-        &self,
+    /// Encodes `body` with the config's codec, setting `Content-Type` and,
+    /// for anything but JSON, `Accept`.
+    pub(crate) fn apply_body<T>(
+        config: &RequestConfig,
         mut request: reqwest::RequestBuilder,
-    ) -> reqwest::RequestBuilder {
-        if let Some(params) = &self.config.params {
-            request = request.query(params);
+        body: Option<T>,
+    ) -> Result<reqwest::RequestBuilder, EchoError>
+    where
+        T: serde::Serialize,
+    {
+        let codec = request_codec(config);
+        if config.codec.is_some() {
+            // responses can always fall back to JSON
+            request = request.header(
+                ACCEPT,
+                format!("{}, application/json;q=0.9", codec.content_type()),
+            );
         }
-        request
+
+        if let Some(body) = body {
+            let body = codec
+                .encode(&body)
+                .map_err(|source| EchoError::EncodeError {
+                    content_type: codec.content_type().to_string(),
+                    source,
+                })?;
+            request = request
+                .header(CONTENT_TYPE, codec.content_type())
+                .body(body);
+        }
+        Ok(request)
     }
 
-    pub(crate) fn apply_body<T>(
-        &self,
+    /// Applies everything from `config` onto the request builder.
+    /// The body goes first so headers set on the config win over the codec's.
+    pub(crate) fn build_request<T>(
+        config: &RequestConfig,
         mut request: reqwest::RequestBuilder,
         body: Option<T>,
-    ) -> reqwest::RequestBuilder
+    ) -> Result<reqwest::RequestBuilder, EchoError>
     where
         T: serde::Serialize,
     {
-        if let Some(body) = body {
-            request = request.json(&body);
+        request = Self::apply_body(config, request, body)?;
+        request = Self::apply_headers(config, request);
+        request = Self::apply_timeout(config, request);
+        Ok(request)
+    }
+
+    /// Races `future` against the config's `cancel_token`, if one is set.
+    /// Cancellation is checked first so an already cancelled token never sends anything.
+    pub(crate) async fn with_cancellation<F, R>(
+        config: &RequestConfig<'_>,
+        future: F,
+    ) -> Result<R, EchoError>
+    where
+        F: std::future::Future<Output = Result<R, EchoError>>,
+    {
+        match &config.cancel_token {
+            Some(token) => {
+                tokio::select! {
+                    biased;
+                    _ = token.cancelled() => Err(EchoError::Cancelled),
+                    result = future => result,
+                }
+            }
+            None => future.await,
         }
-        request
     }
 
-    async fn handle_response_type<T>(&self, response: reqwest::Response) -> Result<T, EchoError>
+    /// Reads the body, runs `transform_response` and decodes the result.
+    /// `headers` are the response's own, transforms may change them.
+    async fn handle_response_type<T>(
+        config: &RequestConfig<'_>,
+        response: reqwest::Response,
+        headers: &mut HeaderMap,
+    ) -> Result<T, EchoError>
     where
         T: serde::de::DeserializeOwned,
     {
-        match self.config.response_type {
-            ResponseType::Json => response.json::<T>().await.map_err(EchoError::from),
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+
+        let bytes = match config.response_type {
+            ResponseType::Json | ResponseType::ArrayBuffer => {
+                response.bytes().await.map_err(EchoError::from)?
+            }
             ResponseType::Text => {
                 let text = response.text().await.map_err(EchoError::from)?;
-                serde_json::from_str(&text).map_err(EchoError::from)
+                Bytes::from(text)
             }
-            ResponseType::ArrayBuffer => {
-                let bytes = response.bytes().await.map_err(EchoError::from)?;
-                serde_json::from_slice(&bytes).map_err(EchoError::from)
-            }
-            _ => Err(EchoError::UnsupportedResponseType(
-                self.config.response_type,
-            )),
+            _ => return Err(EchoError::UnsupportedResponseType(config.response_type)),
+        };
+        let bytes = Self::transform_response(config, bytes, headers)?;
+
+        decode_body(response_codec(config, headers), bytes, status, &url)
+    }
+
+    /// Everything about the response but its body, read before the body is consumed.
+    pub(crate) fn response_head<'r>(
+        config: &RequestConfig<'r>,
+        response: &reqwest::Response,
+        url: &str,
+        exchange: Exchange,
+    ) -> Result<Response<'r, ()>, EchoError> {
+        let request = Self::request_url(config, exchange.endpoint.as_deref(), url)?.to_string();
+        let mut config = RequestInfo::new(config);
+        if let Some(endpoint) = exchange.endpoint {
+            config.base_url = Some(endpoint);
         }
+        Ok(Response {
+            data: (),
+            status: response.status().as_u16(),
+            status_text: response
+                .status()
+                .canonical_reason()
+                .unwrap_or("")
+                .to_string(),
+            headers: response.headers().clone(),
+            config,
+            request,
+            url: response.url().to_string(),
+            redirects: exchange.redirects,
+            version: response.version(),
+            remote_addr: response.remote_addr(),
+            content_length: response.content_length(),
+            cache: exchange.cache,
+            attempt: exchange.attempt,
+            timings: Timings {
+                ttfb: exchange.ttfb,
+                queued: exchange.queued,
+                retries: exchange.retries,
+                ..Default::default()
+            },
+        })
     }
 
-    async fn parse_response<T>(
-        &self,
+    pub(crate) async fn parse_response<'r, T>(
+        config: &RequestConfig<'r>,
         response: reqwest::Response,
         url: &str,
-    ) -> Result<Response<T>, EchoError>
+        exchange: Exchange,
+    ) -> Result<Response<'r, T>, EchoError>
     where
         T: serde::de::DeserializeOwned,
     {
-        let status = response.status().as_u16();
-        let status_text = response
-            .status()
-            .canonical_reason()
-            .unwrap_or("")
-            .to_string();
-        let headers = response.headers().clone();
+        let started = exchange.started;
+        let mut head = Self::response_head(config, &response, url, exchange)?;
 
         // let data = if response.status().is_success() {
         //     response.json::<T>().await? // Deserialize directly to T
         // } else {
         //     panic!("Unexpected response body or error for URL: {}", url)
         // };
-        let data = self.handle_response_type::<T>(response).await?;
+        let data = Self::handle_response_type::<T>(config, response, &mut head.headers).await?;
 
-        Ok(Response {
-            data,
-            status,
-            status_text,
-            headers,
-            config: self.config.clone(),
-            request: self.get_full_url(url),
-        })
+        Ok(head.finish(data, started))
     }
 
-    pub(crate) async fn send_request<T, U>(
+    pub(crate) async fn send_request<'r, T, U>(
         &self,
-        mut request: reqwest::RequestBuilder,
+        config: &RequestConfig<'r>,
+        dedupe_key: Option<&str>,
+        method: reqwest::Method,
         url: &str,
         body: Option<T>,
-    ) -> Result<Response<U>, EchoError>
+    ) -> Result<Response<'r, U>, EchoError>
     where
         T: serde::Serialize,
         U: serde::de::DeserializeOwned,
     {
-        request = self.apply_headers(request);
-        request = self.apply_timeout(request);
-        request = self.apply_body(request, body);
-        request = self.apply_params(request);
-
-        let response = request.send().await?;
-        self.parse_response(response, url).await
+        self.execute(
+            config,
+            dedupe_key,
+            method,
+            url,
+            body,
+            |response, exchange| Self::parse_response(config, response, url, exchange),
+        )
+        .await
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::RequestConfig;
-
-    #[test]
-    fn test_parse_url() {
-        assert_eq!(Echo::parse_url("/endpoint/"), "endpoint");
-        assert_eq!(Echo::parse_url("endpoint/"), "endpoint");
-        assert_eq!(Echo::parse_url("/endpoint"), "endpoint");
-        assert_eq!(Echo::parse_url("endpoint"), "endpoint");
+    /// Builds and sends the request, then hands the response to `parse`.
+    /// `dedupe_key` comes from the per-request config, never the instance one.
+    /// Every error coming out of here carries an `ErrorContext`.
+    pub(crate) async fn execute<T, R, F, Fut>(
+        &self,
+        config: &RequestConfig<'_>,
+        dedupe_key: Option<&str>,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<T>,
+        parse: F,
+    ) -> Result<R, EchoError>
+    where
+        T: serde::Serialize,
+        F: FnOnce(reqwest::Response, Exchange) -> Fut,
+        Fut: std::future::Future<Output = Result<R, EchoError>>,
+    {
+        // absolute urls don't go to any of the `base_urls`
+        let base_urls = config
+            .base_urls
+            .as_deref()
+            .filter(|_| !is_absolute_url(url));
+        let mut endpoint = base_urls.and_then(|base_urls| base_urls.pick(&[]));
+
+        let full_url = match Self::request_url(config, endpoint.as_ref().map(Endpoint::url), url) {
+            Ok(full_url) => full_url,
+            Err(err) => {
+                return Err(err.with_context(ErrorContext {
+                    method,
+                    url: url.to_string(),
+                    attempt: 1,
+                    response: None,
+                }))
+            }
+        };
+
+        let context = |full_url: &Url, attempt, response| ErrorContext {
+            method: method.clone(),
+            url: full_url.to_string(),
+            attempt,
+            response,
+        };
+
+        let adapter = config.adapter.as_deref().unwrap_or(&*self.adapter);
+        let request = self.client.request(method.clone(), full_url.clone());
+        let request = match Self::build_request(config, request, body) {
+            Ok(request) => request,
+            Err(err) => return Err(err.with_context(context(&full_url, 1, None))),
+        };
+        let max_redirects = config.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS);
+
+        Self::with_cancellation(config, async {
+            let mut request = request.build()?;
+            Self::transform_request(config, &mut request)?;
+            let mut attempt = 1;
+            let mut tried = Vec::new();
+            let mut queued = Duration::ZERO;
+            let mut retries = Duration::ZERO;
+
+            let (full_url, response, cache, redirects, started, _permits) = loop {
+                let full_url = request.url().clone();
+                let base_url = endpoint
+                    .as_ref()
+                    .map(Endpoint::url)
+                    .or(config.base_url.as_deref());
+                // bodies built from `data` are plain bytes, so this only fails for streams
+                let retry = base_urls
+                    .filter(|_| method.is_idempotent())
+                    .and_then(|_| request.try_clone());
+                let attempt_started = Instant::now();
+                let mut waited = Duration::ZERO;
+
+                let result = async {
+                    // held until `parse` is done with the body
+                    let permit = match &config.queue {
+                        Some(queue) => {
+                            let host = Self::host_key(&full_url);
+                            let priority = config.priority.unwrap_or_default();
+                            let (permit, wait) = queue.enter(&host, priority).await?;
+                            waited = wait;
+                            Some(permit)
+                        }
+                        None => None,
+                    };
+                    let context = |response| context(&full_url, attempt, response);
+                    let sent = Self::send_attempt(
+                        config,
+                        dedupe_key,
+                        adapter,
+                        request,
+                        base_url,
+                        max_redirects,
+                        context,
+                    )
+                    .await?;
+                    Ok::<_, EchoError>((sent, permit))
+                }
+                .await;
+                queued += waited;
+
+                let err = match result {
+                    Ok(((response, cache, redirects, hedge_permit), permit)) => {
+                        let started = attempt_started + waited;
+                        let permits = (permit, hedge_permit);
+                        break (full_url, response, cache, redirects, started, permits);
+                    }
+                    Err(err) => err,
+                };
+                let (Some(base_urls), Some(failed)) = (base_urls, endpoint.take()) else {
+                    return Err(err.with_context(context(&full_url, attempt, None)));
+                };
+
+                // another base url is only worth a try when this one didn't get to answer
+                let failover = err.is_connection_failure()
+                    || matches!(err.kind(), EchoError::CircuitOpen { .. });
+                if err.is_connection_failure() {
+                    base_urls.eject(failed.index());
+                }
+                tried.push(failed.index());
+                drop(failed);
+
+                let next = retry
+                    .filter(|_| failover)
+                    .and_then(|retry| Some((retry, base_urls.pick(&tried)?)));
+                let Some((mut retry, next)) = next else {
+                    return Err(err.with_context(context(&full_url, attempt, None)));
+                };
+                *retry.url_mut() = match Self::request_url(config, Some(next.url()), url) {
+                    Ok(next_url) => next_url,
+                    Err(err) => return Err(err.with_context(context(&full_url, attempt, None))),
+                };
+
+                retries += attempt_started.elapsed().saturating_sub(waited);
+                attempt += 1;
+                endpoint = Some(next);
+                request = retry;
+            };
+
+            let exchange = Exchange {
+                started,
+                ttfb: started.elapsed(),
+                redirects,
+                cache,
+                queued,
+                retries,
+                endpoint: endpoint.as_ref().map(|endpoint| endpoint.url().to_string()),
+                attempt,
+            };
+            let snapshot = ErrorResponse::from(&response);
+            parse(response, exchange)
+                .await
+                .map_err(|err| err.with_context(context(&full_url, attempt, Some(snapshot))))
+        })
+        .await
+        .map_err(|err| err.with_context(context(&full_url, 1, None)))
     }
 
-    #[test]
-    fn test_get_full_url_with_base_url() {
-        let config = RequestConfig {
-            base_url: Some("https://api.example.com".to_string()),
-            ..Default::default()
+    /// Sends `request`, following redirects. `base_url` is the one it was resolved against.
+    /// The queue slot of a hedged copy that answered comes with the response.
+    async fn send_attempt<'c>(
+        config: &'c RequestConfig<'_>,
+        dedupe_key: Option<&str>,
+        adapter: &dyn Adapter,
+        mut request: reqwest::Request,
+        base_url: Option<&str>,
+        max_redirects: usize,
+        context: impl Fn(Option<ErrorResponse>) -> ErrorContext,
+    ) -> Result<SentAttempt<'c>, EchoError> {
+        let mut redirects = Vec::new();
+
+        let (response, cache, permit) = loop {
+            let retry = request.try_clone();
+            // a `dedupe_key` and the `base_url` only stand for the request as sent,
+            // not where it's redirected to
+            let first = redirects.is_empty();
+            let dedupe_key = dedupe_key.filter(|_| first);
+            let base_url = base_url.filter(|_| first);
+            let (response, cache, permit) =
+                Self::send_deduped(config, adapter, request, dedupe_key, base_url).await?;
+
+            let (Some(location), Some(retry)) = (Self::redirect_location(&response), retry) else {
+                break (response, cache, permit);
+            };
+            if max_redirects == 0 {
+                break (response, cache, permit);
+            }
+            if redirects.len() >= max_redirects {
+                let snapshot = ErrorResponse::from(&response);
+                return Err(EchoError::TooManyRedirects(max_redirects)
+                    .with_context(context(Some(snapshot))));
+            }
+
+            redirects.push(Redirect {
+                url: response.url().to_string(),
+                status: response.status().as_u16(),
+                location: location.to_string(),
+            });
+            request = Self::redirect_request(retry, response.status().as_u16(), location);
         };
-        let echo = Echo::configure(Some(config));
-
-        assert_eq!(
-            echo.get_full_url("/endpoint"),
-            "https://api.example.com/endpoint"
-        );
-        assert_eq!(
-            echo.get_full_url("endpoint/"),
-            "https://api.example.com/endpoint"
-        );
+
+        Ok((response, cache, redirects, permit))
     }
 
-    #[test]
-    fn test_get_full_url_without_base_url() {
-        let echo = Echo::configure(None);
-        assert_eq!(
-            echo.get_full_url("https://api.example.com/endpoint"),
-            "https://api.example.com/endpoint"
-        );
+    /// Where a `3xx` response points to, `None` for anything that isn't a followable redirect.
+    fn redirect_location(response: &reqwest::Response) -> Option<Url> {
+        if !matches!(response.status().as_u16(), 301 | 302 | 303 | 307 | 308) {
+            return None;
+        }
+        let location = response.headers().get(LOCATION)?.to_str().ok()?;
+        let location = response.url().join(location).ok()?;
+        matches!(location.scheme(), "http" | "https").then_some(location)
+    }
+
+    /// The request to send to `location`, following the same rules as browsers:
+    /// `303` (and `301` / `302` after a `POST`) turn into a `GET` without a body,
+    /// credentials are dropped when leaving the origin.
+    fn redirect_request(
+        mut request: reqwest::Request,
+        status: u16,
+        location: Url,
+    ) -> reqwest::Request {
+        let switch_to_get = match status {
+            303 => request.method() != Method::HEAD,
+            301 | 302 => request.method() == Method::POST,
+            _ => false,
+        };
+        if switch_to_get {
+            *request.method_mut() = Method::GET;
+            *request.body_mut() = None;
+            for header in [CONTENT_TYPE, CONTENT_LENGTH, CONTENT_ENCODING] {
+                request.headers_mut().remove(header);
+            }
+        }
+
+        if request.url().origin() != location.origin() {
+            for header in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE] {
+                request.headers_mut().remove(header);
+            }
+        }
+
+        *request.url_mut() = location;
+        request
     }
 }
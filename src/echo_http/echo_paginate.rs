@@ -0,0 +1,29 @@
+use crate::{Echo, PageStrategy, Paginator, RequestConfig};
+
+impl<'a> Echo<'a> {
+    /// Walks the pages of `config`'s url, `strategy` finds the next one in every response.
+    /// ```rs
+    /// use futures_util::StreamExt;
+    ///
+    /// let users: Vec<User> = echo
+    ///     .paginate(
+    ///         RequestConfig {
+    ///             url: Some("/users".to_string()),
+    ///             ..Default::default()
+    ///         },
+    ///         Cursor::new("/meta/next_cursor", "cursor"),
+    ///     )
+    ///     .items_at("/data")
+    ///     .max_pages(10)
+    ///     .items::<User>()
+    ///     .map(|user| user.expect("a page failed"))
+    ///     .collect()
+    ///     .await;
+    /// ```
+    pub fn paginate<'r, S>(&'r self, config: RequestConfig<'r>, strategy: S) -> Paginator<'r, 'a>
+    where
+        S: PageStrategy + 'r,
+    {
+        Paginator::new(self, config, Box::new(strategy))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{Adapter, Echo, EchoError, RequestConfig};
+
+use tokio::time::sleep;
+
+impl<'a> Echo<'a> {
+    /// Sends `request` with `adapter` once the config's `rate_limit` lets it through.
+    pub(crate) async fn send_limited(
+        config: &RequestConfig<'_>,
+        adapter: &dyn Adapter,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, EchoError> {
+        let Some(limiter) = config.rate_limit.as_deref() else {
+            return adapter.send(request).await;
+        };
+
+        let key = limiter.key(&request);
+        let reservation = limiter.acquire(&key)?;
+        if !reservation.wait.is_zero() {
+            sleep(reservation.wait).await;
+        }
+        reservation.spend();
+        let response = adapter.send(request).await?;
+        limiter.observe(&key, response.status().as_u16(), response.headers());
+        Ok(response)
+    }
+}
@@ -0,0 +1,78 @@
+use reqwest::Method;
+
+use crate::echo_http::echo_cache::rebuild;
+use crate::queue::queue::QueuePermit;
+use crate::single_flight::single_flight::Joined;
+use crate::single_flight::SharedResponse;
+use crate::{Adapter, CacheStatus, Echo, EchoError, RequestConfig};
+
+impl<'a> Echo<'a> {
+    /// Sends `request` like `send_cached`, unless an identical `GET` is already in flight
+    /// with the config's `single_flight`, then it gets a copy of that one's response.
+    /// `dedupe_key` replaces the computed key, redirects go by the computed one.
+    pub(crate) async fn send_deduped<'c>(
+        config: &'c RequestConfig<'_>,
+        adapter: &dyn Adapter,
+        request: reqwest::Request,
+        dedupe_key: Option<&str>,
+        base_url: Option<&str>,
+    ) -> Result<
+        (
+            reqwest::Response,
+            Option<CacheStatus>,
+            Option<QueuePermit<'c>>,
+        ),
+        EchoError,
+    > {
+        let single_flight = config
+            .single_flight
+            .as_deref()
+            .filter(|_| request.method() == Method::GET);
+        let Some(single_flight) = single_flight else {
+            return Self::send_cached(config, adapter, request, base_url).await;
+        };
+
+        let key = dedupe_key.map_or_else(|| single_flight.key(&request), String::from);
+        let leader = match single_flight.join(key) {
+            Joined::Leader(leader) => leader,
+            Joined::Follower(wait) => {
+                return match wait.await {
+                    Some(shared) => Ok((shared.to_response(), shared.cache, None)),
+                    // the leader failed or was cancelled, errors aren't shared
+                    None => Self::send_cached(config, adapter, request, base_url).await,
+                };
+            }
+        };
+
+        let (response, cache, permit) =
+            Self::send_cached(config, adapter, request, base_url).await?;
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let url = response.url().clone();
+        let body = response.bytes().await?;
+        let shared = SharedResponse {
+            status,
+            version,
+            headers,
+            url,
+            body,
+            cache,
+        };
+        let response = shared.to_response();
+        leader.finish(shared);
+        Ok((response, cache, permit))
+    }
+}
+
+impl SharedResponse {
+    fn to_response(&self) -> reqwest::Response {
+        rebuild(
+            self.status,
+            self.version,
+            self.headers.clone(),
+            self.url.clone(),
+            self.body.clone(),
+        )
+    }
+}
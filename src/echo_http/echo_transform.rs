@@ -0,0 +1,49 @@
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+
+use crate::{Echo, EchoError, RequestConfig};
+
+impl<'a> Echo<'a> {
+    /// Runs `transform_request` over the built request, in order.
+    pub(crate) fn transform_request(
+        config: &RequestConfig<'_>,
+        request: &mut reqwest::Request,
+    ) -> Result<(), EchoError> {
+        let Some(transforms) = &config.transform_request else {
+            return Ok(());
+        };
+
+        // bodies built from `data` are always plain bytes
+        let had_body = request.body().is_some();
+        let mut body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(Bytes::copy_from_slice)
+            .unwrap_or_default();
+
+        for transform in transforms {
+            body = transform
+                .apply(body, request.headers_mut())
+                .map_err(EchoError::RequestTransformError)?;
+        }
+
+        if had_body || !body.is_empty() {
+            *request.body_mut() = Some(body.into());
+        }
+        Ok(())
+    }
+
+    /// Runs `transform_response` over the raw body, in order.
+    pub(crate) fn transform_response(
+        config: &RequestConfig<'_>,
+        mut body: Bytes,
+        headers: &mut HeaderMap,
+    ) -> Result<Bytes, EchoError> {
+        for transform in config.transform_response.iter().flatten() {
+            body = transform
+                .apply(body, headers)
+                .map_err(EchoError::ResponseTransformError)?;
+        }
+        Ok(body)
+    }
+}
@@ -1,51 +1,56 @@
-use crate::{Echo, EchoError, Response, ResponseUnknown};
+use crate::codec::codec::response_codec;
+use crate::echo_errors::decode_error::decode_body;
+use crate::echo_http::Exchange;
+use crate::{Echo, EchoError, RequestConfig, ResponseUnknown};
 
 impl<'a> Echo<'a> {
-    async fn parse_response_unknown(
-        &self,
+    async fn parse_response_unknown<'r>(
+        config: &RequestConfig<'r>,
         response: reqwest::Response,
         url: &str,
-    ) -> Result<ResponseUnknown, EchoError> {
-        let status = response.status().as_u16();
-        let status_text = response
-            .status()
-            .canonical_reason()
-            .unwrap_or("")
-            .to_string();
-        let headers = response.headers().clone();
+        exchange: Exchange,
+    ) -> Result<ResponseUnknown<'r>, EchoError> {
+        let started = exchange.started;
+        let mut head = Self::response_head(config, &response, url, exchange)?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .unwrap_or_else(|_| serde_json::Value::Null);
+        let bytes = response.bytes().await?;
+        let bytes = Self::transform_response(config, bytes, &mut head.headers)?;
+        // nothing to parse, e.g. a `204 No Content`
+        let data = if bytes.iter().all(u8::is_ascii_whitespace) {
+            serde_json::Value::Null
+        } else {
+            decode_body(
+                response_codec(config, &head.headers),
+                bytes,
+                head.status,
+                &head.url,
+            )?
+        };
 
         Ok(ResponseUnknown {
-            inner: Response {
-                data,
-                status,
-                status_text,
-                headers,
-                config: self.config.clone(),
-                request: self.get_full_url(url),
-            },
+            inner: head.finish(data, started),
         })
     }
 
-    pub(crate) async fn send_request_unknown<T>(
+    pub(crate) async fn send_request_unknown<'r, T>(
         &self,
-        mut request: reqwest::RequestBuilder,
+        config: &RequestConfig<'r>,
+        dedupe_key: Option<&str>,
+        method: reqwest::Method,
         url: &str,
         body: Option<T>,
-    ) -> Result<ResponseUnknown, EchoError>
+    ) -> Result<ResponseUnknown<'r>, EchoError>
     where
         T: serde::Serialize,
     {
-        request = self.apply_headers(request);
-        request = self.apply_timeout(request);
-        request = self.apply_body(request, body);
-        request = self.apply_params(request);
-
-        let response = request.send().await?;
-        self.parse_response_unknown(response, url).await
+        self.execute(
+            config,
+            dedupe_key,
+            method,
+            url,
+            body,
+            |response, exchange| Self::parse_response_unknown(config, response, url, exchange),
+        )
+        .await
     }
 }
@@ -0,0 +1,484 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use url::Url;
+
+use crate::echo_errors::UrlErrorReason;
+use crate::params::{scalar_to_string, Params};
+use crate::{BaseUrls, Echo, EchoError, RequestConfig};
+
+/// Everything but the RFC 3986 unreserved characters, so a value always stays a single segment.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+impl<'a> Echo<'a> {
+    fn parse_url(url: &str) -> String {
+        let url = url.trim_start_matches("/").trim_end_matches("/");
+
+        url.to_string()
+    }
+
+    /// The url a request to `url` would be sent to, `base_url` included.
+    /// With `base_urls` that's the preferred one, see `BaseUrls::preferred`.
+    /// Falls back to `url` as is when it can't be resolved, requests fail with `EchoError::UrlError` instead.
+    pub fn get_full_url(&self, url: &str) -> String {
+        Self::resolve_url(&self.config, None, url).unwrap_or_else(|_| url.to_string())
+    }
+
+    /// The base url to resolve against: the `endpoint` picked from `base_urls`,
+    /// the preferred one of them without, `base_url` when there are none.
+    fn base_url<'c>(config: &'c RequestConfig, endpoint: Option<&'c str>) -> Option<&'c str> {
+        endpoint
+            .or_else(|| config.base_urls.as_deref().and_then(BaseUrls::preferred))
+            .or(config.base_url.as_deref())
+    }
+
+    /// Replaces every `{name}` placeholder in the path of `url` with the percent-encoded path param.
+    /// The query and fragment are left alone. A placeholder without a value is an error,
+    /// with or without `path_params`.
+    pub(crate) fn render_path(
+        url: &str,
+        path_params: Option<&Params>,
+    ) -> Result<String, EchoError> {
+        let url_error = |reason| EchoError::UrlError {
+            url: url.to_string(),
+            reason,
+        };
+
+        let (path, tail) = split_path(url);
+        let mut rendered = String::with_capacity(url.len());
+        let mut missing = Vec::new();
+        let mut rest = path;
+
+        for (start, name, end) in placeholders(path) {
+            rendered.push_str(&path[path.len() - rest.len()..start]);
+            match path_params.and_then(|params| params.params.get(name)) {
+                Some(value) => {
+                    let value = scalar_to_string(value).ok_or_else(|| {
+                        url_error(UrlErrorReason::InvalidPathParam(name.to_string()))
+                    })?;
+                    rendered.extend(utf8_percent_encode(&value, PATH_SEGMENT));
+                }
+                None => missing.push(name.to_string()),
+            }
+            rest = &path[end..];
+        }
+        rendered.push_str(rest);
+        rendered.push_str(tail);
+
+        if !missing.is_empty() {
+            return Err(url_error(UrlErrorReason::MissingPathParams(missing)));
+        }
+        Ok(rendered)
+    }
+
+    /// Fails with `UnusedPathParams` for `path_params` that have no placeholder in the path of `url`.
+    /// Only checked for the ones set on the request, the instance's are shared by every request.
+    pub(crate) fn check_path_params(
+        url: &str,
+        path_params: Option<&Params>,
+    ) -> Result<(), EchoError> {
+        let Some(path_params) = path_params else {
+            return Ok(());
+        };
+        let used: Vec<&str> = placeholders(split_path(url).0)
+            .map(|(_, name, _)| name)
+            .collect();
+        let unused: Vec<String> = path_params
+            .params
+            .keys()
+            .filter(|key| !used.contains(&key.as_str()))
+            .cloned()
+            .collect();
+
+        if unused.is_empty() {
+            return Ok(());
+        }
+        Err(EchoError::UrlError {
+            url: url.to_string(),
+            reason: UrlErrorReason::UnusedPathParams(unused),
+        })
+    }
+
+    /// Resolves `url` against `base_url` the way axios does:
+    /// - absolute urls are used as is, unless `allow_absolute_urls` is `false`
+    /// - relative urls are appended to the path of `base_url`, never replacing it
+    /// - a query string on `base_url` is kept and merged with the one on `url`
+    pub(crate) fn full_url(
+        config: &RequestConfig,
+        base_url: Option<&str>,
+        url: &str,
+    ) -> Result<Url, EchoError> {
+        let url_error = |reason| EchoError::UrlError {
+            url: url.to_string(),
+            reason,
+        };
+
+        let full_url = if is_absolute_url(url) {
+            if base_url.is_some() && config.allow_absolute_urls == Some(false) {
+                return Err(url_error(UrlErrorReason::AbsoluteUrlNotAllowed));
+            }
+            Url::parse(url).map_err(|err| url_error(UrlErrorReason::InvalidUrl(err.to_string())))?
+        } else {
+            match base_url {
+                Some(base_url) => Self::join_url(base_url, url).map_err(url_error)?,
+                None => return Err(url_error(UrlErrorReason::RelativeUrlWithoutBase)),
+            }
+        };
+
+        match full_url.scheme() {
+            "http" | "https" => Ok(full_url),
+            scheme => Err(url_error(UrlErrorReason::UnsupportedScheme(
+                scheme.to_string(),
+            ))),
+        }
+    }
+
+    fn join_url(base_url: &str, url: &str) -> Result<Url, UrlErrorReason> {
+        let mut joined =
+            Url::parse(base_url).map_err(|err| UrlErrorReason::InvalidBaseUrl(err.to_string()))?;
+
+        if joined.cannot_be_a_base() {
+            return Err(UrlErrorReason::InvalidBaseUrl(format!(
+                "`{}` can't be used as a base url",
+                base_url
+            )));
+        }
+
+        let (url, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (url, None),
+        };
+        let (path, query) = match url.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (url, None),
+        };
+
+        let path = Self::parse_url(path);
+        if !path.is_empty() {
+            let base_path = joined.path().trim_end_matches('/').to_string();
+            joined.set_path(&format!("{}/{}", base_path, path));
+        }
+
+        if let Some(query) = query.filter(|query| !query.is_empty()) {
+            let query = match joined.query().filter(|base_query| !base_query.is_empty()) {
+                Some(base_query) => format!("{}&{}", base_query, query),
+                None => query.to_string(),
+            };
+            joined.set_query(Some(&query));
+        }
+
+        if fragment.is_some() {
+            joined.set_fragment(fragment);
+        }
+
+        Ok(joined)
+    }
+
+    /// Full url with the path params filled in, but without `params`.
+    /// `endpoint` is the one picked from `base_urls`, if any.
+    pub(crate) fn resolve_url(
+        config: &RequestConfig,
+        endpoint: Option<&str>,
+        url: &str,
+    ) -> Result<String, EchoError> {
+        let url = Self::render_path(url, config.path_params.as_ref())?;
+        let base_url = Self::base_url(config, endpoint);
+        Ok(Self::full_url(config, base_url, &url)?.to_string())
+    }
+
+    /// Full url including the serialized `params`, this is what actually gets requested.
+    pub(crate) fn request_url(
+        config: &RequestConfig,
+        endpoint: Option<&str>,
+        url: &str,
+    ) -> Result<Url, EchoError> {
+        let rendered = Self::render_path(url, config.path_params.as_ref())?;
+        let mut full_url = Self::full_url(config, Self::base_url(config, endpoint), &rendered)?;
+
+        let query = match &config.params {
+            Some(params) if !params.is_empty() => {
+                params.to_query_string(&config.params_serializer.clone().unwrap_or_default())
+            }
+            _ => return Ok(full_url),
+        };
+
+        if !query.is_empty() {
+            let query = match full_url.query().filter(|existing| !existing.is_empty()) {
+                Some(existing) => format!("{}&{}", existing, query),
+                None => query,
+            };
+            full_url.set_query(Some(&query));
+        }
+
+        Ok(full_url)
+    }
+
+    /// `host`, or `host:port` with an explicit port, what per host limits count by.
+    pub(crate) fn host_key(url: &Url) -> String {
+        match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (host, _) => host.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Whether `url` starts with a scheme followed by `://`, the same test as axios' `isAbsoluteURL`.
+/// `items:batchGet` or `localhost:8080/x` are relative, even though they parse as urls.
+pub(crate) fn is_absolute_url(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once("://") else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// `url` split before its query or fragment, placeholders only count in the first part.
+fn split_path(url: &str) -> (&str, &str) {
+    url.split_at(url.find(['?', '#']).unwrap_or(url.len()))
+}
+
+/// Every `{name}` in `path` as `(start of the brace, name, end of the closing brace)`,
+/// any other brace is left untouched.
+fn placeholders(path: &str) -> impl Iterator<Item = (usize, &str, usize)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || loop {
+        let start = offset + path[offset..].find('{')?;
+        let name_len = path[start + 1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(path.len() - start - 1);
+        let end = start + 1 + name_len;
+        if name_len == 0 || !path[end..].starts_with('}') {
+            offset = start + 1;
+            continue;
+        }
+        offset = end + 1;
+        return Some((start, &path[start + 1..end], end + 1));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(Echo::parse_url("/endpoint/"), "endpoint");
+        assert_eq!(Echo::parse_url("endpoint/"), "endpoint");
+        assert_eq!(Echo::parse_url("/endpoint"), "endpoint");
+        assert_eq!(Echo::parse_url("endpoint"), "endpoint");
+    }
+
+    #[test]
+    fn test_get_full_url_with_base_url() {
+        let config = RequestConfig {
+            base_url: Some("https://api.example.com".to_string()),
+            ..Default::default()
+        };
+        let echo = Echo::configure(Some(config));
+
+        assert_eq!(
+            echo.get_full_url("/endpoint"),
+            "https://api.example.com/endpoint"
+        );
+        assert_eq!(
+            echo.get_full_url("endpoint/"),
+            "https://api.example.com/endpoint"
+        );
+    }
+
+    #[test]
+    fn test_get_full_url_without_base_url() {
+        let echo = Echo::configure(None);
+        assert_eq!(
+            echo.get_full_url("https://api.example.com/endpoint"),
+            "https://api.example.com/endpoint"
+        );
+    }
+
+    #[test]
+    fn test_render_path() {
+        let mut path_params = Params::new();
+        path_params.insert("id", "a/b c").unwrap();
+        path_params.insert("post_id", 7).unwrap();
+
+        assert_eq!(
+            Echo::render_path("/users/{id}/posts/{post_id}", Some(&path_params)).unwrap(),
+            "/users/a%2Fb%20c/posts/7"
+        );
+        assert_eq!(
+            Echo::render_path("/search?q={}", None).unwrap(),
+            "/search?q={}"
+        );
+        // only the path has placeholders
+        assert_eq!(
+            Echo::render_path("/users/{id}?q={id}#{x}", Some(&path_params)).unwrap(),
+            "/users/a%2Fb%20c?q={id}#{x}"
+        );
+        assert_eq!(
+            Echo::render_path("/users/1?q={x}", None).unwrap(),
+            "/users/1?q={x}"
+        );
+    }
+
+    #[test]
+    fn test_path_params_missing_and_unused() {
+        let mut path_params = Params::new();
+        path_params.insert("id", 1).unwrap();
+        path_params.insert("extra", 2).unwrap();
+
+        match Echo::render_path("/users/{id}/posts/{post_id}", Some(&path_params)) {
+            Err(EchoError::UrlError { reason, .. }) => assert_eq!(
+                reason,
+                UrlErrorReason::MissingPathParams(vec!["post_id".to_string()])
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // without any path params the placeholders are still there
+        for path_params in [None, Some(&Params::new())] {
+            match Echo::render_path("/users/{id}", path_params) {
+                Err(EchoError::UrlError { reason, .. }) => assert_eq!(
+                    reason,
+                    UrlErrorReason::MissingPathParams(vec!["id".to_string()])
+                ),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+
+        match Echo::check_path_params("/users/{id}?extra={extra}", Some(&path_params)) {
+            Err(EchoError::UrlError { reason, .. }) => assert_eq!(
+                reason,
+                UrlErrorReason::UnusedPathParams(vec!["extra".to_string()])
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn full_url(config: RequestConfig, url: &str) -> Result<String, EchoError> {
+        Echo::full_url(&config, config.base_url.as_deref(), url).map(|url| url.to_string())
+    }
+
+    fn with_base(base_url: &str) -> RequestConfig<'static> {
+        RequestConfig {
+            base_url: Some(base_url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn reason(result: Result<String, EchoError>) -> UrlErrorReason {
+        match result {
+            Err(EchoError::UrlError { reason, .. }) => reason,
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_join_with_base_url() {
+        assert_eq!(
+            full_url(with_base("https://api.example.com/"), "/users/1").unwrap(),
+            "https://api.example.com/users/1"
+        );
+        assert_eq!(
+            full_url(with_base("https://api.example.com/v1/"), "users").unwrap(),
+            "https://api.example.com/v1/users"
+        );
+        assert_eq!(
+            full_url(
+                with_base("https://api.example.com/v1?key=abc#top"),
+                "users?page=2"
+            )
+            .unwrap(),
+            "https://api.example.com/v1/users?key=abc&page=2#top"
+        );
+        assert_eq!(
+            full_url(with_base("https://api.example.com/v1"), "").unwrap(),
+            "https://api.example.com/v1"
+        );
+    }
+
+    #[test]
+    fn test_absolute_urls() {
+        assert_eq!(
+            full_url(
+                with_base("https://api.example.com"),
+                "https://other.example.com/x"
+            )
+            .unwrap(),
+            "https://other.example.com/x"
+        );
+
+        let config = RequestConfig {
+            allow_absolute_urls: Some(false),
+            ..with_base("https://api.example.com")
+        };
+        assert_eq!(
+            reason(full_url(config, "https://other.example.com/x")),
+            UrlErrorReason::AbsoluteUrlNotAllowed
+        );
+    }
+
+    #[test]
+    fn test_scheme_like_relative_urls() {
+        for url in [
+            "https://a.example.com",
+            "HTTP://a",
+            "git+ssh://a",
+            "ftp://a",
+        ] {
+            assert!(is_absolute_url(url), "{url}");
+        }
+        for url in [
+            "items:batchGet",
+            "localhost:8080/x",
+            "/a://b",
+            "1http://a",
+            "://a",
+        ] {
+            assert!(!is_absolute_url(url), "{url}");
+        }
+
+        assert_eq!(
+            full_url(with_base("https://api.example.com/v1"), "items:batchGet").unwrap(),
+            "https://api.example.com/v1/items:batchGet"
+        );
+        assert_eq!(
+            full_url(with_base("https://api.example.com"), "localhost:8080/x").unwrap(),
+            "https://api.example.com/localhost:8080/x"
+        );
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        assert_eq!(
+            reason(full_url(RequestConfig::default(), "/users")),
+            UrlErrorReason::RelativeUrlWithoutBase
+        );
+        assert!(matches!(
+            reason(full_url(with_base("not a url"), "/users")),
+            UrlErrorReason::InvalidBaseUrl(_)
+        ));
+        assert_eq!(
+            reason(full_url(RequestConfig::default(), "ftp://example.com/file")),
+            UrlErrorReason::UnsupportedScheme("ftp".to_string())
+        );
+    }
+
+    #[test]
+    fn test_request_url_appends_params() {
+        let mut params = Params::new();
+        params.insert("page", 2).unwrap();
+
+        let config = RequestConfig {
+            params: Some(params),
+            ..with_base("https://api.example.com/v1?key=abc")
+        };
+
+        assert_eq!(
+            Echo::request_url(&config, None, "/users").unwrap().as_str(),
+            "https://api.example.com/v1/users?key=abc&page=2"
+        );
+    }
+}
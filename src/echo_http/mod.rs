@@ -1,10 +1,45 @@
-use crate::RequestConfig;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::cache::CacheStatus;
+use crate::{Adapter, Redirect, RequestConfig};
+
+pub mod echo_api_error;
+pub mod echo_batch;
+pub mod echo_cache;
+pub mod echo_circuit_breaker;
+pub mod echo_hedging;
+#[allow(clippy::module_inception)]
 pub mod echo_http;
 pub mod echo_internal;
+pub mod echo_paginate;
+pub mod echo_rate_limit;
+pub mod echo_single_flight;
+pub mod echo_transform;
 pub mod echo_unknown;
+pub mod echo_url;
 
 pub struct Echo<'a> {
     pub config: RequestConfig<'a>,
+    /// sends requests unless the config brings its own `adapter`
+    adapter: Arc<dyn Adapter>,
+    /// builds requests, whichever adapter ends up sending them
     client: reqwest::Client,
 }
+
+/// What happened between sending a request and receiving the final response headers.
+pub(crate) struct Exchange {
+    pub started: Instant,
+    pub ttfb: Duration,
+    pub redirects: Vec<Redirect>,
+    /// how the cache answered the last hop, `None` without a cache
+    pub cache: Option<CacheStatus>,
+    /// time spent waiting in the `queue`
+    pub queued: Duration,
+    /// time spent in earlier attempts on other `base_urls`
+    pub retries: Duration,
+    /// the base url picked from `base_urls`
+    pub endpoint: Option<String>,
+    /// 1 for the first try
+    pub attempt: u32,
+}
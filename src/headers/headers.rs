@@ -57,9 +57,8 @@ impl<'a> Headers<'a> {
 }
 
 /// Automatically convert `Headers` into `reqwest::header::HeaderMap`.
-impl Into<HeaderMap> for Headers<'_> {
-    fn into(self) -> HeaderMap {
-        self.to_header_map()
+impl From<Headers<'_>> for HeaderMap {
+    fn from(headers: Headers<'_>) -> Self {
+        headers.to_header_map()
     }
 }
-
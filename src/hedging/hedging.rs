@@ -0,0 +1,109 @@
+use super::{HedgeDelay, Hedging, HedgingState, HedgingStats};
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Observed latencies needed before `HedgeDelay::Percentile` stops using its fallback.
+const MIN_SAMPLES: usize = 20;
+
+impl Hedging {
+    /// Hedges requests still waiting for a response after `delay`.
+    pub fn after(delay: Duration) -> Self {
+        Self::with_delay(HedgeDelay::Fixed(delay))
+    }
+
+    /// Hedges requests slower than the `percentile` (0.0 to 1.0) of recent latencies,
+    /// after `fallback` until enough of them were observed.
+    pub fn at_percentile(percentile: f64, fallback: Duration) -> Self {
+        Self::with_delay(HedgeDelay::Percentile {
+            percentile: percentile.clamp(0.0, 1.0),
+            fallback,
+        })
+    }
+
+    fn with_delay(delay: HedgeDelay) -> Self {
+        Hedging {
+            delay,
+            max_extra_load: 0.1,
+            window: 200,
+            state: Mutex::new(HedgingState::default()),
+        }
+    }
+
+    /// Hedges per request sent at most, counted over the last `window` requests.
+    /// The default of `0.1` sends at most 10% more requests.
+    pub fn max_extra_load(mut self, share: f64) -> Self {
+        self.max_extra_load = share.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How many recent latencies the percentile is taken over, and how many recent requests
+    /// `max_extra_load` is measured over, 200 by default.
+    pub fn window(mut self, latencies: usize) -> Self {
+        self.window = latencies.max(1);
+        self
+    }
+
+    pub fn stats(&self) -> HedgingStats {
+        self.state().stats
+    }
+
+    /// How long a request waits for its response before it's hedged, also counts it as sent.
+    pub(crate) fn start(&self) -> Duration {
+        let mut state = self.state();
+        state.stats.requests += 1;
+        match self.delay {
+            HedgeDelay::Fixed(delay) => delay,
+            HedgeDelay::Percentile { fallback, .. } if state.latencies.len() < MIN_SAMPLES => {
+                fallback
+            }
+            HedgeDelay::Percentile { percentile, .. } => {
+                let mut latencies: Vec<Duration> = state.latencies.iter().copied().collect();
+                latencies.sort_unstable();
+                let index = (percentile * (latencies.len() - 1) as f64).round() as usize;
+                latencies[index]
+            }
+        }
+    }
+
+    /// Whether a slow request may be hedged, counting it either way.
+    pub(crate) fn try_hedge(&self) -> bool {
+        let mut state = self.state();
+        let requests = state.stats.requests;
+        let window = self.window as u64;
+        while state
+            .hedges
+            .front()
+            .is_some_and(|request| request + window <= requests)
+        {
+            state.hedges.pop_front();
+        }
+
+        let budget = self.max_extra_load * requests.min(window) as f64;
+        if (state.hedges.len() + 1) as f64 > budget {
+            state.stats.over_budget += 1;
+            return false;
+        }
+        state.hedges.push_back(requests);
+        state.stats.hedged += 1;
+        true
+    }
+
+    /// Records how long a request took to answer, `won` when it was the copy that answered first.
+    pub(crate) fn observe(&self, latency: Duration, won: bool) {
+        let mut state = self.state();
+        state.latencies.push_back(latency);
+        while state.latencies.len() > self.window {
+            state.latencies.pop_front();
+        }
+        if won {
+            state.stats.wins += 1;
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, HedgingState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
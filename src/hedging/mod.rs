@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+#[allow(clippy::module_inception)]
+pub mod hedging;
+
+/// `hedging`, sends a second copy of a slow idempotent request and takes whichever answers first.
+///
+/// When the response headers haven't arrived after the delay, the same request is sent again.
+/// The first successful response (anything but an error or a `5xx`) wins and the other request
+/// is cancelled. The delay is either fixed or a percentile of recently observed latencies, and
+/// `max_extra_load` caps hedges to a share of the recent requests so a slow upstream isn't hit twice as hard.
+/// Only requests that go out are hedged, responses served by the `cache` never are.
+/// ```rs
+/// let hedging = Arc::new(Hedging::at_percentile(0.95, Duration::from_millis(100)).max_extra_load(0.05));
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     hedging: Some(hedging.clone()),
+///     ..Default::default()
+/// }));
+///
+/// let user = echo.get::<User>("/users/1").await?;
+/// println!("{:?}", hedging.stats());
+/// ```
+#[derive(Debug)]
+pub struct Hedging {
+    delay: HedgeDelay,
+    /// hedges allowed per request sent, 1.0 for no cap
+    max_extra_load: f64,
+    /// latencies kept for `HedgeDelay::Percentile`, and requests the budget is measured over
+    window: usize,
+    state: Mutex<HedgingState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HedgeDelay {
+    Fixed(Duration),
+    /// `percentile` of the observed latencies, `fallback` until there are enough of them
+    Percentile {
+        percentile: f64,
+        fallback: Duration,
+    },
+}
+
+/// A snapshot of a `Hedging` policy, see `Hedging::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HedgingStats {
+    /// requests sent under the policy
+    pub requests: u64,
+    /// requests that got a second copy
+    pub hedged: u64,
+    /// hedged requests the copy answered first
+    pub wins: u64,
+    /// requests that were slow enough but weren't hedged because of `max_extra_load`
+    pub over_budget: u64,
+}
+
+#[derive(Debug, Default)]
+struct HedgingState {
+    /// the most recent last
+    latencies: VecDeque<Duration>,
+    /// the number of the request each recent hedge was sent for, by `stats.requests`
+    hedges: VecDeque<u64>,
+    stats: HedgingStats,
+}
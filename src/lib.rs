@@ -1,14 +1,60 @@
+pub mod adapter;
+pub mod base_urls;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod cancel_token;
+pub mod cassette;
+pub mod circuit_breaker;
+pub mod codec;
 pub mod echo_errors;
 pub mod echo_http;
 pub mod headers;
+pub mod hedging;
+pub mod mock;
+pub mod paginate;
+pub mod params;
+pub mod queue;
+pub mod rate_limit;
+pub(crate) mod redact;
 pub mod request_config;
 pub mod response;
+pub mod single_flight;
+pub mod transform;
 
-pub use echo_errors::EchoError;
+pub use adapter::{Adapter, ReqwestAdapter};
+pub use base_urls::{BaseUrls, Strategy};
+pub use cache::{CacheMode, CacheStatus, CacheStore, DiskCache, MemoryCache};
+pub use cancel_token::CancelToken;
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+#[cfg(feature = "cbor")]
+pub use codec::CborCodec;
+#[cfg(feature = "msgpack")]
+pub use codec::MsgPackCodec;
+#[cfg(feature = "xml")]
+pub use codec::XmlCodec;
+#[cfg(feature = "yaml")]
+pub use codec::YamlCodec;
+pub use codec::{Codec, JsonCodec};
+pub use echo_errors::{
+    ApiErrorResponse, DecodeError, EchoError, ErrorCode, ErrorContext, ErrorResponse, ErrorSummary,
+    Problem, UrlErrorReason,
+};
 pub use echo_http::Echo;
 pub use headers::Headers;
+pub use hedging::{Hedging, HedgingStats};
+pub use paginate::{
+    Cursor, LinkHeader, NextPage, Offset, PageNumber, PageState, PageStrategy, Paginator,
+};
+pub use params::{ArrayFormat, Params, ParamsSerializer};
+pub use queue::{Priority, QueueStats, RequestQueue};
+pub use rate_limit::{RateLimit, RateLimiter};
 pub use request_config::RequestConfig;
-pub use response::{Response, ResponseUnknown};
+pub use response::{
+    Redirect, RequestInfo, Response, ResponseMeta, ResponseUnknown, Settled, Timings,
+};
+pub use single_flight::{SingleFlight, SingleFlightStats};
+pub use transform::{TransformRequest, TransformResponse};
 
 use once_cell::sync::Lazy;
 
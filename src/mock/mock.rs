@@ -0,0 +1,480 @@
+use super::{
+    BodyMatcher, Expectation, MockAdapter, MockExpectation, MockReply, MockState, RecordedRequest,
+    RequestMatcher,
+};
+use crate::adapter::BoxFuture;
+use crate::{Adapter, EchoError, ErrorCode};
+
+use bytes::Bytes;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, ResponseBuilderExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, MutexGuard};
+use std::time::Duration;
+use tokio::time::sleep;
+
+impl MockAdapter {
+    pub fn new() -> Self {
+        MockAdapter::default()
+    }
+
+    /// The mock as a config `adapter`, it keeps sharing state with `self`.
+    pub fn adapter(&self) -> Arc<dyn Adapter> {
+        Arc::new(self.clone())
+    }
+
+    /// Expects a request with `method` to `url`.
+    ///
+    /// `url` is either a path, matched against the request path, or a full url
+    /// matched against the request url without its query. `*` matches any run of characters,
+    /// and a `?query` part is the same as calling `with_query` with its pairs.
+    pub fn on(&self, method: Method, url: &str) -> MockExpectation {
+        let mut matcher = RequestMatcher {
+            method: Some(method),
+            ..Default::default()
+        };
+        let url = match url.split_once('?') {
+            Some((url, query)) => {
+                matcher.query = url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect();
+                url
+            }
+            None => url,
+        };
+        matcher.url = Some(url.to_string());
+        self.expect(matcher)
+    }
+
+    pub fn on_get(&self, url: &str) -> MockExpectation {
+        self.on(Method::GET, url)
+    }
+
+    pub fn on_post(&self, url: &str) -> MockExpectation {
+        self.on(Method::POST, url)
+    }
+
+    pub fn on_put(&self, url: &str) -> MockExpectation {
+        self.on(Method::PUT, url)
+    }
+
+    pub fn on_delete(&self, url: &str) -> MockExpectation {
+        self.on(Method::DELETE, url)
+    }
+
+    pub fn on_patch(&self, url: &str) -> MockExpectation {
+        self.on(Method::PATCH, url)
+    }
+
+    pub fn on_head(&self, url: &str) -> MockExpectation {
+        self.on(Method::HEAD, url)
+    }
+
+    /// Expects any request, e.g. as a fallback registered after the specific ones.
+    pub fn on_any(&self) -> MockExpectation {
+        self.expect(RequestMatcher::default())
+    }
+
+    /// Every request received so far, in order, including the ones that matched nothing.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state().requests.clone()
+    }
+
+    /// Panics unless every expectation was met and every request matched one.
+    ///
+    /// An expectation is met once it answered, a `times(n)` expectation once it answered `n` times,
+    /// and `reply_once` / `reply_sequence` expectations once all their replies were used.
+    pub fn verify(&self) {
+        let state = self.state();
+        let mut problems = Vec::new();
+        for expectation in &state.expectations {
+            if !expectation.is_met() {
+                problems.push(format!(
+                    "expected {} (called {} times)",
+                    expectation.matcher, expectation.calls
+                ));
+            }
+        }
+        for request in &state.unmatched {
+            problems.push(format!("unexpected request {request}"));
+        }
+        if !problems.is_empty() {
+            panic!("mock verification failed:\n  {}", problems.join("\n  "));
+        }
+    }
+
+    /// Forgets all expectations and recorded requests.
+    pub fn reset(&self) {
+        *self.state() = MockState::default();
+    }
+
+    fn expect(&self, matcher: RequestMatcher) -> MockExpectation {
+        MockExpectation {
+            mock: self.clone(),
+            matcher,
+            times: None,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // a panicking test must not hide the expectations from the others
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Records the request and takes the reply of the first expectation that matches it.
+    fn answer(&self, request: RecordedRequest) -> Option<MockReply> {
+        let mut state = self.state();
+        let reply = state
+            .expectations
+            .iter_mut()
+            .find(|expectation| expectation.is_available() && expectation.matcher.matches(&request))
+            .map(Expectation::take_reply);
+        if reply.is_none() {
+            state
+                .unmatched
+                .push(format!("{} {}", request.method, request.url));
+        }
+        state.requests.push(request);
+        reply
+    }
+}
+
+impl Adapter for MockAdapter {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+        Box::pin(async move {
+            let timeout = request.timeout().copied();
+            let recorded = RecordedRequest::from(&request);
+            let target = format!("{} {}", recorded.method, recorded.url);
+
+            let Some(reply) = self.answer(recorded) else {
+                return Err(EchoError::Network {
+                    code: ErrorCode::Network,
+                    message: format!("no mock expectation matches {target}"),
+                });
+            };
+
+            if let Some(delay) = reply.delay {
+                match timeout {
+                    Some(timeout) if timeout < delay => {
+                        sleep(timeout).await;
+                        return Err(EchoError::Network {
+                            code: ErrorCode::TimedOut,
+                            message: format!("mock reply to {target} timed out"),
+                        });
+                    }
+                    _ => sleep(delay).await,
+                }
+            }
+
+            if let Some(code) = reply.error {
+                return Err(EchoError::Network {
+                    code,
+                    message: format!("mock network error for {target}"),
+                });
+            }
+
+            let mut response = http::Response::builder()
+                .status(reply.status)
+                .url(request.url().clone());
+            for (name, value) in &reply.headers {
+                response = response.header(name.as_str(), value.as_str());
+            }
+            let response = response
+                .body(reply.body)
+                .map_err(|err| EchoError::Network {
+                    code: ErrorCode::BadResponse,
+                    message: format!("invalid mock reply to {target}: {err}"),
+                })?;
+            Ok(response.into())
+        })
+    }
+}
+
+impl MockExpectation {
+    /// Requires these query params, other params are allowed.
+    pub fn with_query<K, V>(mut self, params: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: ToString,
+    {
+        self.matcher.query.extend(
+            params
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.to_string())),
+        );
+        self
+    }
+
+    /// Requires a header with exactly this value, the name is case-insensitive.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.matcher
+            .headers
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    /// Requires a JSON body equal to `body`.
+    pub fn with_body<B: Serialize>(mut self, body: B) -> Self {
+        let body = serde_json::to_value(body).expect("mock body is not serializable");
+        self.matcher.body = Some(BodyMatcher::Json(body));
+        self
+    }
+
+    /// Requires a body for which `matches` returns true.
+    pub fn with_body_matching<F>(mut self, matches: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.matcher.body = Some(BodyMatcher::Custom(Arc::new(matches)));
+        self
+    }
+
+    /// Answers at most `times` requests, and is only met once it answered exactly that many.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Answers every matching request with `status` and `body` as JSON.
+    pub fn reply<B: Serialize>(self, status: u16, body: B) {
+        self.reply_with(MockReply::json(status, &body))
+    }
+
+    /// Answers the next matching request only.
+    pub fn reply_once<B: Serialize>(self, status: u16, body: B) {
+        self.register([MockReply::json(status, &body)], false)
+    }
+
+    /// Answers every matching request with `reply`.
+    pub fn reply_with(self, reply: MockReply) {
+        self.register([reply], true)
+    }
+
+    /// Answers matching requests with `replies` in order, one each.
+    /// Once they are used up, requests fall through to the next matching expectation.
+    pub fn reply_sequence(self, replies: impl IntoIterator<Item = MockReply>) {
+        self.register(replies, false)
+    }
+
+    /// Fails every matching request with `code`, e.g. `ErrorCode::ConnectionRefused`.
+    pub fn network_error(self, code: ErrorCode) {
+        self.reply_with(MockReply::network_error(code))
+    }
+
+    fn register(self, replies: impl IntoIterator<Item = MockReply>, repeat: bool) {
+        let expectation = Expectation {
+            matcher: self.matcher,
+            replies: replies.into_iter().collect::<VecDeque<_>>(),
+            repeat,
+            times: self.times,
+            calls: 0,
+        };
+        self.mock.state().expectations.push(expectation);
+    }
+}
+
+impl Expectation {
+    fn is_available(&self) -> bool {
+        !self.replies.is_empty() && self.times.is_none_or(|times| self.calls < times)
+    }
+
+    fn is_met(&self) -> bool {
+        match self.times {
+            Some(times) => self.calls == times,
+            None if self.repeat => self.calls > 0,
+            None => self.replies.is_empty(),
+        }
+    }
+
+    fn take_reply(&mut self) -> MockReply {
+        self.calls += 1;
+        if self.repeat && self.replies.len() == 1 {
+            self.replies[0].clone()
+        } else {
+            self.replies
+                .pop_front()
+                .expect("only available with replies")
+        }
+    }
+}
+
+impl RequestMatcher {
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        if self
+            .method
+            .as_ref()
+            .is_some_and(|method| *method != request.method)
+        {
+            return false;
+        }
+
+        if let Some(pattern) = &self.url {
+            let matched = if pattern.starts_with("http://") || pattern.starts_with("https://") {
+                let mut url = request.url.clone();
+                url.set_query(None);
+                url.set_fragment(None);
+                glob(pattern, url.as_str())
+            } else {
+                glob(pattern, request.url.path())
+            };
+            if !matched {
+                return false;
+            }
+        }
+
+        let query: Vec<(String, String)> = request.url.query_pairs().into_owned().collect();
+        if !self.query.iter().all(|pair| query.contains(pair)) {
+            return false;
+        }
+
+        let headers_match = self.headers.iter().all(|(name, value)| {
+            request
+                .headers
+                .get_all(name.as_str())
+                .iter()
+                .any(|actual| actual.as_bytes() == value.as_bytes())
+        });
+        if !headers_match {
+            return false;
+        }
+
+        match &self.body {
+            Some(BodyMatcher::Json(expected)) => {
+                serde_json::from_slice::<serde_json::Value>(&request.body)
+                    .ok()
+                    .as_ref()
+                    == Some(expected)
+            }
+            Some(BodyMatcher::Custom(matches)) => matches(&request.body),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for RequestMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.method {
+            Some(method) => write!(f, "{method}")?,
+            None => f.write_str("ANY")?,
+        }
+        write!(f, " {}", self.url.as_deref().unwrap_or("*"))?;
+        if !self.query.is_empty() {
+            let query: Vec<String> = self
+                .query
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            write!(f, "?{}", query.join("&"))?;
+        }
+        for (name, value) in &self.headers {
+            write!(f, " [{name}: {value}]")?;
+        }
+        match &self.body {
+            Some(BodyMatcher::Json(body)) => write!(f, " with body {body}"),
+            Some(BodyMatcher::Custom(_)) => f.write_str(" with matching body"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for BodyMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyMatcher::Json(body) => f.debug_tuple("Json").field(body).finish(),
+            BodyMatcher::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl MockReply {
+    /// A reply with an empty body.
+    pub fn new(status: u16) -> Self {
+        MockReply {
+            status,
+            headers: Vec::new(),
+            body: Bytes::new(),
+            delay: None,
+            error: None,
+        }
+    }
+
+    /// A reply with `body` as JSON and a matching `Content-Type`.
+    pub fn json<B: Serialize>(status: u16, body: &B) -> Self {
+        let body = serde_json::to_vec(body).expect("mock reply is not serializable");
+        MockReply::new(status)
+            .header(CONTENT_TYPE.as_str(), "application/json")
+            .body(body)
+    }
+
+    /// Fails the request with `code` instead of answering.
+    pub fn network_error(code: ErrorCode) -> Self {
+        MockReply {
+            error: Some(code),
+            ..MockReply::new(0)
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+impl RecordedRequest {
+    /// The body parsed as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+impl From<&reqwest::Request> for RecordedRequest {
+    fn from(request: &reqwest::Request) -> Self {
+        RecordedRequest {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(Bytes::copy_from_slice)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any run of characters.
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = text.strip_prefix(parts.next().unwrap_or("")) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
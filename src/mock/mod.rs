@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use serde_json::Value;
+use url::Url;
+
+use crate::ErrorCode;
+
+#[allow(clippy::module_inception)]
+pub mod mock;
+
+/// An `Adapter` that answers from registered expectations instead of the network,
+/// like `axios-mock-adapter`. Clones share their expectations and history.
+/// ```rs
+/// let mock = MockAdapter::new();
+/// mock.on_get("/users/1").reply(200, json!({ "id": 1, "name": "Test" }));
+/// mock.on_post("/users")
+///     .with_header("authorization", "Bearer token")
+///     .with_body(json!({ "name": "Test" }))
+///     .reply(201, json!({ "id": 2 }));
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     base_url: Some("https://api.example.com".to_string()),
+///     adapter: Some(mock.adapter()),
+///     ..Default::default()
+/// }));
+///
+/// let user = echo.get::<User>("/users/1").await?;
+///
+/// mock.verify(); // panics if an expectation was not met or a request matched none
+/// ```
+/// Requests that match no expectation fail with an `ERR_NETWORK` error.
+#[derive(Debug, Clone, Default)]
+pub struct MockAdapter {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    expectations: Vec<Expectation>,
+    requests: Vec<RecordedRequest>,
+    /// `METHOD url` of every request no expectation answered
+    unmatched: Vec<String>,
+}
+
+/// What a request has to look like, returned by `MockAdapter::on`.
+///
+/// Nothing is registered until one of the `reply*` methods or `network_error` is called.
+#[must_use = "expectations are only registered once a reply is set"]
+#[derive(Debug)]
+pub struct MockExpectation {
+    mock: MockAdapter,
+    matcher: RequestMatcher,
+    times: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RequestMatcher {
+    /// `None` matches any method
+    method: Option<Method>,
+    /// a path like `/users/*` or a full url, `None` matches any url
+    url: Option<String>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Option<BodyMatcher>,
+}
+
+type BodyFn = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+#[derive(Clone)]
+enum BodyMatcher {
+    Json(Value),
+    Custom(Arc<BodyFn>),
+}
+
+#[derive(Debug)]
+struct Expectation {
+    matcher: RequestMatcher,
+    replies: VecDeque<MockReply>,
+    /// keep answering with the last reply instead of using it up
+    repeat: bool,
+    times: Option<usize>,
+    calls: usize,
+}
+
+/// A canned answer: a response, optionally delayed, or a network error.
+/// ```rs
+/// mock.on_get("/users").reply_sequence([
+///     MockReply::network_error(ErrorCode::ConnectionReset),
+///     MockReply::new(503).header("retry-after", "1"),
+///     MockReply::json(200, &json!([])).delay(Duration::from_millis(50)),
+/// ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockReply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+    /// waits this long before answering, longer than the request's `timeout` fails with `ETIMEDOUT`
+    pub delay: Option<Duration>,
+    /// fails the request with this code instead of answering
+    pub error: Option<ErrorCode>,
+}
+
+/// A request the mock received, see `MockAdapter::requests`.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    /// full url, params included
+    pub url: Url,
+    pub headers: HeaderMap,
+    /// the encoded and transformed body, empty when there was none
+    pub body: Bytes,
+}
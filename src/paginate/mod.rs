@@ -0,0 +1,103 @@
+use crate::{Echo, Params, RequestConfig, ResponseUnknown};
+
+#[allow(clippy::module_inception)]
+pub mod paginate;
+
+/// Walks a paginated api, see `Echo::paginate`.
+///
+/// Pages are requested one after the other, the `PageStrategy` finds the next one in the
+/// response. `pages()` streams whole pages, `items::<T>()` the items of every page, found at
+/// `items_at`. A page that fails, or answers with a non-2xx status, is yielded as an error
+/// and ends the stream, there's no telling where the next page would be.
+/// ```rs
+/// use futures_util::StreamExt;
+///
+/// let repos = RequestConfig {
+///     url: Some("/orgs/rust-lang/repos".to_string()),
+///     ..Default::default()
+/// };
+/// let mut repos = std::pin::pin!(echo.paginate(repos, LinkHeader).max_items(250).items::<Repo>());
+///
+/// while let Some(repo) = repos.next().await {
+///     println!("{}", repo?.name);
+/// }
+/// ```
+pub struct Paginator<'e, 'a> {
+    echo: &'e Echo<'a>,
+    config: RequestConfig<'e>,
+    strategy: Box<dyn PageStrategy + 'e>,
+    items_at: String,
+    max_pages: Option<usize>,
+    max_items: Option<usize>,
+}
+
+/// Finds the page after the current one, implement it for apis the built-in
+/// `LinkHeader`, `Cursor`, `PageNumber` and `Offset` don't cover.
+/// ```rs
+/// /// `{ "data": [..], "next_page_url": "https://.." }`
+/// struct NextPageUrl;
+///
+/// impl PageStrategy for NextPageUrl {
+///     fn next_page(&self, page: &ResponseUnknown<'_>, _: &PageState) -> Option<NextPage> {
+///         page.pointer("/next_page_url")?.as_str().map(|url| NextPage::Url(url.to_string()))
+///     }
+/// }
+/// ```
+pub trait PageStrategy: Send + Sync {
+    /// Params added to the first request, e.g. `page=1`.
+    fn first_page(&self) -> Params {
+        Params::new()
+    }
+
+    /// Where the page after `page` is, `None` when it was the last one.
+    fn next_page(&self, page: &ResponseUnknown<'_>, state: &PageState) -> Option<NextPage>;
+}
+
+/// Where a `PageStrategy` says the next page is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextPage {
+    /// this url, as is: it replaces the request's url and `params`
+    Url(String),
+    /// the same url, with these params set on top of the request's `params`
+    Params(Params),
+}
+
+/// How far a `Paginator` got, handed to `PageStrategy::next_page`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageState {
+    /// pages fetched so far, the current one included
+    pub pages: usize,
+    /// items on the current page
+    pub items: usize,
+    /// items on every page so far, the current one included
+    pub total_items: usize,
+}
+
+/// Follows the RFC 8288 `Link` header's `rel="next"`, the way GitHub and many others paginate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkHeader;
+
+/// Sends the value found at a JSON pointer in the page as a param, until it's missing,
+/// `null` or empty.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pointer: String,
+    param: String,
+}
+
+/// Counts pages in a param, `1`, `2`, .. until a page has no items,
+/// or fewer than its `per_page`.
+#[derive(Debug, Clone)]
+pub struct PageNumber {
+    param: String,
+    start: u64,
+    per_page: Option<(String, u64)>,
+}
+
+/// Skips the items seen so far with an offset param, until a page has fewer than `limit` items.
+#[derive(Debug, Clone)]
+pub struct Offset {
+    param: String,
+    limit_param: String,
+    limit: u64,
+}
@@ -0,0 +1,349 @@
+use super::{Cursor, LinkHeader, NextPage, Offset, PageNumber, PageState, PageStrategy, Paginator};
+use crate::echo_errors::decode_error::decode_value;
+use crate::{Echo, EchoError, Params, RequestConfig, ResponseUnknown};
+
+use futures_util::future;
+use futures_util::stream::{self, Stream, StreamExt};
+use reqwest::header::LINK;
+use serde_json::Value;
+
+impl<'e, 'a> Paginator<'e, 'a> {
+    pub(crate) fn new(
+        echo: &'e Echo<'a>,
+        config: RequestConfig<'e>,
+        strategy: Box<dyn PageStrategy + 'e>,
+    ) -> Self {
+        Paginator {
+            echo,
+            config,
+            strategy,
+            items_at: String::new(),
+            max_pages: None,
+            max_items: None,
+        }
+    }
+
+    /// JSON pointer to the items array of a page, e.g. `/data`.
+    /// The default is `""`, the body itself is the array.
+    pub fn items_at(mut self, pointer: &str) -> Self {
+        self.items_at = pointer.to_string();
+        self
+    }
+
+    /// Stops after `pages` pages, the default is no limit.
+    pub fn max_pages(mut self, pages: usize) -> Self {
+        self.max_pages = Some(pages);
+        self
+    }
+
+    /// Stops once `items` items were seen, the default is no limit.
+    /// `items()` yields exactly that many, `pages()` the whole page that reached it.
+    pub fn max_items(mut self, items: usize) -> Self {
+        self.max_items = Some(items);
+        self
+    }
+
+    /// Every page, as it arrives.
+    pub fn pages(self) -> impl Stream<Item = Result<ResponseUnknown<'e>, EchoError>> + 'e {
+        let Paginator {
+            echo,
+            mut config,
+            strategy,
+            items_at,
+            max_pages,
+            max_items,
+        } = self;
+        let mut params = config.params.take().unwrap_or_default();
+        params.extend(strategy.first_page());
+        config.params = Some(params);
+
+        let walk = Walk {
+            echo,
+            next: Some(config),
+            strategy,
+            items_at,
+            max_pages,
+            max_items,
+            state: PageState::default(),
+        };
+        stream::unfold(walk, |mut walk| async move {
+            let config = walk.next.take()?;
+            if walk.max_pages.is_some_and(|max| walk.state.pages >= max)
+                || walk
+                    .max_items
+                    .is_some_and(|max| walk.state.total_items >= max)
+            {
+                return None;
+            }
+
+            let page = match walk.echo.request_unknown(config.clone()).await {
+                Ok(page) => page,
+                Err(err) => return Some((Err(err), walk)),
+            };
+            let page = match page.into_inner().error_for_status() {
+                Ok(inner) => ResponseUnknown { inner },
+                Err(err) => return Some((Err(err), walk)),
+            };
+
+            let items = page
+                .pointer(&walk.items_at)
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            walk.state.pages += 1;
+            walk.state.items = items;
+            walk.state.total_items += items;
+            let (url, params) = (config.url.clone(), config.params.clone());
+            // a next page that is this page again, e.g. a `rel="next"` link to itself, never ends
+            walk.next = walk
+                .strategy
+                .next_page(&page, &walk.state)
+                .map(|next| next.apply(config))
+                .filter(|next| next.url != url || next.params != params);
+            Some((Ok(page), walk))
+        })
+    }
+
+    /// Every item of every page, decoded into `T`. An item that doesn't decode ends the stream
+    /// with a `DecodeError` pointing at it, a page without the items array counts as empty.
+    pub fn items<T>(self) -> impl Stream<Item = Result<T, EchoError>> + 'e
+    where
+        T: serde::de::DeserializeOwned + 'e,
+    {
+        let items_at = self.items_at.clone();
+        let max_items = self.max_items.unwrap_or(usize::MAX);
+        self.pages()
+            .flat_map(move |page| stream::iter(page_items(page, &items_at)))
+            .scan(false, |failed, item| {
+                if *failed {
+                    return future::ready(None);
+                }
+                *failed = item.is_err();
+                future::ready(Some(item))
+            })
+            .take(max_items)
+    }
+}
+
+/// The items of `page`, each decoded on its own so an error points at the one that failed.
+fn page_items<T>(
+    page: Result<ResponseUnknown<'_>, EchoError>,
+    items_at: &str,
+) -> Vec<Result<T, EchoError>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let page = match page {
+        Ok(page) => page.into_inner(),
+        Err(err) => return vec![Err(err)],
+    };
+    match page.data.pointer(items_at) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let pointer = format!("{items_at}/{index}");
+                decode_value(item.clone(), &pointer, page.status, &page.request)
+            })
+            .collect(),
+        // not a list, decoding one says so
+        Some(items) => {
+            match decode_value::<Vec<T>>(items.clone(), items_at, page.status, &page.request) {
+                Ok(items) => items.into_iter().map(Ok).collect(),
+                Err(err) => vec![Err(err)],
+            }
+        }
+    }
+}
+
+/// Where a `Paginator` is, moved from one page to the next.
+struct Walk<'e, 'a> {
+    echo: &'e Echo<'a>,
+    /// the request for the next page, `None` once there's none
+    next: Option<RequestConfig<'e>>,
+    strategy: Box<dyn PageStrategy + 'e>,
+    items_at: String,
+    max_pages: Option<usize>,
+    max_items: Option<usize>,
+    state: PageState,
+}
+
+impl NextPage {
+    fn apply<'e>(self, mut config: RequestConfig<'e>) -> RequestConfig<'e> {
+        match self {
+            NextPage::Url(url) => {
+                config.url = Some(url);
+                config.params = None;
+                config.path_params = None;
+            }
+            NextPage::Params(params) => {
+                let mut merged = config.params.take().unwrap_or_default();
+                merged.extend(params);
+                config.params = Some(merged);
+            }
+        }
+        config
+    }
+}
+
+impl PageStrategy for LinkHeader {
+    fn next_page(&self, page: &ResponseUnknown<'_>, _: &PageState) -> Option<NextPage> {
+        let next = page
+            .headers
+            .get_all(LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(next_link)?;
+        // relative targets are relative to the page they came with
+        let url = url::Url::parse(&page.url)
+            .and_then(|base| base.join(&next))
+            .map_or(next, String::from);
+        Some(NextPage::Url(url))
+    }
+}
+
+/// The target of the `rel="next"` link in a `Link` header value,
+/// e.g. `<https://api.example.test/items?page=2>; rel="next", <..>; rel="last"`.
+fn next_link(header: &str) -> Option<String> {
+    let mut rest = header;
+    loop {
+        let start = rest.find('<')?;
+        let end = start + rest[start..].find('>')?;
+        let target = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        // the params run until the next link, commas in quoted values don't count
+        let mut quoted = false;
+        let params_end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c == ',' && !quoted
+            })
+            .map_or(rest.len(), |(index, _)| index);
+        let params = &rest[..params_end];
+        rest = &rest[params_end..];
+
+        let is_next = params.split(';').any(|param| {
+            let Some((name, value)) = param.split_once('=') else {
+                return false;
+            };
+            name.trim().eq_ignore_ascii_case("rel")
+                && value
+                    .trim()
+                    .trim_matches('"')
+                    .split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next"))
+        });
+        if is_next {
+            return Some(target.trim().to_string());
+        }
+    }
+}
+
+impl Cursor {
+    /// Sends the value at `pointer`, e.g. `/meta/next_cursor`, as the `param` of the next request.
+    pub fn new(pointer: &str, param: &str) -> Self {
+        Cursor {
+            pointer: pointer.to_string(),
+            param: param.to_string(),
+        }
+    }
+}
+
+impl PageStrategy for Cursor {
+    fn next_page(&self, page: &ResponseUnknown<'_>, _: &PageState) -> Option<NextPage> {
+        let cursor = match page.pointer(&self.pointer)? {
+            Value::String(cursor) if !cursor.is_empty() => Value::String(cursor.clone()),
+            Value::Number(cursor) => Value::Number(cursor.clone()),
+            _ => return None,
+        };
+        let mut params = Params::new();
+        params.params.insert(self.param.clone(), cursor);
+        Some(NextPage::Params(params))
+    }
+}
+
+impl PageNumber {
+    /// Counts pages in `param`, starting at `1`.
+    pub fn new(param: &str) -> Self {
+        PageNumber {
+            param: param.to_string(),
+            start: 1,
+            per_page: None,
+        }
+    }
+
+    /// The number of the first page, e.g. `0`.
+    pub fn starting_at(mut self, start: u64) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Asks for `size` items a page in `param`, a page with fewer is the last one.
+    pub fn per_page(mut self, param: &str, size: u64) -> Self {
+        self.per_page = Some((param.to_string(), size));
+        self
+    }
+
+    fn page(&self, page: u64) -> Params {
+        let mut params = Params::new();
+        params.params.insert(self.param.clone(), page.into());
+        if let Some((param, size)) = &self.per_page {
+            params.params.insert(param.clone(), (*size).into());
+        }
+        params
+    }
+}
+
+impl PageStrategy for PageNumber {
+    fn first_page(&self) -> Params {
+        self.page(self.start)
+    }
+
+    fn next_page(&self, _: &ResponseUnknown<'_>, state: &PageState) -> Option<NextPage> {
+        let short = self
+            .per_page
+            .as_ref()
+            .is_some_and(|(_, size)| (state.items as u64) < *size);
+        if state.items == 0 || short {
+            return None;
+        }
+        Some(NextPage::Params(self.page(self.start + state.pages as u64)))
+    }
+}
+
+impl Offset {
+    /// Asks for `limit` items a page in `limit_param`, skipping the ones seen in `param`.
+    pub fn new(param: &str, limit_param: &str, limit: u64) -> Self {
+        Offset {
+            param: param.to_string(),
+            limit_param: limit_param.to_string(),
+            limit,
+        }
+    }
+
+    fn at(&self, offset: u64) -> Params {
+        let mut params = Params::new();
+        params.params.insert(self.param.clone(), offset.into());
+        params
+            .params
+            .insert(self.limit_param.clone(), self.limit.into());
+        params
+    }
+}
+
+impl PageStrategy for Offset {
+    fn first_page(&self) -> Params {
+        self.at(0)
+    }
+
+    fn next_page(&self, _: &ResponseUnknown<'_>, state: &PageState) -> Option<NextPage> {
+        if state.items == 0 || (state.items as u64) < self.limit {
+            return None;
+        }
+        Some(NextPage::Params(self.at(state.total_items as u64)))
+    }
+}
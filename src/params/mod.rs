@@ -0,0 +1,55 @@
+#[allow(clippy::module_inception)]
+pub mod params;
+
+pub(crate) use params::scalar_to_string;
+
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// URL parameters, kept in insertion order.
+///
+/// Values can be anything that serializes to a JSON scalar, array or object,
+/// arrays and objects are written according to the `ParamsSerializer`.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Params {
+    pub params: Map<String, Value>,
+}
+
+/// How arrays are written into the query string.
+///
+/// For `{ "id": [1, 2] }`:
+/// - `Repeat`   => `id=1&id=2`
+/// - `Brackets` => `id[]=1&id[]=2` (default, same as axios)
+/// - `Indices`  => `id[0]=1&id[1]=2`
+/// - `Comma`    => `id=1,2` (a `,` inside a value is written as `%2C`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayFormat {
+    Repeat,
+    #[default]
+    Brackets,
+    Indices,
+    Comma,
+}
+
+/// Custom query string serializer, see `ParamsSerializer::serialize`.
+pub type SerializeParams = Arc<dyn Fn(&Params) -> String + Send + Sync>;
+
+/// `paramsSerializer`, controls how `params` are turned into a query string.
+#[derive(Clone, Default)]
+pub struct ParamsSerializer {
+    /// `array_format` is used by the built-in serializer for arrays
+    pub array_format: ArrayFormat,
+
+    /// `serialize` replaces the built-in serializer entirely,
+    /// the returned string is appended to the url as is (without the leading `?`)
+    pub serialize: Option<SerializeParams>,
+}
+
+impl std::fmt::Debug for ParamsSerializer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParamsSerializer")
+            .field("array_format", &self.array_format)
+            .field("serialize", &self.serialize.as_ref().map(|_| "Fn"))
+            .finish()
+    }
+}
@@ -0,0 +1,237 @@
+use super::{ArrayFormat, Params, ParamsSerializer};
+use crate::{Echo, EchoError};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Same as `encodeURIComponent`, but leaves the characters axios leaves readable.
+const QUERY: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'[')
+    .remove(b']')
+    .remove(b':')
+    .remove(b'$')
+    .remove(b',');
+
+/// `QUERY` plus `,`, for the elements of `Comma` arrays, so a comma in a value can't pass for a separator.
+const COMMA_ELEMENT: &AsciiSet = &QUERY.add(b',');
+
+impl Params {
+    /// Creates an empty `Params` instance.
+    pub fn new() -> Self {
+        Params { params: Map::new() }
+    }
+
+    /// Creates `Params` from anything that serializes to an object, a struct or a map.
+    /// ```rs
+    /// use echo_http::Params;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Search {
+    ///     q: String,
+    ///     tags: Vec<String>,
+    /// }
+    ///
+    /// let params = Params::from_serialize(&Search { .. })?;
+    /// ```
+    pub fn from_serialize<T>(params: &T) -> Result<Self, EchoError>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let params = serde_json::to_value(params).map_err(|err| {
+            EchoError::InvalidParams(format!("params could not be serialized: {}", err))
+        })?;
+        match params {
+            Value::Object(params) => Ok(Params { params }),
+            Value::Null => Ok(Params::new()),
+            other => Err(EchoError::InvalidParams(format!(
+                "params must serialize to an object, got {}",
+                other
+            ))),
+        }
+    }
+
+    /// Inserts a single param, replacing any previous value for `key`.
+    /// A value that can't be serialized, e.g. a map with non-string keys,
+    /// is an `EchoError::InvalidParams`.
+    /// ```rs
+    /// let mut params = Params::new();
+    /// params.insert("page", 2)?;
+    /// params.insert("tags", vec!["rust", "http"])?;
+    /// ```
+    pub fn insert<V>(&mut self, key: &str, value: V) -> Result<(), EchoError>
+    where
+        V: serde::Serialize,
+    {
+        let value = to_param(key, value)?;
+        self.params.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Appends a value to `key`, turning it into an array when it is already set.
+    /// ```rs
+    /// let mut params = Params::new();
+    /// params.append("id", 1)?;
+    /// params.append("id", 2)?; // id=[1, 2]
+    /// ```
+    pub fn append<V>(&mut self, key: &str, value: V) -> Result<(), EchoError>
+    where
+        V: serde::Serialize,
+    {
+        let value = to_param(key, value)?;
+        match self.params.get_mut(key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                self.params.insert(key.to_string(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds every param from `other`, keys already present are replaced.
+    pub fn extend(&mut self, other: Params) {
+        self.params.extend(other.params);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Fills the `{placeholders}` in the path of `url` the way `path_params` do,
+    /// for the shorthand methods that only take a url.
+    /// A placeholder without a value, or a value without a placeholder, is an `EchoError::UrlError`.
+    /// ```rs
+    /// let mut path_params = Params::new();
+    /// path_params.insert("id", "john doe")?;
+    ///
+    /// // GET /users/john%20doe
+    /// let user = echo.get::<User>(&path_params.render("/users/{id}")?).await?;
+    /// ```
+    pub fn render(&self, url: &str) -> Result<String, EchoError> {
+        Echo::check_path_params(url, Some(self))?;
+        Echo::render_path(url, Some(self))
+    }
+
+    /// Serializes the params into a query string (without the leading `?`).
+    pub fn to_query_string(&self, serializer: &ParamsSerializer) -> String {
+        if let Some(serialize) = &serializer.serialize {
+            return serialize(self);
+        }
+
+        let mut pairs = Vec::new();
+        for (key, value) in &self.params {
+            flatten(key.clone(), value, serializer.array_format, &mut pairs);
+        }
+
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{}={}", utf8_percent_encode(key, QUERY), value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+fn to_param<V>(key: &str, value: V) -> Result<Value, EchoError>
+where
+    V: serde::Serialize,
+{
+    serde_json::to_value(value).map_err(|err| {
+        EchoError::InvalidParams(format!("param `{}` could not be serialized: {}", key, err))
+    })
+}
+
+pub(crate) fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Collects the `key=value` pairs for `value`, with the values already percent-encoded.
+fn flatten(key: String, value: &Value, format: ArrayFormat, pairs: &mut Vec<(String, String)>) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (child, value) in map {
+                flatten(format!("{}[{}]", key, child), value, format, pairs);
+            }
+        }
+        Value::Array(values) => {
+            let scalars: Option<Vec<String>> = values.iter().map(scalar_to_string).collect();
+
+            match (format, scalars) {
+                (ArrayFormat::Comma, Some(scalars)) => {
+                    let elements: Vec<String> = scalars
+                        .iter()
+                        .map(|scalar| utf8_percent_encode(scalar, COMMA_ELEMENT).to_string())
+                        .collect();
+                    pairs.push((key, elements.join(",")));
+                }
+                (ArrayFormat::Repeat, _) => {
+                    for value in values {
+                        flatten(key.clone(), value, format, pairs);
+                    }
+                }
+                (ArrayFormat::Brackets, _) => {
+                    for value in values {
+                        flatten(format!("{}[]", key), value, format, pairs);
+                    }
+                }
+                // arrays of objects can't be comma separated, fall back to indices
+                (ArrayFormat::Indices, _) | (ArrayFormat::Comma, None) => {
+                    for (index, value) in values.iter().enumerate() {
+                        flatten(format!("{}[{}]", key, index), value, format, pairs);
+                    }
+                }
+            }
+        }
+        scalar => {
+            if let Some(value) = scalar_to_string(scalar) {
+                pairs.push((key, utf8_percent_encode(&value, QUERY).to_string()));
+            }
+        }
+    }
+}
+
+/// Plain string maps can still be used as params.
+impl From<HashMap<String, String>> for Params {
+    fn from(map: HashMap<String, String>) -> Self {
+        let mut params = Params::new();
+        for (key, value) in map {
+            params.params.insert(key, Value::String(value));
+        }
+        params
+    }
+}
+
+/// Pairs with the same key are appended, see `Params::append`.
+impl<K, V> TryFrom<Vec<(K, V)>> for Params
+where
+    K: AsRef<str>,
+    V: serde::Serialize,
+{
+    type Error = EchoError;
+
+    fn try_from(pairs: Vec<(K, V)>) -> Result<Self, Self::Error> {
+        let mut params = Params::new();
+        for (key, value) in pairs {
+            params.append(key.as_ref(), value)?;
+        }
+        Ok(params)
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::response::response::serialize_millis;
+
+#[allow(clippy::module_inception)]
+pub mod queue;
+
+/// `queue`, caps how many requests are in flight at once, overall and per host.
+///
+/// A request over the cap waits until one finishes, higher `priority` first and
+/// in arrival order within a priority. A request is in flight from the moment it's sent
+/// until its body is read, redirects included.
+/// ```rs
+/// let queue = Arc::new(RequestQueue::new(50).max_per_host(6).max_wait(Duration::from_secs(30)));
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     queue: Some(queue.clone()),
+///     ..Default::default()
+/// }));
+///
+/// // a batch job on the same client, it never holds back interactive calls
+/// let report = echo
+///     .request::<Report>(RequestConfig {
+///         url: Some("/reports/2024".to_string()),
+///         priority: Some(Priority::Low),
+///         ..Default::default()
+///     })
+///     .await?;
+///
+/// println!("{:?}", queue.stats());
+/// ```
+#[derive(Debug, Default)]
+pub struct RequestQueue {
+    /// `None` for no overall cap
+    max_in_flight: Option<usize>,
+    /// `None` for no cap per host
+    max_per_host: Option<usize>,
+    /// `None` to wait as long as it takes
+    max_wait: Option<Duration>,
+    state: Mutex<QueueState>,
+}
+
+/// Where a request goes in the `queue`, the default is `Normal`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A snapshot of a `RequestQueue`, see `RequestQueue::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QueueStats {
+    /// requests sent and not finished yet
+    pub in_flight: usize,
+    /// requests waiting for their turn
+    pub queued: usize,
+    /// requests that got their turn, right away or after waiting
+    pub sent: u64,
+    /// requests that gave up after `max_wait`
+    pub timed_out: u64,
+    /// time spent waiting by all sent requests
+    #[serde(rename = "total_wait_ms", serialize_with = "serialize_millis")]
+    pub total_wait: Duration,
+    /// the longest a sent request waited
+    #[serde(rename = "longest_wait_ms", serialize_with = "serialize_millis")]
+    pub longest_wait: Duration,
+    /// how long the request at the front of the queue has been waiting
+    #[serde(rename = "oldest_waiting_ms", serialize_with = "serialize_millis")]
+    pub oldest_waiting: Duration,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    in_flight: usize,
+    /// in flight requests per host, hosts with none are removed
+    hosts: HashMap<String, usize>,
+    waiting: Vec<Waiter>,
+    next_id: u64,
+    sent: u64,
+    timed_out: u64,
+    total_wait: Duration,
+    longest_wait: Duration,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    priority: Priority,
+    host: String,
+    since: Instant,
+    /// a slot was taken for it, it only has to notice
+    granted: bool,
+    waker: Option<Waker>,
+}
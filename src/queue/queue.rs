@@ -0,0 +1,237 @@
+use super::{Priority, QueueState, QueueStats, RequestQueue, Waiter};
+use crate::EchoError;
+
+use std::cmp::Reverse;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::MutexGuard;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+impl RequestQueue {
+    /// At most `max_in_flight` requests at once.
+    ///
+    /// Panics if `max_in_flight` is zero, no request would ever leave the queue.
+    pub fn new(max_in_flight: usize) -> Self {
+        assert!(
+            max_in_flight > 0,
+            "a request queue needs at least one request in flight"
+        );
+        RequestQueue {
+            max_in_flight: Some(max_in_flight),
+            ..Default::default()
+        }
+    }
+
+    /// At most `max_per_host` requests at once to any single host,
+    /// use `RequestQueue::default().max_per_host(..)` for no overall cap.
+    ///
+    /// Panics if `max_per_host` is zero, like `new`.
+    pub fn max_per_host(mut self, max_per_host: usize) -> Self {
+        assert!(
+            max_per_host > 0,
+            "a request queue needs at least one request in flight"
+        );
+        self.max_per_host = Some(max_per_host);
+        self
+    }
+
+    /// Requests waiting longer than this fail with `EchoError::QueueTimeout`.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let state = self.state();
+        QueueStats {
+            in_flight: state.in_flight,
+            queued: state
+                .waiting
+                .iter()
+                .filter(|waiter| !waiter.granted)
+                .count(),
+            sent: state.sent,
+            timed_out: state.timed_out,
+            total_wait: state.total_wait,
+            longest_wait: state.longest_wait,
+            oldest_waiting: state
+                .waiting
+                .iter()
+                .filter(|waiter| !waiter.granted)
+                .map(|waiter| waiter.since.elapsed())
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Waits for a slot for a request to `host`, returning it and how long it took.
+    pub(crate) async fn enter(
+        &self,
+        host: &str,
+        priority: Priority,
+    ) -> Result<(QueuePermit<'_>, Duration), EchoError> {
+        let since = Instant::now();
+        let id = {
+            let mut state = self.state();
+            if self.has_room(&state, host) {
+                self.take(&mut state, host);
+                state.sent += 1;
+                return Ok((self.permit(host), Duration::ZERO));
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            state.waiting.push(Waiter {
+                id,
+                priority,
+                host: host.to_string(),
+                since,
+                granted: false,
+                waker: None,
+            });
+            id
+        };
+
+        let mut turn = Turn {
+            queue: self,
+            id,
+            done: false,
+        };
+        let granted = match self.max_wait {
+            Some(max_wait) => tokio::select! {
+                biased;
+                _ = &mut turn => true,
+                _ = sleep(max_wait) => false,
+            },
+            None => {
+                (&mut turn).await;
+                true
+            }
+        };
+        // gives the slot back, should it have been granted just now
+        drop(turn);
+
+        let waited = since.elapsed();
+        let mut state = self.state();
+        if !granted {
+            state.timed_out += 1;
+            return Err(EchoError::QueueTimeout(waited));
+        }
+        state.sent += 1;
+        state.total_wait += waited;
+        state.longest_wait = state.longest_wait.max(waited);
+        Ok((self.permit(host), waited))
+    }
+
+    fn permit(&self, host: &str) -> QueuePermit<'_> {
+        QueuePermit {
+            queue: self,
+            host: host.to_string(),
+        }
+    }
+
+    fn has_room(&self, state: &QueueState, host: &str) -> bool {
+        let in_host = state.hosts.get(host).copied().unwrap_or(0);
+        self.max_in_flight.is_none_or(|max| state.in_flight < max)
+            && self.max_per_host.is_none_or(|max| in_host < max)
+    }
+
+    fn take(&self, state: &mut QueueState, host: &str) {
+        state.in_flight += 1;
+        *state.hosts.entry(host.to_string()).or_default() += 1;
+    }
+
+    /// Gives a slot back and hands out the freed room, highest priority first.
+    fn release(&self, state: &mut QueueState, host: &str) {
+        state.in_flight -= 1;
+        if let Some(in_host) = state.hosts.get_mut(host) {
+            *in_host -= 1;
+            if *in_host == 0 {
+                state.hosts.remove(host);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..state.waiting.len())
+            .filter(|&index| !state.waiting[index].granted)
+            .collect();
+        order.sort_by_key(|&index| {
+            let waiter = &state.waiting[index];
+            (Reverse(waiter.priority), waiter.id)
+        });
+        for index in order {
+            let host = state.waiting[index].host.clone();
+            if !self.has_room(state, &host) {
+                continue;
+            }
+            self.take(state, &host);
+            let waiter = &mut state.waiting[index];
+            waiter.granted = true;
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A slot in the queue, given back when dropped.
+#[derive(Debug)]
+pub(crate) struct QueuePermit<'q> {
+    queue: &'q RequestQueue,
+    host: String,
+}
+
+impl Drop for QueuePermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state();
+        self.queue.release(&mut state, &self.host);
+    }
+}
+
+/// Resolves once a waiter was granted a slot. Dropped before that, it leaves the queue.
+struct Turn<'q> {
+    queue: &'q RequestQueue,
+    id: u64,
+    done: bool,
+}
+
+impl Future for Turn<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.queue.state();
+        let Some(index) = state.waiting.iter().position(|waiter| waiter.id == self.id) else {
+            return Poll::Pending;
+        };
+        if state.waiting[index].granted {
+            state.waiting.remove(index);
+            drop(state);
+            self.done = true;
+            return Poll::Ready(());
+        }
+        state.waiting[index].waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut state = self.queue.state();
+        let Some(index) = state.waiting.iter().position(|waiter| waiter.id == self.id) else {
+            return;
+        };
+        let waiter = state.waiting.remove(index);
+        if waiter.granted {
+            self.queue.release(&mut state, &waiter.host);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[allow(clippy::module_inception)]
+pub mod rate_limit;
+
+type KeyFn = dyn Fn(&reqwest::Request) -> String + Send + Sync;
+
+/// `rate_limit`, a client side token bucket that keeps requests within a quota.
+///
+/// Every request takes a token, tokens come back at the `RateLimit`'s pace, up to its `burst`.
+/// Without a token the request waits for one, or fails right away with `EchoError::RateLimited`
+/// after `fail_fast()`. Buckets are per host unless keyed otherwise.
+/// Responses served by the `cache` don't take a token.
+/// ```rs
+/// let limiter = RateLimiter::new(RateLimit::per_second(10).burst(20))
+///     .adaptive(); // slow down on `Retry-After` and `X-RateLimit-Remaining: 0`
+///
+/// let echo = Echo::configure(Some(RequestConfig {
+///     rate_limit: Some(Arc::new(limiter)),
+///     ..Default::default()
+/// }));
+/// ```
+/// Share one limiter between instances to share the quota.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    key: BucketKey,
+    fail_fast: bool,
+    adaptive: bool,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+/// How many requests are allowed, and how many may go at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+    /// tokens a bucket holds when full, the default is `requests`
+    pub burst: u32,
+}
+
+/// What the requests are counted by.
+#[derive(Clone, Default)]
+enum BucketKey {
+    /// `host:port`
+    #[default]
+    Host,
+    /// one bucket for everything
+    Instance,
+    Custom(Arc<KeyFn>),
+}
+
+/// A token taken from a bucket by a request that may still have to wait for it.
+pub(crate) struct Reservation<'l> {
+    limiter: &'l RateLimiter,
+    key: &'l str,
+    /// how long until the token is actually there
+    pub wait: Duration,
+    /// whether the request went out with it
+    spent: bool,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// negative while requests wait for tokens that haven't come back yet
+    tokens: f64,
+    /// when `tokens` was last brought up to date, in the future while the server asked to pause
+    updated: Instant,
+}
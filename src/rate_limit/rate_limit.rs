@@ -0,0 +1,301 @@
+use super::{Bucket, BucketKey, RateLimit, RateLimiter, Reservation};
+use crate::{Echo, EchoError};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// `X-RateLimit-Reset` values above this are unix timestamps rather than seconds to wait.
+const RESET_TIMESTAMP_THRESHOLD: f64 = 1_000_000_000.0;
+
+impl RateLimit {
+    /// `requests` every `per`, all of which may go at once.
+    ///
+    /// Panics if `requests` or `per` is zero, a bucket like that never refills.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        let limit = RateLimit {
+            requests,
+            per,
+            burst: requests,
+        };
+        limit.assert_valid();
+        limit
+    }
+
+    pub fn per_second(requests: u32) -> Self {
+        RateLimit::new(requests, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        RateLimit::new(requests, Duration::from_secs(60))
+    }
+
+    /// At most `burst` requests in a row, when the bucket is full.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// A leaky bucket: requests leave at an even pace, one every `per / requests`, never in a burst.
+    pub fn leaky(self) -> Self {
+        self.burst(1)
+    }
+
+    fn assert_valid(&self) {
+        assert!(
+            self.requests > 0 && !self.per.is_zero(),
+            "a rate limit needs at least one request per a non-zero duration"
+        );
+    }
+
+    /// How long it takes for `tokens` to come back.
+    fn time_for(&self, tokens: f64) -> Duration {
+        if tokens <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::try_from_secs_f64(tokens / self.rate()).unwrap_or(Duration::MAX)
+    }
+
+    /// Tokens coming back per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.requests) / self.per.as_secs_f64()
+    }
+
+    fn capacity(&self) -> f64 {
+        f64::from(self.burst.max(1))
+    }
+}
+
+impl RateLimiter {
+    /// Buckets per host, requests wait for their token.
+    ///
+    /// Panics if the limit allows zero requests or has a zero `per`, see `RateLimit::new`.
+    pub fn new(limit: RateLimit) -> Self {
+        limit.assert_valid();
+        RateLimiter {
+            limit,
+            key: BucketKey::default(),
+            fail_fast: false,
+            adaptive: false,
+            buckets: Default::default(),
+        }
+    }
+
+    /// One bucket for every request, whatever the host.
+    pub fn per_instance(mut self) -> Self {
+        self.key = BucketKey::Instance;
+        self
+    }
+
+    /// Buckets by a key of your own, e.g. the api key a request is sent with.
+    /// ```rs
+    /// let limiter = RateLimiter::new(RateLimit::per_minute(60)).key_by(|request| {
+    ///     let key = request.headers().get("x-api-key");
+    ///     key.and_then(|key| key.to_str().ok()).unwrap_or_default().to_string()
+    /// });
+    /// ```
+    pub fn key_by<F>(mut self, key: F) -> Self
+    where
+        F: Fn(&reqwest::Request) -> String + Send + Sync + 'static,
+    {
+        self.key = BucketKey::Custom(Arc::new(key));
+        self
+    }
+
+    /// Fail with `EchoError::RateLimited` instead of waiting for a token.
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Follow the server's lead: `X-RateLimit-Remaining` (or `RateLimit-Remaining`) caps the tokens,
+    /// `Retry-After` on a `429` / `503`, or `X-RateLimit-Reset` once nothing remains,
+    /// holds the bucket's requests back until then.
+    pub fn adaptive(mut self) -> Self {
+        self.adaptive = true;
+        self
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Requests `key` may send right now, a full bucket for keys not seen yet.
+    pub fn available(&self, key: &str) -> u32 {
+        let now = Instant::now();
+        let mut buckets = self.buckets();
+        let bucket = self.bucket(&mut buckets, key, now);
+        if bucket.updated > now {
+            return 0;
+        }
+        bucket.tokens.max(0.0) as u32
+    }
+
+    /// The bucket `request` is counted in.
+    pub(crate) fn key(&self, request: &reqwest::Request) -> String {
+        match &self.key {
+            BucketKey::Host => Echo::host_key(request.url()),
+            BucketKey::Instance => "*".to_string(),
+            BucketKey::Custom(key) => key(request),
+        }
+    }
+
+    /// Takes a token from `key`'s bucket, the reservation says how long to wait until it's
+    /// actually there and gives it back if dropped before `spend()`.
+    pub(crate) fn acquire<'l>(&'l self, key: &'l str) -> Result<Reservation<'l>, EchoError> {
+        let now = Instant::now();
+        let mut buckets = self.buckets();
+        let bucket = self.bucket(&mut buckets, key, now);
+        let paused = bucket.updated.saturating_duration_since(now);
+
+        if self.fail_fast {
+            if paused.is_zero() && bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                return Ok(Reservation::new(self, key, Duration::ZERO));
+            }
+            return Err(EchoError::RateLimited {
+                key: key.to_string(),
+                retry_after: paused.saturating_add(self.limit.time_for(1.0 - bucket.tokens)),
+            });
+        }
+
+        // the token is reserved now, so waiting requests go in the order they came
+        bucket.tokens -= 1.0;
+        let wait = paused.saturating_add(self.limit.time_for(-bucket.tokens));
+        Ok(Reservation::new(self, key, wait))
+    }
+
+    /// Puts a token that was taken but not used back into `key`'s bucket.
+    fn refund(&self, key: &str) {
+        let mut buckets = self.buckets();
+        let bucket = self.bucket(&mut buckets, key, Instant::now());
+        bucket.tokens = (bucket.tokens + 1.0).min(self.limit.capacity());
+    }
+
+    /// Lets the rate limit headers of a response adjust `key`'s bucket, when `adaptive`.
+    pub(crate) fn observe(&self, key: &str, status: u16, headers: &HeaderMap) {
+        if !self.adaptive {
+            return;
+        }
+        let remaining = header_number(headers, &["x-ratelimit-remaining", "ratelimit-remaining"]);
+        let pause = match status {
+            429 | 503 => retry_after(headers),
+            _ => None,
+        }
+        .or_else(|| {
+            let reset = header_number(headers, &["x-ratelimit-reset", "ratelimit-reset"]);
+            reset.filter(|_| remaining == Some(0.0)).map(reset_delay)
+        });
+        if remaining.is_none() && pause.is_none() {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets();
+        let bucket = self.bucket(&mut buckets, key, now);
+        if let Some(remaining) = remaining {
+            bucket.tokens = bucket.tokens.min(remaining);
+        }
+        if let Some(until) = pause.and_then(|pause| now.checked_add(pause)) {
+            if until > bucket.updated {
+                // nothing goes before `until`, then a single request to see where things stand
+                bucket.tokens = bucket.tokens.min(0.0) + 1.0;
+                bucket.updated = until;
+            }
+        }
+    }
+
+    /// `key`'s bucket, topped up to `now`.
+    fn bucket<'b>(
+        &self,
+        buckets: &'b mut HashMap<String, Bucket>,
+        key: &str,
+        now: Instant,
+    ) -> &'b mut Bucket {
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            tokens: self.limit.capacity(),
+            updated: now,
+        });
+        if now > bucket.updated {
+            let refilled = (now - bucket.updated).as_secs_f64() * self.limit.rate();
+            bucket.tokens = (bucket.tokens + refilled).min(self.limit.capacity());
+            bucket.updated = now;
+        }
+        bucket
+    }
+
+    fn buckets(&self) -> MutexGuard<'_, HashMap<String, Bucket>> {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<'l> Reservation<'l> {
+    fn new(limiter: &'l RateLimiter, key: &'l str, wait: Duration) -> Self {
+        Reservation {
+            limiter,
+            key,
+            wait,
+            spent: false,
+        }
+    }
+
+    /// The request goes out, the token is used up.
+    pub(crate) fn spend(mut self) {
+        self.spent = true;
+    }
+}
+
+impl Drop for Reservation<'_> {
+    /// A request cancelled while it waited for its token hands the token back.
+    fn drop(&mut self) {
+        if !self.spent {
+            self.limiter.refund(self.key);
+        }
+    }
+}
+
+impl fmt::Debug for BucketKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BucketKey::Host => f.write_str("Host"),
+            BucketKey::Instance => f.write_str("Instance"),
+            BucketKey::Custom(_) => f.write_str("Custom(Fn)"),
+        }
+    }
+}
+
+/// The first of `names` holding a non-negative number, lists like `"0, 100"` count by their first entry.
+fn header_number(headers: &HeaderMap, names: &[&str]) -> Option<f64> {
+    names.iter().find_map(|name| {
+        let value = headers.get(*name)?.to_str().ok()?;
+        let number: f64 = value.split(',').next()?.trim().parse().ok()?;
+        (number.is_finite() && number >= 0.0).then_some(number)
+    })
+}
+
+/// `Retry-After`, in seconds or as an http date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// A reset header is either seconds to wait (`RateLimit-Reset`, most apis)
+/// or a unix timestamp (e.g. GitHub's `X-RateLimit-Reset`).
+fn reset_delay(reset: f64) -> Duration {
+    let reset = Duration::try_from_secs_f64(reset).unwrap_or(Duration::MAX);
+    if reset.as_secs_f64() > RESET_TIMESTAMP_THRESHOLD {
+        return UNIX_EPOCH
+            .checked_add(reset)
+            .and_then(|reset| reset.duration_since(SystemTime::now()).ok())
+            .unwrap_or_default();
+    }
+    reset
+}
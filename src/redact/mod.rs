@@ -0,0 +1,32 @@
+#[allow(clippy::module_inception)]
+pub mod redact;
+
+pub(crate) use redact::{is_sensitive, redact, redact_headers, redact_text};
+
+/// What secrets are replaced with.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Keys whose values are never shown, matched case-insensitively against the last words
+/// of a key, so `client_secret` and `set-cookie` are covered too.
+const SENSITIVE_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "secret_key",
+    "token",
+    "authorization",
+    "api_key",
+    "apikey",
+    "access_key",
+    "private_key",
+    "cookie",
+    "session",
+    "session_id",
+    "sessionid",
+    "credit_card",
+    "card_number",
+    "ssn",
+];
+
+/// Keys ending like `SENSITIVE_KEYS` that are still no secret, e.g. pagination cursors.
+const NOT_SENSITIVE_KEYS: &[&str] = &["page_token", "next_token", "continuation_token"];
@@ -0,0 +1,175 @@
+use super::{NOT_SENSITIVE_KEYS, REDACTED, SENSITIVE_KEYS};
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::Value;
+
+/// Replaces the values of sensitive keys with `[REDACTED]`, at any depth.
+pub(crate) fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) {
+                    mask(value);
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Hides a secret without changing its JSON type: strings become `[REDACTED]`,
+/// numbers `0`, and objects and arrays are masked element by element.
+fn mask(value: &mut Value) {
+    match value {
+        Value::String(_) => *value = Value::String(REDACTED.to_string()),
+        Value::Number(_) => *value = Value::from(0),
+        Value::Object(map) => map.values_mut().for_each(mask),
+        Value::Array(values) => values.iter_mut().for_each(mask),
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+/// Replaces the values of sensitive headers with `[REDACTED]`, every one of a repeated header.
+pub(crate) fn redact_headers(headers: &mut HeaderMap) {
+    for (name, value) in headers.iter_mut() {
+        if is_sensitive(name.as_str()) {
+            *value = HeaderValue::from_static(REDACTED);
+        }
+    }
+}
+
+/// Whether a JSON key, param or header name looks like it holds a secret, i.e. its last words
+/// are one of `SENSITIVE_KEYS`. `X-Api-Key`, `apiKey` and `api_key` are treated the same,
+/// while `max_tokens` or `session_count` are not secrets.
+pub(crate) fn is_sensitive(key: &str) -> bool {
+    let words = words(key);
+    let ends_with = |keys: &[&str]| {
+        keys.iter().any(|sensitive| {
+            let sensitive = sensitive.split('_');
+            let count = sensitive.clone().count();
+            count <= words.len() && words[words.len() - count..].iter().eq(sensitive)
+        })
+    };
+    ends_with(SENSITIVE_KEYS) && !ends_with(NOT_SENSITIVE_KEYS)
+}
+
+/// The lowercased words of a key, split at `_`, `-`, `.` and camelCase humps.
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous: Option<char> = None;
+    for c in key.chars() {
+        if matches!(c, '_' | '-' | '.' | ' ') {
+            words.push(std::mem::take(&mut word));
+        } else {
+            let hump = c.is_uppercase()
+                && previous
+                    .is_some_and(|previous| previous.is_lowercase() || previous.is_ascii_digit());
+            if hump {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        previous = Some(c);
+    }
+    words.push(word);
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// Masks the values of sensitive keys in text that isn't JSON: `key=value` pairs as in forms
+/// and query strings, `key: value` or `"key": "value"` as in YAML or malformed JSON,
+/// and `<key>value</key>` elements.
+pub(crate) fn redact_text(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if !is_key_byte(bytes[pos]) || (pos > 0 && is_key_byte(bytes[pos - 1])) {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < bytes.len() && is_key_byte(bytes[pos]) {
+            pos += 1;
+        }
+        let key = &text[start..pos];
+        let Some((value_start, value_end)) = value_after(text, start, pos) else {
+            continue;
+        };
+        if value_start < value_end && is_sensitive(key) {
+            redacted.push_str(&text[copied..value_start]);
+            redacted.push_str(REDACTED);
+            copied = value_end;
+            pos = value_end;
+        }
+    }
+    redacted.push_str(&text[copied..]);
+    redacted
+}
+
+fn is_key_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.')
+}
+
+/// Where the value of the key at `start..end` is, if it's followed by one.
+fn value_after(text: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let before = start.checked_sub(1).map(|before| bytes[before]);
+
+    // `<key attr="..">value</key>`
+    if before == Some(b'<') {
+        let value_start = end + text[end..].find('>')? + 1;
+        let value_end = text[value_start..]
+            .find('<')
+            .map_or(text.len(), |len| value_start + len);
+        return Some((value_start, value_end));
+    }
+
+    let mut pos = end;
+    // the closing quote of `"key"`
+    if let Some(quote @ (b'"' | b'\'')) = before {
+        if bytes.get(pos) == Some(&quote) {
+            pos += 1;
+        }
+    }
+    while bytes
+        .get(pos)
+        .is_some_and(|byte| *byte == b' ' || *byte == b'\t')
+    {
+        pos += 1;
+    }
+    let separator = *bytes.get(pos).filter(|byte| matches!(byte, b'=' | b':'))?;
+    pos += 1;
+    while bytes
+        .get(pos)
+        .is_some_and(|byte| *byte == b' ' || *byte == b'\t')
+    {
+        pos += 1;
+    }
+
+    match bytes.get(pos) {
+        Some(&quote @ (b'"' | b'\'')) => {
+            let value_start = pos + 1;
+            let value_end = text[value_start..]
+                .find(quote as char)
+                .map_or(text.len(), |len| value_start + len);
+            Some((value_start, value_end))
+        }
+        // `key=value` ends with the pair, `key: value` with the line, e.g. `Authorization: Bearer ..`
+        _ => {
+            let value_end = text[pos..]
+                .find(|c: char| match separator {
+                    b'=' => c.is_whitespace() || "&,;<>()[]{}\"'".contains(c),
+                    _ => c == '\n' || c == '\r' || ",;<>[]{}\"'".contains(c),
+                })
+                .map_or(text.len(), |len| pos + len);
+            Some((pos, value_end))
+        }
+    }
+}
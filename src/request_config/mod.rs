@@ -1,10 +1,23 @@
+#[allow(clippy::module_inception)]
 pub mod request_config;
 
 use reqwest::Method;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::adapter::Adapter;
+use crate::base_urls::BaseUrls;
+use crate::cache::{CacheMode, CacheStore};
+use crate::cancel_token::CancelToken;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::codec::Codec;
 use crate::headers::Headers;
+use crate::hedging::Hedging;
+use crate::params::{Params, ParamsSerializer};
+use crate::queue::{Priority, RequestQueue};
+use crate::rate_limit::RateLimiter;
+use crate::single_flight::SingleFlight;
+use crate::transform::{TransformRequest, TransformResponse};
 
 /// Request Configuration
 #[derive(Debug, Clone)]
@@ -20,25 +33,105 @@ pub struct RequestConfig<'a> {
     /// to methods of that instance.
     pub base_url: Option<String>,
 
+    /// `base_urls` spreads requests with a relative `url` over several base urls, see `BaseUrls`.
+    /// It takes precedence over `base_url`, which responses set to the one that was picked.
+    /// Idempotent requests that can't connect are retried on another one.
+    pub base_urls: Option<Arc<BaseUrls>>,
+
+    /// `allow_absolute_urls` decides whether an absolute `url` may bypass `base_url`.
+    /// When `false`, absolute urls are rejected on instances with a `base_url`.
+    /// the default value is `true`
+    pub allow_absolute_urls: Option<bool>,
+
+    /// `path_params` fill the `{placeholders}` in `url`, each value is percent-encoded
+    /// e.g. `/users/{id}/posts/{post_id}`
+    /// every placeholder needs a value and every value needs a placeholder
+    pub path_params: Option<Params>,
+
     /// `headers` are custom headers to be sent
     pub headers: Option<Headers<'a>>,
 
     /// `params` are the URL parameters to be sent with the request
-    /// Must be a plain object or a URLSearchParams object
-    pub params: Option<HashMap<String, String>>,
+    /// Build them from any `Serialize` struct or map with `Params::from_serialize`,
+    /// or key by key with `Params::insert` / `Params::append`
+    pub params: Option<Params>,
+
+    /// `params_serializer` controls how `params` are written to the query string,
+    /// e.g. the array format or a completely custom serializer
+    pub params_serializer: Option<ParamsSerializer>,
 
     /// `data` is the data to be sent as the request body
     /// Only applicable for request methods 'PUT', 'POST', 'DELETE , and 'PATCH' (soon &trade;)
-    /// When no `transformRequest` (soon &trade;) is set, must be of one of the following types:
-    /// - string, plain object, ArrayBuffer, ArrayBufferView, URLSearchParams
-    /// - Browser only: FormData, File, Blob
-    /// - Node only: Stream, Buffer, FormData (form-data package)
+    /// It is encoded with `codec`, then handed to `transform_request`
     pub data: Option<Value>,
 
+    /// `codec` encodes `data` and decodes responses, it is sent as `Content-Type` and `Accept`.
+    /// Responses in any other format the crate understands are still decoded by their `Content-Type`.
+    /// the default is `JsonCodec`, the others need their cargo feature:
+    /// `msgpack`, `cbor`, `yaml` or `xml`
+    pub codec: Option<Arc<dyn Codec>>,
+
+    /// `transform_request` allows changes to the request body and headers before they are sent,
+    /// the transforms run in order on the encoded body, so they see exactly what goes on the wire
+    pub transform_request: Option<Vec<TransformRequest>>,
+
+    /// `transform_response` allows changes to the response body and headers before decoding,
+    /// for typed and unknown responses alike, the transforms run in order on the raw bytes
+    pub transform_response: Option<Vec<TransformResponse>>,
+
     /// `timeout` specifies the number of milliseconds before the request times out.
     /// If the request takes longer than `timeout`, the request will be aborted.
     /// the default value is `0` (no timeout)
     pub timeout: Option<u64>,
+
+    /// `max_redirects` defines the maximum number of redirects to follow.
+    /// If set to 0, no redirects will be followed and the 3xx response is returned as is.
+    /// the default value is `10`
+    pub max_redirects: Option<usize>,
+
+    /// `adapter` sends the request, see `Adapter`. the default sends it with reqwest
+    pub adapter: Option<Arc<dyn Adapter>>,
+
+    /// `cache` keeps `GET` and `HEAD` responses as allowed by their `Cache-Control`,
+    /// see `CacheStore`. the default is no cache
+    pub cache: Option<Arc<dyn CacheStore>>,
+
+    /// `cache_mode` decides how the request uses the `cache`, see `CacheMode`.
+    /// the default is `CacheMode::Default`
+    pub cache_mode: Option<CacheMode>,
+
+    /// `rate_limit` holds requests back, or fails them with `EchoError::RateLimited`,
+    /// once they go over the limiter's quota, see `RateLimiter`. the default is no limit
+    pub rate_limit: Option<Arc<RateLimiter>>,
+
+    /// `queue` caps the requests in flight at once, the others wait their turn, see `RequestQueue`.
+    /// the default is no cap
+    pub queue: Option<Arc<RequestQueue>>,
+
+    /// `priority` is the request's place in the `queue`, higher priorities go first.
+    /// the default is `Priority::Normal`
+    pub priority: Option<Priority>,
+
+    /// `circuit_breaker` fails requests to a failing upstream right away with `EchoError::CircuitOpen`,
+    /// instead of letting every one of them wait for it, see `CircuitBreaker`. the default is none
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+
+    /// `hedging` sends a copy of an idempotent request that's slow to answer and takes
+    /// the first successful response, see `Hedging`. the default is no hedging
+    pub hedging: Option<Arc<Hedging>>,
+
+    /// `single_flight` lets concurrent identical `GET` requests share one network call,
+    /// see `SingleFlight`. the default is none
+    pub single_flight: Option<Arc<SingleFlight>>,
+
+    /// `dedupe_key` replaces what `single_flight` tells requests apart by:
+    /// requests with the same key share a response, whatever their url and headers.
+    /// only read from the per-request config, one set on the instance is ignored
+    pub dedupe_key: Option<String>,
+
+    /// `cancel_token` aborts the request when cancelled, see `CancelToken`.
+    /// One token can be shared between any number of requests.
+    pub cancel_token: Option<CancelToken>,
     //
     // (soon &trade;)
     // `xsrfCookieName` is the name of the cookie to use as a value for xsrf token
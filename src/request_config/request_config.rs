@@ -1,4 +1,5 @@
 use super::{RequestConfig, ResponseType};
+
 use reqwest::Method;
 
 impl<'a> Default for RequestConfig<'a> {
@@ -7,12 +8,112 @@ impl<'a> Default for RequestConfig<'a> {
             url: None,
             method: Method::GET,
             base_url: None,
+            base_urls: None,
+            allow_absolute_urls: None,
+            path_params: None,
             timeout: None,
+            max_redirects: None,
+            adapter: None,
+            cache: None,
+            cache_mode: None,
+            rate_limit: None,
+            queue: None,
+            priority: None,
+            circuit_breaker: None,
+            hedging: None,
+            single_flight: None,
+            dedupe_key: None,
+            cancel_token: None,
             headers: None,
             params: None,
+            params_serializer: None,
             data: None,
+            codec: None,
+            transform_request: None,
+            transform_response: None,
             response_type: ResponseType::Json,
         }
     }
 }
 
+impl<'a> RequestConfig<'a> {
+    /// Layers a per-request config on top of an instance config.
+    ///
+    /// Values set on `overrides` win, `headers` and `params` are combined
+    /// with the per-request entries taking precedence.
+    pub(crate) fn merge(&self, overrides: RequestConfig<'a>) -> RequestConfig<'a> {
+        let headers = match (&self.headers, overrides.headers) {
+            (Some(base), Some(extra)) => {
+                let mut headers = base.clone();
+                headers.headers.extend(extra.headers);
+                Some(headers)
+            }
+            (base, extra) => extra.or_else(|| base.clone()),
+        };
+
+        let params = match (&self.params, overrides.params) {
+            (Some(base), Some(extra)) => {
+                let mut params = base.clone();
+                params.extend(extra);
+                Some(params)
+            }
+            (base, extra) => extra.or_else(|| base.clone()),
+        };
+
+        let path_params = match (&self.path_params, overrides.path_params) {
+            (Some(base), Some(extra)) => {
+                let mut path_params = base.clone();
+                path_params.extend(extra);
+                Some(path_params)
+            }
+            (base, extra) => extra.or_else(|| base.clone()),
+        };
+
+        let response_type = if overrides.response_type == ResponseType::Json {
+            self.response_type
+        } else {
+            overrides.response_type
+        };
+
+        RequestConfig {
+            url: overrides.url.or_else(|| self.url.clone()),
+            method: overrides.method,
+            base_url: overrides.base_url.or_else(|| self.base_url.clone()),
+            base_urls: overrides.base_urls.or_else(|| self.base_urls.clone()),
+            allow_absolute_urls: overrides.allow_absolute_urls.or(self.allow_absolute_urls),
+            path_params,
+            headers,
+            params,
+            params_serializer: overrides
+                .params_serializer
+                .or_else(|| self.params_serializer.clone()),
+            data: overrides.data.or_else(|| self.data.clone()),
+            codec: overrides.codec.or_else(|| self.codec.clone()),
+            transform_request: overrides
+                .transform_request
+                .or_else(|| self.transform_request.clone()),
+            transform_response: overrides
+                .transform_response
+                .or_else(|| self.transform_response.clone()),
+            timeout: overrides.timeout.or(self.timeout),
+            max_redirects: overrides.max_redirects.or(self.max_redirects),
+            adapter: overrides.adapter.or_else(|| self.adapter.clone()),
+            cache: overrides.cache.or_else(|| self.cache.clone()),
+            cache_mode: overrides.cache_mode.or(self.cache_mode),
+            rate_limit: overrides.rate_limit.or_else(|| self.rate_limit.clone()),
+            queue: overrides.queue.or_else(|| self.queue.clone()),
+            priority: overrides.priority.or(self.priority),
+            circuit_breaker: overrides
+                .circuit_breaker
+                .or_else(|| self.circuit_breaker.clone()),
+            hedging: overrides.hedging.or_else(|| self.hedging.clone()),
+            single_flight: overrides
+                .single_flight
+                .or_else(|| self.single_flight.clone()),
+            // per request only, on the instance it would make every request share one response
+            dedupe_key: overrides.dedupe_key,
+            cancel_token: overrides.cancel_token.or_else(|| self.cancel_token.clone()),
+            response_type,
+        }
+    }
+}
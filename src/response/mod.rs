@@ -1,26 +1,126 @@
-use std::ops::Deref;
+#[allow(clippy::module_inception)]
+pub mod response;
 
-use crate::RequestConfig;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Version};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::time::Duration;
 
-#[derive(Debug)]
+use crate::cache::CacheStatus;
+use crate::{EchoError, Headers, Params};
+
+#[derive(Debug, Default)]
 pub struct Response<'a, T> {
     pub data: T,
     pub status: u16,
     pub status_text: String,
-    pub headers: reqwest::header::HeaderMap,
-    pub config: RequestConfig<'a>,
+    pub headers: HeaderMap,
+    /// what the request was sent with, secret looking headers and params redacted
+    pub config: RequestInfo<'a>,
+    /// the url that was requested, with its params, see `url` for where the response actually came from
     pub request: String,
+    /// the final url, after following redirects
+    pub url: String,
+    /// every redirect followed on the way to `url`, in order
+    pub redirects: Vec<Redirect>,
+    pub version: Version,
+    /// the address of the server that answered, when known
+    pub remote_addr: Option<SocketAddr>,
+    /// the `Content-Length` announced by the server
+    pub content_length: Option<u64>,
+    /// whether the response came from the `cache`, `None` when there is none
+    pub cache: Option<CacheStatus>,
+    /// 1 for the first try, more when it failed over to another of the `base_urls`
+    pub attempt: u32,
+    pub timings: Timings,
 }
 
+/// The parts of a request's `RequestConfig` kept on its `Response`,
+/// the rest, like the body and the shared cache, queue or adapter, is left behind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestInfo<'a> {
+    pub method: Method,
+    /// the url as given, see `Response::request` for the full one
+    pub url: Option<String>,
+    /// the base url the url was resolved against, with `base_urls` the one that was picked
+    pub base_url: Option<String>,
+    /// with the values of secret looking ones redacted
+    pub headers: Option<Headers<'a>>,
+    /// with the values of secret looking ones redacted
+    pub params: Option<Params>,
+}
+
+/// How one request of a batch ended, see `Echo::all_settled` and `Echo::stream_all`.
 #[derive(Debug)]
-pub struct ResponseUnknown<'a> {
-    pub inner: Response<'a, serde_json::Value>,
+pub struct Settled<'a, T> {
+    /// where the request was in the batch
+    pub index: usize,
+    pub method: Method,
+    /// the full url the request went to, `Response::request` or the error's `context().url`,
+    /// empty when it failed before it had one
+    pub url: String,
+    pub result: Result<Response<'a, T>, EchoError>,
 }
 
-impl<'a> Deref for ResponseUnknown<'a> {
-    type Target = Response<'a, serde_json::Value>;
+/// Everything about a response but its data, see `Response::into_parts`.
+///
+/// Serializes to plain JSON, e.g. for access logs: headers as a map,
+/// the version as `"HTTP/1.1"` and timings in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseMeta {
+    #[serde(serialize_with = "response::serialize_method")]
+    pub method: Method,
+    pub status: u16,
+    pub status_text: String,
+    /// with the values of secret looking ones, like `Set-Cookie`, redacted
+    #[serde(serialize_with = "response::serialize_headers")]
+    pub headers: HeaderMap,
+    pub request: String,
+    pub url: String,
+    pub redirects: Vec<Redirect>,
+    #[serde(serialize_with = "response::serialize_version")]
+    pub version: Version,
+    pub remote_addr: Option<SocketAddr>,
+    pub content_length: Option<u64>,
+    pub cache: Option<CacheStatus>,
+    pub attempt: u32,
+    pub timings: Timings,
+}
+
+/// A single hop of a redirect chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Redirect {
+    /// the url that answered with a redirect
+    pub url: String,
+    /// `301`, `302`, `303`, `307` or `308`
+    pub status: u16,
+    /// where it pointed to
+    pub location: String,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
+/// How long the request took, measured from the moment it was sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Timings {
+    /// time to first byte: until the headers of the final response arrived, redirects included
+    #[serde(rename = "ttfb_ms", serialize_with = "response::serialize_millis")]
+    pub ttfb: Duration,
+    /// until the body was read and decoded
+    #[serde(rename = "total_ms", serialize_with = "response::serialize_millis")]
+    pub total: Duration,
+    /// time spent in earlier, failed attempts, zero unless the request was retried
+    #[serde(rename = "retries_ms", serialize_with = "response::serialize_millis")]
+    pub retries: Duration,
+    /// time spent waiting in the `queue` before the request was sent, not part of the others
+    #[serde(rename = "queued_ms", serialize_with = "response::serialize_millis")]
+    pub queued: Duration,
+}
+
+/// A response whose shape isn't known up front.
+///
+/// Derefs to `Response<serde_json::Value>`, probe it with `pointer()` and
+/// turn it into a typed `Response<T>` with `json::<T>()` or `into_typed::<T>()`.
+#[derive(Debug, Default)]
+pub struct ResponseUnknown<'a> {
+    pub inner: Response<'a, serde_json::Value>,
 }
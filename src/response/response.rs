@@ -0,0 +1,268 @@
+use super::{RequestInfo, Response, ResponseMeta, ResponseUnknown};
+use crate::echo_errors::decode_error::decode_value;
+use crate::echo_errors::{ErrorContext, ErrorResponse};
+use crate::redact::{is_sensitive, redact, redact_headers, REDACTED};
+use crate::{EchoError, Params, RequestConfig};
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Version};
+use serde::ser::{SerializeMap, Serializer};
+use serde_json::Value;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+impl<'a> RequestInfo<'a> {
+    /// What's kept of `config`, secret looking headers and params redacted.
+    pub(crate) fn new(config: &RequestConfig<'a>) -> Self {
+        let headers = config.headers.clone().map(|mut headers| {
+            for (key, value) in headers.headers.iter_mut() {
+                if is_sensitive(key) {
+                    *value = REDACTED;
+                }
+            }
+            headers
+        });
+
+        let params = config.params.clone().map(|params| {
+            let mut params = Value::Object(params.params);
+            redact(&mut params);
+            match params {
+                Value::Object(params) => Params { params },
+                _ => Params::new(),
+            }
+        });
+
+        RequestInfo {
+            method: config.method.clone(),
+            url: config.url.clone(),
+            base_url: config.base_url.clone(),
+            headers,
+            params,
+        }
+    }
+}
+
+impl<'a> ResponseUnknown<'a> {
+    /// Deserializes a copy of the data into `T`, keeping everything else.
+    /// ```rs
+    /// let res = echo.get_unknown("/users/1").await?;
+    /// if res.pointer("/admin").is_some() {
+    ///     let admin = res.json::<Admin>()?;
+    /// }
+    /// ```
+    pub fn json<T>(&self) -> Result<Response<'a, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let data = decode_value(self.data.clone(), "", self.status, &self.request)?;
+        Ok(Response {
+            data,
+            status: self.status,
+            status_text: self.status_text.clone(),
+            headers: self.headers.clone(),
+            config: self.config.clone(),
+            request: self.request.clone(),
+            url: self.url.clone(),
+            redirects: self.redirects.clone(),
+            version: self.version,
+            remote_addr: self.remote_addr,
+            content_length: self.content_length,
+            cache: self.cache,
+            attempt: self.attempt,
+            timings: self.timings,
+        })
+    }
+
+    /// Same as `json::<T>()`, without copying the data.
+    pub fn into_typed<T>(self) -> Result<Response<'a, T>, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut inner = self.inner;
+        let data = decode_value(inner.data.take(), "", inner.status, &inner.request)?;
+        Ok(inner.map(|_| data))
+    }
+
+    /// Looks up a value by JSON pointer (RFC 6901), e.g. `"/data/0/id"`.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        self.data.pointer(pointer)
+    }
+
+    /// Deserializes the value at `pointer` into `T`.
+    ///
+    /// A missing value is treated as `null`, so asking for an `Option<T>` gives `None`.
+    /// ```rs
+    /// let id: u64 = res.pointer_as("/data/0/id")?;
+    /// let next: Option<String> = res.pointer_as("/meta/next")?;
+    /// ```
+    pub fn pointer_as<T>(&self, pointer: &str) -> Result<T, EchoError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let value = self.pointer(pointer).cloned().unwrap_or(Value::Null);
+        decode_value(value, pointer, self.status, &self.request)
+    }
+
+    /// The underlying `Response<serde_json::Value>`.
+    pub fn into_inner(self) -> Response<'a, Value> {
+        self.inner
+    }
+}
+
+impl<'a, T> Response<'a, T> {
+    /// Transforms the data, keeping everything else.
+    /// ```rs
+    /// let users: Response<Vec<User>> = echo.get::<Page<User>>("/users").await?.map(|page| page.items);
+    /// ```
+    pub fn map<U, F>(self, f: F) -> Response<'a, U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Response {
+            data: f(self.data),
+            status: self.status,
+            status_text: self.status_text,
+            headers: self.headers,
+            config: self.config,
+            request: self.request,
+            url: self.url,
+            redirects: self.redirects,
+            version: self.version,
+            remote_addr: self.remote_addr,
+            content_length: self.content_length,
+            cache: self.cache,
+            attempt: self.attempt,
+            timings: self.timings,
+        }
+    }
+
+    /// Splits the response into its metadata and data, the request info is dropped.
+    /// Secret looking headers are redacted in the metadata, like in `config`.
+    /// ```rs
+    /// let (meta, user) = echo.get::<User>("/users/1").await?.into_parts();
+    /// log::info!("{}", serde_json::to_string(&meta)?);
+    /// ```
+    pub fn into_parts(self) -> (ResponseMeta, T) {
+        let mut headers = self.headers;
+        redact_headers(&mut headers);
+        let meta = ResponseMeta {
+            method: self.config.method,
+            status: self.status,
+            status_text: self.status_text,
+            headers,
+            request: self.request,
+            url: self.url,
+            redirects: self.redirects,
+            version: self.version,
+            remote_addr: self.remote_addr,
+            content_length: self.content_length,
+            cache: self.cache,
+            attempt: self.attempt,
+            timings: self.timings,
+        };
+        (meta, self.data)
+    }
+
+    /// `2xx`
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// `4xx`
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.status)
+    }
+
+    /// `5xx`
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.status)
+    }
+
+    /// Turns a non-2xx response into an `EchoError::Status`, which keeps the status and headers.
+    /// ```rs
+    /// let res = echo.get_unknown("/users/1").await?.error_for_status()?;
+    /// ```
+    pub fn error_for_status(self) -> Result<Self, EchoError> {
+        if self.is_success() {
+            return Ok(self);
+        }
+
+        let context = ErrorContext {
+            method: self.config.method.clone(),
+            url: self.url.clone(),
+            attempt: self.attempt,
+            response: Some(ErrorResponse {
+                status: self.status,
+                status_text: self.status_text.clone(),
+                headers: self.headers.clone(),
+            }),
+        };
+        Err(EchoError::Status(self.status).with_context(context))
+    }
+}
+
+impl<'a> Response<'a, ()> {
+    /// Fills in the decoded body of a response built by `Echo::response_head`.
+    pub(crate) fn finish<T>(mut self, data: T, started: Instant) -> Response<'a, T> {
+        self.timings.total = started.elapsed();
+        self.map(|_| data)
+    }
+}
+
+impl<'a> Deref for ResponseUnknown<'a> {
+    type Target = Response<'a, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for ResponseUnknown<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<'a> From<Response<'a, Value>> for ResponseUnknown<'a> {
+    fn from(inner: Response<'a, Value>) -> Self {
+        ResponseUnknown { inner }
+    }
+}
+
+pub(crate) fn serialize_method<S: Serializer>(
+    method: &Method,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(method.as_str())
+}
+
+/// Repeated headers are joined with `, `, values that aren't utf-8 are written lossily.
+pub(crate) fn serialize_headers<S: Serializer>(
+    headers: &HeaderMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(headers.keys_len()))?;
+    for name in headers.keys() {
+        let values: Vec<_> = headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+            .collect();
+        map.serialize_entry(name.as_str(), &values.join(", "))?;
+    }
+    map.end()
+}
+
+pub(crate) fn serialize_version<S: Serializer>(
+    version: &Version,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:?}", version))
+}
+
+pub(crate) fn serialize_millis<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
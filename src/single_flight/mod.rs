@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::{StatusCode, Version};
+use serde::Serialize;
+use url::Url;
+
+use crate::cache::CacheStatus;
+
+#[allow(clippy::module_inception)]
+pub mod single_flight;
+
+/// `single_flight`, lets concurrent identical `GET` requests share one network call.
+///
+/// Requests are identical when they have the same full url, `params` included, and the same
+/// values for the policy's headers (`Accept`, `Authorization` and `Cookie` by default), or when
+/// they were given the same `dedupe_key`. The first one is sent, the others wait for its response
+/// and decode their own copy of it. When it fails, or is cancelled, the waiting ones are sent
+/// on their own: errors aren't shared.
+/// ```rs
+/// let echo = Echo::configure(Some(RequestConfig {
+///     single_flight: Some(Arc::new(SingleFlight::new())),
+///     ..Default::default()
+/// }));
+///
+/// // a single request goes out
+/// let (a, b) = tokio::join!(
+///     echo.get::<Settings>("/config"),
+///     echo.get::<Settings>("/config"),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct SingleFlight {
+    /// request headers that tell requests apart
+    headers: Vec<HeaderName>,
+    state: Mutex<SingleFlightState>,
+}
+
+/// A snapshot of a `SingleFlight`, see `SingleFlight::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SingleFlightStats {
+    /// requests sent right now that others can join
+    pub in_flight: usize,
+    /// requests that went out
+    pub sent: u64,
+    /// requests that got the response of another one instead
+    pub shared: u64,
+}
+
+#[derive(Debug, Default)]
+struct SingleFlightState {
+    flights: HashMap<String, Arc<Flight>>,
+    sent: u64,
+    shared: u64,
+}
+
+/// A request in flight, with the ones waiting for its response.
+#[derive(Debug, Default)]
+struct Flight {
+    state: Mutex<FlightState>,
+}
+
+#[derive(Debug)]
+enum FlightState {
+    Pending(Vec<Waker>),
+    /// `None` when the request failed or was cancelled
+    Done(Option<SharedResponse>),
+}
+
+impl Default for FlightState {
+    fn default() -> Self {
+        FlightState::Pending(Vec::new())
+    }
+}
+
+/// A response read in full, so every request of a flight gets its own copy.
+#[derive(Debug, Clone)]
+pub(crate) struct SharedResponse {
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    pub url: Url,
+    pub body: Bytes,
+    pub cache: Option<CacheStatus>,
+}
@@ -0,0 +1,164 @@
+use super::{
+    Flight, FlightState, SharedResponse, SingleFlight, SingleFlightState, SingleFlightStats,
+};
+
+use reqwest::header::{HeaderName, ACCEPT, AUTHORIZATION, COOKIE};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+impl SingleFlight {
+    /// Tells requests apart by full url, `Accept`, `Authorization` and `Cookie`.
+    pub fn new() -> Self {
+        SingleFlight {
+            headers: vec![ACCEPT, AUTHORIZATION, COOKIE],
+            state: Mutex::new(SingleFlightState::default()),
+        }
+    }
+
+    /// Replaces the request headers that tell requests apart, e.g. to add `Accept-Language`.
+    pub fn key_headers<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.headers = headers.into_iter().collect();
+        self
+    }
+
+    pub fn stats(&self) -> SingleFlightStats {
+        let state = self.state();
+        SingleFlightStats {
+            in_flight: state.flights.len(),
+            sent: state.sent,
+            shared: state.shared,
+        }
+    }
+
+    /// The key `request` is deduplicated by, unless it has its own `dedupe_key`.
+    pub(crate) fn key(&self, request: &reqwest::Request) -> String {
+        let mut key = format!("{} {}", request.method(), request.url());
+        for name in &self.headers {
+            for value in request.headers().get_all(name) {
+                key.push_str(&format!(
+                    "\n{}: {}",
+                    name,
+                    String::from_utf8_lossy(value.as_bytes())
+                ));
+            }
+        }
+        key
+    }
+
+    /// Sends the request under `key`, or waits for the one already in flight.
+    pub(crate) fn join(&self, key: String) -> Joined<'_> {
+        let mut state = self.state();
+        if let Some(flight) = state.flights.get(&key) {
+            let flight = flight.clone();
+            state.shared += 1;
+            return Joined::Follower(FlightWait { flight });
+        }
+
+        let flight = Arc::new(Flight::default());
+        state.flights.insert(key.clone(), flight.clone());
+        state.sent += 1;
+        Joined::Leader(FlightLeader {
+            single_flight: self,
+            key,
+            flight,
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, SingleFlightState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for SingleFlight {
+    fn default() -> Self {
+        SingleFlight::new()
+    }
+}
+
+impl Flight {
+    fn state(&self) -> MutexGuard<'_, FlightState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether a request goes out or waits for an identical one.
+pub(crate) enum Joined<'s> {
+    Leader(FlightLeader<'s>),
+    Follower(FlightWait),
+}
+
+/// The request of a flight that actually goes out.
+/// Dropped without `finish`, e.g. on an error, the waiting requests are sent on their own.
+#[derive(Debug)]
+pub(crate) struct FlightLeader<'s> {
+    single_flight: &'s SingleFlight,
+    key: String,
+    flight: Arc<Flight>,
+}
+
+impl FlightLeader<'_> {
+    /// Hands `response` to every waiting request.
+    pub(crate) fn finish(self, response: SharedResponse) {
+        self.land(Some(response));
+    }
+
+    fn land(&self, response: Option<SharedResponse>) {
+        let mut state = self.single_flight.state();
+        // a later flight may have taken the key already
+        if state
+            .flights
+            .get(&self.key)
+            .is_some_and(|flight| Arc::ptr_eq(flight, &self.flight))
+        {
+            state.flights.remove(&self.key);
+        }
+        drop(state);
+
+        let mut flight = self.flight.state();
+        if let FlightState::Pending(wakers) =
+            std::mem::replace(&mut *flight, FlightState::Done(response))
+        {
+            wakers.into_iter().for_each(|waker| waker.wake());
+        }
+    }
+}
+
+impl Drop for FlightLeader<'_> {
+    fn drop(&mut self) {
+        if matches!(*self.flight.state(), FlightState::Pending(_)) {
+            self.land(None);
+        }
+    }
+}
+
+/// Resolves with the leader's response, `None` when it didn't get one.
+#[derive(Debug)]
+pub(crate) struct FlightWait {
+    flight: Arc<Flight>,
+}
+
+impl Future for FlightWait {
+    type Output = Option<SharedResponse>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut flight = self.flight.state();
+        match &mut *flight {
+            FlightState::Pending(wakers) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+            FlightState::Done(response) => Poll::Ready(response.clone()),
+        }
+    }
+}
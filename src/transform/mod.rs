@@ -0,0 +1,31 @@
+#[allow(clippy::module_inception)]
+pub mod transform;
+
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+
+/// Whatever went wrong inside a transform.
+pub type TransformError = Box<dyn std::error::Error + Send + Sync>;
+
+type TransformFn = dyn Fn(Bytes, &mut HeaderMap) -> Result<Bytes, TransformError> + Send + Sync;
+
+/// `transformRequest`, rewrites the encoded request body and headers before sending.
+/// ```rs
+/// let sign = TransformRequest::new(|body, headers| {
+///     headers.insert("x-signature", sign(&body).parse()?);
+///     Ok(body)
+/// });
+/// ```
+#[derive(Clone)]
+pub struct TransformRequest(Arc<TransformFn>);
+
+/// `transformResponse`, rewrites the raw response body and headers before decoding.
+/// ```rs
+/// let unwrap = TransformResponse::new(|body, _headers| {
+///     let mut envelope: serde_json::Value = serde_json::from_slice(&body)?;
+///     Ok(serde_json::to_vec(&envelope["data"].take())?.into())
+/// });
+/// ```
+#[derive(Clone)]
+pub struct TransformResponse(Arc<TransformFn>);
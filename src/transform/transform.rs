@@ -0,0 +1,45 @@
+use super::{TransformError, TransformRequest, TransformResponse};
+
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+
+impl TransformRequest {
+    pub fn new<F>(transform: F) -> Self
+    where
+        F: Fn(Bytes, &mut HeaderMap) -> Result<Bytes, TransformError> + Send + Sync + 'static,
+    {
+        TransformRequest(Arc::new(transform))
+    }
+
+    /// Runs the transform, requests without a body get an empty one.
+    pub fn apply(&self, body: Bytes, headers: &mut HeaderMap) -> Result<Bytes, TransformError> {
+        (self.0)(body, headers)
+    }
+}
+
+impl TransformResponse {
+    pub fn new<F>(transform: F) -> Self
+    where
+        F: Fn(Bytes, &mut HeaderMap) -> Result<Bytes, TransformError> + Send + Sync + 'static,
+    {
+        TransformResponse(Arc::new(transform))
+    }
+
+    /// Runs the transform, `headers` are the ones that end up on the `Response`.
+    pub fn apply(&self, body: Bytes, headers: &mut HeaderMap) -> Result<Bytes, TransformError> {
+        (self.0)(body, headers)
+    }
+}
+
+impl std::fmt::Debug for TransformRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TransformRequest(Fn)")
+    }
+}
+
+impl std::fmt::Debug for TransformResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TransformResponse(Fn)")
+    }
+}
@@ -0,0 +1,153 @@
+use echo_http::adapter::BoxFuture;
+use echo_http::{Adapter, EchoError, ErrorCode, RequestConfig};
+use reqwest::ResponseBuilderExt;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::{echo_at, API};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User {
+    id: u32,
+}
+
+// status, headers, body
+type Reply = (u16, Vec<(&'static str, &'static str)>, &'static str);
+
+// answers with queued responses and records every request it sees
+#[derive(Debug, Default)]
+struct Canned {
+    replies: Mutex<VecDeque<Reply>>,
+    seen: Mutex<Vec<String>>,
+}
+
+impl Canned {
+    fn new(replies: Vec<Reply>) -> Arc<Self> {
+        Arc::new(Canned {
+            replies: Mutex::new(replies.into()),
+            ..Default::default()
+        })
+    }
+
+    fn seen(&self) -> Vec<String> {
+        self.seen.lock().unwrap().clone()
+    }
+}
+
+impl Adapter for Canned {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, EchoError>> {
+        Box::pin(async move {
+            self.seen
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", request.method(), request.url()));
+
+            let Some((status, headers, body)) = self.replies.lock().unwrap().pop_front() else {
+                return Err(EchoError::Cancelled);
+            };
+            let mut response = http::Response::builder()
+                .status(status)
+                .url(request.url().clone());
+            for (name, value) in headers {
+                response = response.header(name, value);
+            }
+            Ok(response.body(body).unwrap().into())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_adapter_sends_the_built_request() {
+    let adapter = Canned::new(vec![(200, vec![], r#"{"id":1}"#)]);
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(adapter.clone()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_with_error::<User, ()>("/users/1").await.unwrap();
+
+    assert_eq!(response.data, User { id: 1 });
+    assert_eq!(response.url, "https://api.example.test/users/1");
+    assert!(response.remote_addr.is_none());
+    assert_eq!(adapter.seen(), vec!["GET https://api.example.test/users/1"]);
+}
+
+#[tokio::test]
+async fn test_redirects_go_through_the_adapter() {
+    let adapter = Canned::new(vec![
+        (301, vec![("location", "/v2/users/1")], ""),
+        (200, vec![], r#"{"id":1}"#),
+    ]);
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(adapter.clone()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users/1").await.unwrap();
+
+    assert_eq!(response.data["id"], 1);
+    assert_eq!(response.redirects.len(), 1);
+    assert_eq!(
+        adapter.seen(),
+        vec![
+            "GET https://api.example.test/users/1",
+            "GET https://api.example.test/v2/users/1",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_adapter_errors_carry_the_context() {
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Canned::new(vec![])),
+            ..Default::default()
+        },
+    );
+
+    let err = echo.get_unknown("/users/1").await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::Canceled);
+    assert_eq!(
+        err.context().unwrap().url,
+        "https://api.example.test/users/1"
+    );
+}
+
+#[tokio::test]
+async fn test_per_request_adapter() {
+    let instance = Canned::new(vec![]);
+    let request = Canned::new(vec![(200, vec![], r#"{"id":2}"#)]);
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(instance.clone()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo
+        .request::<serde_json::Value>(RequestConfig {
+            url: Some("/users/2".to_string()),
+            adapter: Some(request.clone()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.data["id"], 2);
+    assert!(instance.seen().is_empty());
+    assert_eq!(request.seen().len(), 1);
+}
@@ -0,0 +1,103 @@
+use echo_http::{EchoError, ErrorCode, Problem, RequestConfig};
+use reqwest::Method;
+
+mod common;
+use common::{echo_at, echo_server, fixed_server};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct User {
+    id: u32,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct ApiError {
+    code: String,
+    message: String,
+}
+
+#[tokio::test]
+async fn test_error_body_is_decoded() {
+    let server = fixed_server(404, r#"{"code":"not_found","message":"no such user"}"#).await;
+    let echo = echo_at(&server, RequestConfig::default());
+
+    let err = echo
+        .get_with_error::<User, ApiError>("/users/1")
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.status(), Some(404));
+    assert_eq!(err.code(), ErrorCode::BadRequest);
+    assert!(!err.is_retryable());
+    assert_eq!(
+        err.api_data::<ApiError>(),
+        Some(&ApiError {
+            code: "not_found".to_string(),
+            message: "no such user".to_string(),
+        })
+    );
+    assert!(err.api_data::<Problem>().is_none());
+
+    let api = err.api_error().unwrap();
+    assert!(api.url.ends_with("/users/1"));
+    assert_eq!(err.context().unwrap().method, Method::GET);
+}
+
+#[tokio::test]
+async fn test_problem_details() {
+    let server = fixed_server(
+        503,
+        r#"{"type":"https://example.com/out-of-credit","title":"Out of credit","status":503,"balance":30}"#,
+    )
+    .await;
+    let echo = echo_at(&server, RequestConfig::default());
+
+    let err = echo
+        .get_with_error::<User, Problem>("/users/1")
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert!(err.is_retryable());
+
+    let EchoError::Api(api) = err.into_kind() else {
+        panic!("expected an api error");
+    };
+    let api = api.into_data::<ApiError>().unwrap_err();
+    let problem = api.into_data::<Problem>().unwrap();
+
+    assert_eq!(problem.problem_type, "https://example.com/out-of-credit");
+    assert_eq!(problem.title.as_deref(), Some("Out of credit"));
+    assert_eq!(problem.status, Some(503));
+    assert_eq!(problem.extensions["balance"], 30);
+}
+
+#[tokio::test]
+async fn test_undecodable_error_body() {
+    let server = fixed_server(500, r#"{"unexpected":true}"#).await;
+    let echo = echo_at(&server, RequestConfig::default());
+
+    let err = echo
+        .get_with_error::<User, ApiError>("/users/1")
+        .await
+        .unwrap_err();
+
+    let decode_error = err.decode_error().unwrap();
+    assert_eq!(decode_error.status, 500);
+    assert_eq!(err.status(), Some(500));
+}
+
+#[tokio::test]
+async fn test_success_is_decoded_into_t() {
+    let echo = echo_at(&echo_server().await, RequestConfig::default());
+
+    let response = echo
+        .post_with_error::<serde_json::Value, ApiError>(
+            "/users",
+            Some(serde_json::json!({ "id": 1 })),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.data["method"], "POST");
+}
@@ -0,0 +1,204 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{BaseUrls, CircuitBreaker, CircuitState, ErrorCode, RequestConfig, Strategy};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::mock_echo;
+
+const EU: &str = "https://eu.example.test/v1";
+const US: &str = "https://us.example.test/v1";
+const AP: &str = "https://ap.example.test/v1";
+
+fn hosts(mock: &MockAdapter) -> Vec<String> {
+    mock.requests()
+        .iter()
+        .map(|request| request.url.host_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_round_robin() {
+    let mock = MockAdapter::new();
+    mock.on_get("/v1/users").reply(200, ());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(Arc::new(BaseUrls::new([EU, US, AP]))),
+            ..Default::default()
+        },
+    );
+
+    let mut served_by = Vec::new();
+    for _ in 0..4 {
+        let response = echo.get_unknown("/users").await.unwrap();
+        served_by.push(response.config.base_url.clone().unwrap());
+        assert_eq!(
+            response.request,
+            format!("{}/users", served_by.last().unwrap())
+        );
+    }
+
+    assert_eq!(served_by, [EU, US, AP, EU]);
+    assert_eq!(
+        hosts(&mock),
+        [
+            "eu.example.test",
+            "us.example.test",
+            "ap.example.test",
+            "eu.example.test"
+        ]
+    );
+    // absolute urls skip the base urls
+    echo.get_unknown("https://other.example.test/v1/users")
+        .await
+        .unwrap();
+    assert_eq!(hosts(&mock)[4], "other.example.test");
+    // a colon doesn't make a url absolute
+    mock.on_post("/v1/users:batchGet").reply(200, ());
+    echo.post_no("users:batchGet").await.unwrap();
+    assert_eq!(hosts(&mock)[5], "us.example.test");
+}
+
+#[tokio::test]
+async fn test_failover_ejects_and_retries_idempotent_requests() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/*").reply_with(
+        MockReply::network_error(ErrorCode::ConnectionRefused).delay(Duration::from_millis(20)),
+    );
+    mock.on_post("https://eu.example.test/*")
+        .network_error(ErrorCode::ConnectionRefused);
+    mock.on_any().reply(200, ());
+    let base_urls = Arc::new(
+        BaseUrls::new([EU, US])
+            .strategy(Strategy::Failover)
+            .eject_for(Duration::from_millis(100)),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(base_urls.clone()),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        echo.get_full_url("/users"),
+        "https://eu.example.test/v1/users"
+    );
+
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.config.base_url.as_deref(), Some(US));
+    assert!(response.timings.retries >= Duration::from_millis(15));
+    assert_eq!(hosts(&mock), ["eu.example.test", "us.example.test"]);
+
+    // ejected: straight to the next one, which `get_full_url` follows
+    assert_eq!(base_urls.ejected(), [EU]);
+    assert_eq!(
+        echo.get_full_url("/users"),
+        "https://us.example.test/v1/users"
+    );
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.timings.retries, Duration::ZERO);
+    assert_eq!(mock.requests().len(), 3);
+
+    // back once the ejection is over, a `POST` isn't retried
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(base_urls.ejected().is_empty());
+    let err = echo
+        .post("/users", Some(serde_json::json!({ "name": "ada" })))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ConnectionRefused);
+    let context = err.context().unwrap();
+    assert_eq!(context.url, "https://eu.example.test/v1/users");
+    assert_eq!(context.attempt, 1);
+    assert_eq!(mock.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_every_base_url_failing() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/*")
+        .network_error(ErrorCode::ConnectionRefused);
+    mock.on_get("https://us.example.test/*")
+        .network_error(ErrorCode::HostNotFound);
+    let base_urls = Arc::new(BaseUrls::new([EU, US]));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(base_urls.clone()),
+            ..Default::default()
+        },
+    );
+
+    let err = echo.get_unknown("/users").await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::HostNotFound);
+    let context = err.context().unwrap();
+    assert_eq!(context.url, "https://us.example.test/v1/users");
+    assert_eq!(context.attempt, 2);
+    assert_eq!(base_urls.ejected(), [EU, US]);
+
+    // all of them ejected, they're still tried rather than failing right away
+    echo.get_unknown("/users").await.unwrap_err();
+    assert_eq!(mock.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_least_in_flight() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/*")
+        .reply_with(MockReply::new(200).delay(Duration::from_millis(50)));
+    mock.on_any()
+        .reply_with(MockReply::new(200).delay(Duration::from_millis(10)));
+    let base_urls = BaseUrls::new([EU, US]).strategy(Strategy::LeastInFlight);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(Arc::new(base_urls)),
+            ..Default::default()
+        },
+    );
+
+    let (slow, first, second) = tokio::join!(
+        echo.get_unknown("/users"),
+        async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            echo.get_unknown("/users").await
+        },
+        async {
+            tokio::time::sleep(Duration::from_millis(25)).await;
+            echo.get_unknown("/users").await
+        }
+    );
+
+    assert_eq!(slow.unwrap().config.base_url.as_deref(), Some(EU));
+    assert_eq!(first.unwrap().config.base_url.as_deref(), Some(US));
+    // the slow one is still going
+    assert_eq!(second.unwrap().config.base_url.as_deref(), Some(US));
+}
+
+#[tokio::test]
+async fn test_open_circuit_fails_over() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/*").reply(503, ());
+    mock.on_any().reply(200, ());
+    let breaker = Arc::new(CircuitBreaker::new().consecutive_failures(1).per_base_url());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(Arc::new(
+                BaseUrls::new([EU, US]).strategy(Strategy::Failover),
+            )),
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(echo.get_unknown("/users").await.unwrap().status, 503);
+    assert_eq!(breaker.state(EU), CircuitState::Open);
+
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.config.base_url.as_deref(), Some(US));
+    assert_eq!(hosts(&mock), ["eu.example.test", "us.example.test"]);
+}
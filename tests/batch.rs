@@ -0,0 +1,156 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{BaseUrls, Echo, ErrorCode, Params, RequestConfig};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::{mock_echo, API};
+
+fn batch_mock() -> (MockAdapter, Echo<'static>) {
+    let mock = MockAdapter::new();
+    // the later the user, the faster the answer
+    for id in 1..=4u64 {
+        mock.on_get(&format!("/users/{id}")).reply_with(
+            MockReply::json(200, &json!({ "id": id })).delay(Duration::from_millis(50 - 10 * id)),
+        );
+    }
+    mock.on_get("/users/5")
+        .network_error(ErrorCode::ConnectionReset);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+    (mock, echo)
+}
+
+fn users(ids: impl IntoIterator<Item = u64>) -> Vec<RequestConfig<'static>> {
+    ids.into_iter()
+        .map(|id| RequestConfig {
+            url: Some(format!("/users/{id}")),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_all_keeps_input_order() {
+    let (mock, echo) = batch_mock();
+
+    let started = Instant::now();
+    let responses = echo.all::<Value, _>(users(1..=4), 1).await.unwrap();
+
+    let ids: Vec<Value> = responses.iter().map(|res| res.data["id"].clone()).collect();
+    assert_eq!(ids, [json!(1), json!(2), json!(3), json!(4)]);
+    // one at a time: 40 + 30 + 20 + 10ms
+    assert!(started.elapsed() >= Duration::from_millis(95));
+    assert_eq!(mock.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_all_stops_at_the_first_error() {
+    let (_, echo) = batch_mock();
+
+    let err = echo.all::<Value, _>(users([1, 5, 2]), 3).await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::ConnectionReset);
+    assert_eq!(
+        err.context().unwrap().url,
+        "https://api.example.test/users/5"
+    );
+}
+
+#[tokio::test]
+async fn test_all_settled_never_stops_early() {
+    let (mock, echo) = batch_mock();
+    let mut requests = users([5, 1, 2]);
+    requests.push(RequestConfig::default());
+
+    let settled = echo.all_settled::<Value, _>(requests, 2).await;
+
+    let outcomes: Vec<(usize, &str, bool)> = settled
+        .iter()
+        .map(|settled| (settled.index, settled.url.as_str(), settled.result.is_ok()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            (0, "https://api.example.test/users/5", false),
+            (1, "https://api.example.test/users/1", true),
+            (2, "https://api.example.test/users/2", true),
+            (3, "", false),
+        ]
+    );
+    let missing_url = settled[3].result.as_ref().unwrap_err();
+    assert_eq!(missing_url.code(), ErrorCode::InvalidUrl);
+    assert_eq!(mock.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_settled_urls_keep_their_params() {
+    let (_, echo) = batch_mock();
+    let mut params = Params::new();
+    params.insert("fields", "id").unwrap();
+    let requests = users([1, 5]).into_iter().map(|config| RequestConfig {
+        params: Some(params.clone()),
+        ..config
+    });
+
+    let settled = echo.all_settled::<Value, _>(requests, 2).await;
+
+    assert!(settled[0].result.is_ok());
+    assert!(settled[1].result.is_err());
+    assert_eq!(settled[0].url, "https://api.example.test/users/1?fields=id");
+    assert_eq!(settled[1].url, "https://api.example.test/users/5?fields=id");
+}
+
+#[tokio::test]
+async fn test_stream_all_yields_as_completed() {
+    let (_, echo) = batch_mock();
+
+    let settled: Vec<(usize, String)> = echo
+        .stream_all::<Value, _>(users(1..=4), 4)
+        .map(|settled| (settled.index, settled.url))
+        .collect()
+        .await;
+
+    assert_eq!(
+        settled,
+        [
+            (3, "https://api.example.test/users/4".to_string()),
+            (2, "https://api.example.test/users/3".to_string()),
+            (1, "https://api.example.test/users/2".to_string()),
+            (0, "https://api.example.test/users/1".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_settled_url_is_where_the_request_went() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/users/1")
+        .network_error(ErrorCode::ConnectionRefused);
+    mock.on_get("https://us.example.test/users/1")
+        .reply(200, json!({ "id": 1 }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(Arc::new(BaseUrls::new([
+                "https://eu.example.test",
+                "https://us.example.test",
+            ]))),
+            ..Default::default()
+        },
+    );
+
+    let settled = echo.all_settled::<Value, _>(users([1]), 1).await;
+
+    // the first base url refused the connection, the second one answered
+    assert_eq!(settled[0].url, "https://us.example.test/users/1");
+    assert!(settled[0].result.is_ok());
+    assert_eq!(mock.requests().len(), 2);
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "blocking")]
+
+use echo_http::blocking::BlockingAdapter;
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{ErrorCode, RequestConfig, ReqwestAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::{blocking_echo_at, echo_server, fixed_server, redirect_server, silent_server, API};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+}
+
+// the test servers are async, they run on a runtime of their own
+fn serve<F: std::future::Future<Output = String>>(server: F) -> (tokio::runtime::Runtime, String) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let base_url = runtime.block_on(server);
+    (runtime, base_url)
+}
+
+#[test]
+fn test_verbs() {
+    let (_runtime, base_url) = serve(echo_server());
+    let echo = blocking_echo_at(&base_url, RequestConfig::default());
+
+    assert_eq!(echo.get_unknown("/users").unwrap().data["method"], "GET");
+    assert_eq!(echo.delete("/users/1").unwrap().data["method"], "DELETE");
+    assert_eq!(echo.post_no("/users").unwrap().data["method"], "POST");
+
+    let response = echo.post("/users", Some(json!({ "id": 1 }))).unwrap();
+    assert_eq!(response.data["body"], r#"{"id":1}"#);
+    let response = echo.put("/users/1", Some(json!({ "id": 1 }))).unwrap();
+    assert_eq!(response.data["method"], "PUT");
+    let response = echo.patch("/users/1", Some(json!({ "id": 1 }))).unwrap();
+    assert_eq!(response.data["method"], "PATCH");
+
+    let response = echo.head("/users").unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.data, Value::Null);
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users/2".to_string()),
+            method: reqwest::Method::OPTIONS,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(response.data["path"], "/users/2");
+}
+
+#[test]
+fn test_shares_the_async_pipeline() {
+    let (_runtime, base_url) = serve(redirect_server(String::new()));
+    let echo = blocking_echo_at(&base_url, RequestConfig::default());
+
+    let response = echo.get_unknown("/redirect/2").unwrap();
+    assert_eq!(response.redirects.len(), 2);
+    assert_eq!(response.data["path"], "/redirect/0");
+
+    let (_runtime, base_url) = serve(fixed_server(404, r#"{"id":0,"name":"missing"}"#));
+    let echo = blocking_echo_at(&base_url, RequestConfig::default());
+    let err = echo.get_with_error::<User, User>("/users/1").unwrap_err();
+    assert_eq!(err.status(), Some(404));
+    assert_eq!(err.api_data::<User>().unwrap().name, "missing");
+}
+
+#[test]
+fn test_timeout() {
+    let (_runtime, base_url) = serve(silent_server());
+    let mut echo = blocking_echo_at(&base_url, RequestConfig::default());
+    echo.config_mut().timeout = Some(1);
+
+    let err = echo.get_unknown("/users").unwrap_err();
+    assert_eq!(err.code(), ErrorCode::TimedOut);
+}
+
+#[test]
+fn test_with_mock_adapter() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users/1").reply_with(
+        MockReply::json(200, &json!({ "id": 1, "name": "Test" })).delay(Duration::from_millis(10)),
+    );
+    let echo = blocking_echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(mock.adapter()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get::<User>("/users/1").unwrap();
+
+    assert_eq!(response.data.name, "Test");
+    mock.verify();
+}
+
+#[test]
+fn test_with_async_adapter() {
+    let (_runtime, base_url) = serve(echo_server());
+    let echo = blocking_echo_at(
+        &base_url,
+        RequestConfig {
+            adapter: Some(Arc::new(ReqwestAdapter::default())),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users/1").unwrap();
+
+    assert_eq!(response.data["path"], "/users/1");
+}
+
+#[test]
+fn test_with_own_blocking_client() {
+    let (_runtime, base_url) = serve(echo_server());
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("authorization", "Bearer t0k3n".parse().unwrap());
+    let client = reqwest::blocking::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let echo = blocking_echo_at(
+        &base_url,
+        RequestConfig {
+            adapter: Some(Arc::new(BlockingAdapter::new(client))),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users/1").unwrap();
+
+    assert_eq!(response.data["authorization"], "Bearer t0k3n");
+}
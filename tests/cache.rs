@@ -0,0 +1,368 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{CacheMode, CacheStatus, CacheStore, DiskCache, MemoryCache, RequestConfig};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::{mock_echo, API};
+
+fn countries() -> MockReply {
+    MockReply::json(200, &json!(["Italy", "Spain"]))
+}
+
+#[tokio::test]
+async fn test_fresh_responses_are_served_from_the_cache() {
+    let mock = MockAdapter::new();
+    mock.on_get("/countries")
+        .reply_with(countries().header("cache-control", "max-age=3600"));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+
+    let first = echo.get_unknown("/countries").await.unwrap();
+    let second = echo.get_unknown("/countries").await.unwrap();
+
+    assert_eq!(first.cache, Some(CacheStatus::Miss));
+    assert_eq!(second.cache, Some(CacheStatus::Hit));
+    assert_eq!(second.status, 200);
+    assert_eq!(second.data, json!(["Italy", "Spain"]));
+    assert_eq!(second.headers["age"], "0");
+    assert_eq!(second.url, "https://api.example.test/countries");
+    assert_eq!(mock.requests().len(), 1);
+
+    // without a cache there is no cache status
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+    assert_eq!(echo.get_unknown("/countries").await.unwrap().cache, None);
+}
+
+#[tokio::test]
+async fn test_upstream_age_counts_against_freshness() {
+    let mock = MockAdapter::new();
+    mock.on_get("/countries").reply_with(
+        countries()
+            .header("cache-control", "max-age=60")
+            .header("age", "59"),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("/countries").await.unwrap();
+    let cached = echo.get_unknown("/countries").await.unwrap();
+    assert_eq!(cached.cache, Some(CacheStatus::Hit));
+    assert_eq!(cached.headers["age"], "59");
+    assert_eq!(mock.requests().len(), 1);
+
+    // one more second and the 59 seconds it spent upstream make it stale
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let refetched = echo.get_unknown("/countries").await.unwrap();
+    assert_ne!(refetched.cache, Some(CacheStatus::Hit));
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_revalidation_with_etag_and_last_modified() {
+    let mock = MockAdapter::new();
+    mock.on_get("/countries")
+        .with_header("if-none-match", "\"v1\"")
+        .reply_with(MockReply::new(304).header("cache-control", "no-cache"));
+    mock.on_get("/countries").reply_with(
+        countries()
+            .header("cache-control", "no-cache")
+            .header("etag", "\"v1\""),
+    );
+    mock.on_get("/languages")
+        .with_header("if-modified-since", "Tue, 01 Oct 2024 10:00:00 GMT")
+        .reply_with(MockReply::new(304));
+    mock.on_get("/languages").reply_with(
+        MockReply::json(200, &json!(["it"]))
+            .header("cache-control", "max-age=0")
+            .header("last-modified", "Tue, 01 Oct 2024 10:00:00 GMT"),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+
+    let first = echo.get_unknown("/countries").await.unwrap();
+    let second = echo.get_unknown("/countries").await.unwrap();
+    assert_eq!(first.cache, Some(CacheStatus::Miss));
+    assert_eq!(second.cache, Some(CacheStatus::Revalidated));
+    assert_eq!(second.status, 200);
+    assert_eq!(second.data, json!(["Italy", "Spain"]));
+    assert_eq!(second.headers["etag"], "\"v1\"");
+
+    echo.get_unknown("/languages").await.unwrap();
+    let second = echo.get_unknown("/languages").await.unwrap();
+    assert_eq!(second.cache, Some(CacheStatus::Revalidated));
+    assert_eq!(second.data, json!(["it"]));
+
+    assert_eq!(mock.requests().len(), 4);
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_uncacheable_responses() {
+    let mock = MockAdapter::new();
+    mock.on_get("/me")
+        .reply_with(countries().header("cache-control", "no-store, max-age=60"));
+    mock.on_get("/expired")
+        .reply_with(countries().header("expires", "Thu, 01 Jan 1970 00:00:00 GMT"));
+    mock.on_get("/created")
+        .reply_with(MockReply::json(201, &json!({})));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+
+    for url in ["/me", "/expired", "/created"] {
+        echo.get_unknown(url).await.unwrap();
+        let second = echo.get_unknown(url).await.unwrap();
+        assert_eq!(second.cache, Some(CacheStatus::Miss), "{url}");
+    }
+    assert_eq!(mock.requests().len(), 6);
+}
+
+#[tokio::test]
+async fn test_cache_modes() {
+    let mock = MockAdapter::new();
+    mock.on_get("/countries").reply_with(
+        countries()
+            .header("cache-control", "max-age=0, must-revalidate")
+            .header("etag", "\"v1\""),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+    let with_mode = |mode| RequestConfig {
+        url: Some("/countries".to_string()),
+        cache_mode: Some(mode),
+        ..Default::default()
+    };
+
+    let response = echo
+        .request_unknown(with_mode(CacheMode::OnlyIfCached))
+        .await
+        .unwrap();
+    assert_eq!(response.status, 504);
+    assert_eq!(mock.requests().len(), 0);
+
+    let response = echo
+        .request_unknown(with_mode(CacheMode::NoStore))
+        .await
+        .unwrap();
+    assert_eq!(response.cache, Some(CacheStatus::Bypass));
+
+    let response = echo
+        .request_unknown(with_mode(CacheMode::Reload))
+        .await
+        .unwrap();
+    assert_eq!(response.cache, Some(CacheStatus::Miss));
+
+    // stale, but served anyway
+    for mode in [CacheMode::ForceCache, CacheMode::OnlyIfCached] {
+        let response = echo.request_unknown(with_mode(mode)).await.unwrap();
+        assert_eq!(response.cache, Some(CacheStatus::Hit));
+        assert_eq!(response.data, json!(["Italy", "Spain"]));
+    }
+    assert_eq!(mock.requests().len(), 2);
+
+    // the stored response has an etag, yet reload doesn't revalidate
+    echo.request_unknown(with_mode(CacheMode::Reload))
+        .await
+        .unwrap();
+    let requests = mock.requests();
+    assert!(!requests[2].headers.contains_key("if-none-match"));
+}
+
+#[tokio::test]
+async fn test_vary_and_invalidation() {
+    let mock = MockAdapter::new();
+    mock.on_get("/greeting").reply_with(
+        MockReply::json(200, &json!("hello"))
+            .header("cache-control", "max-age=60")
+            .header("vary", "Accept-Language"),
+    );
+    mock.on_put("/greeting").reply(204, ());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+    let in_language = |language: &'static str| {
+        let mut headers = echo_http::Headers::new();
+        headers.insert(language);
+        RequestConfig {
+            url: Some("/greeting".to_string()),
+            headers: Some(headers),
+            ..Default::default()
+        }
+    };
+
+    echo.request_unknown(in_language("Accept-Language: en"))
+        .await
+        .unwrap();
+    let same = echo
+        .request_unknown(in_language("Accept-Language: en"))
+        .await
+        .unwrap();
+    let other = echo
+        .request_unknown(in_language("Accept-Language: it"))
+        .await
+        .unwrap();
+    assert_eq!(same.cache, Some(CacheStatus::Hit));
+    assert_eq!(other.cache, Some(CacheStatus::Miss));
+
+    // the italian response replaced the english one, a PUT drops it
+    echo.put("/greeting", Some(json!("ciao"))).await.unwrap();
+    let after_put = echo
+        .request_unknown(in_language("Accept-Language: it"))
+        .await
+        .unwrap();
+    assert_eq!(after_put.cache, Some(CacheStatus::Miss));
+    assert_eq!(mock.requests().len(), 4);
+}
+
+const ALICE: &str = "Authorization: Bearer alice";
+const BOB: &str = "Authorization: Bearer bob";
+
+#[tokio::test]
+async fn test_responses_to_requests_with_credentials() {
+    let mock = MockAdapter::new();
+    for name in ["alice", "bob"] {
+        mock.on_get("/me")
+            .with_header("authorization", &format!("Bearer {name}"))
+            .reply_with(MockReply::json(200, &json!(name)).header("cache-control", "max-age=60"));
+    }
+    mock.on_get("/news").reply_with(
+        MockReply::json(200, &json!(["headline"])).header("cache-control", "public, max-age=60"),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            ..Default::default()
+        },
+    );
+    let as_user = |url: &str, authorization: &'static str| {
+        let mut headers = echo_http::Headers::new();
+        headers.insert(authorization);
+        RequestConfig {
+            url: Some(url.to_string()),
+            headers: Some(headers),
+            ..Default::default()
+        }
+    };
+
+    let alice = echo.request_unknown(as_user("/me", ALICE)).await.unwrap();
+    let bob = echo.request_unknown(as_user("/me", BOB)).await.unwrap();
+    assert_eq!(alice.data, json!("alice"));
+    assert_eq!(bob.data, json!("bob"));
+    assert_eq!(bob.cache, Some(CacheStatus::Miss));
+    assert_eq!(mock.requests().len(), 2);
+
+    // `public` allows sharing it, and anonymous responses aren't handed to credentials
+    echo.request_unknown(as_user("/news", ALICE)).await.unwrap();
+    let shared = echo.request_unknown(as_user("/news", BOB)).await.unwrap();
+    assert_eq!(shared.cache, Some(CacheStatus::Hit));
+    mock.on_get("/countries")
+        .reply_with(countries().header("cache-control", "max-age=60"));
+    echo.get_unknown("/countries").await.unwrap();
+    let private = echo
+        .request_unknown(as_user("/countries", ALICE))
+        .await
+        .unwrap();
+    assert_eq!(private.cache, Some(CacheStatus::Miss));
+    assert_eq!(mock.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn test_disk_cache() {
+    let dir = std::env::temp_dir().join(format!("echo_http-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mock = MockAdapter::new();
+    mock.on_get("/countries")
+        .reply_with(countries().header("cache-control", "max-age=3600"));
+
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(DiskCache::new(&dir))),
+            ..Default::default()
+        },
+    );
+    echo.get_unknown("/countries").await.unwrap();
+
+    // a new instance, e.g. the next run of the program
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            cache: Some(Arc::new(DiskCache::new(&dir))),
+            ..Default::default()
+        },
+    );
+    let response = echo.get_unknown("/countries").await.unwrap();
+
+    assert_eq!(response.cache, Some(CacheStatus::Hit));
+    assert_eq!(response.data, json!(["Italy", "Spain"]));
+    assert_eq!(mock.requests().len(), 1);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_memory_cache_bound() {
+    let cache = MemoryCache::bounded(1);
+    let stored = |key: &str| echo_http::cache::CachedResponse {
+        key: key.to_string(),
+        status: 200,
+        headers: Vec::new(),
+        vary: Vec::new(),
+        request_time: 0,
+        response_time: 0,
+        body: Default::default(),
+    };
+
+    cache.put("GET /a", stored("GET /a"));
+    cache.put("GET /b", stored("GET /b"));
+
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get("GET /a").is_none());
+    assert!(cache.get("GET /b").is_some());
+}
@@ -0,0 +1,63 @@
+use echo_http::{CancelToken, Echo, EchoError, RequestConfig};
+use std::time::Duration;
+
+mod common;
+use common::silent_server;
+
+#[test]
+fn test_child_token() {
+    let parent = CancelToken::new();
+    let child = parent.child_token();
+
+    child.cancel();
+    assert!(child.is_cancelled());
+    assert!(!parent.is_cancelled());
+
+    let child = parent.child_token();
+    parent.cancel();
+    assert!(child.is_cancelled());
+}
+
+#[tokio::test]
+async fn test_cancelled_before_send() {
+    let token = CancelToken::new();
+    token.cancel();
+
+    let echo = Echo::configure(None);
+    let result = echo
+        .request::<serde_json::Value>(RequestConfig {
+            url: Some("http://127.0.0.1:1/never".to_string()),
+            cancel_token: Some(token),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(matches!(result.unwrap_err().kind(), EchoError::Cancelled));
+}
+
+#[tokio::test]
+async fn test_cancel_many_in_flight() {
+    let base_url = silent_server().await;
+
+    let token = CancelToken::new();
+    let config = RequestConfig {
+        base_url: Some(base_url),
+        cancel_token: Some(token.clone()),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let canceller = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        token.cancel();
+    });
+
+    let (first, second) = tokio::join!(
+        echo.get::<serde_json::Value>("/first"),
+        echo.get_unknown("/second"),
+    );
+    canceller.await.unwrap();
+
+    assert!(matches!(first.unwrap_err().kind(), EchoError::Cancelled));
+    assert!(matches!(second.unwrap_err().kind(), EchoError::Cancelled));
+}
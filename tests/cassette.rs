@@ -0,0 +1,360 @@
+use echo_http::cassette::{Cassette, CassetteBody, MatchRules, RecordMode};
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{ErrorCode, Headers, RequestConfig, TransformRequest};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+mod common;
+use common::{echo_at, API};
+
+// a fresh, not yet existing cassette file per test
+fn cassette_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir()
+        .join(format!("echo_http-{}", std::process::id()))
+        .join(format!("{name}.jsonl"));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn test_record_once_then_replay() {
+    let path = cassette_path("record_once");
+    let live = MockAdapter::new();
+    live.on_get("/users/1")
+        .reply(200, json!({ "id": 1, "name": "Test" }));
+    live.on_post("/users").reply(201, json!({ "id": 2 }));
+
+    let cassette = Cassette::open(&path, RecordMode::RecordOnce)
+        .unwrap()
+        .with_adapter(live.adapter());
+    assert!(cassette.is_recording());
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+    echo.get_unknown("/users/1").await.unwrap();
+    echo.post("/users", Some(json!({ "name": "New" })))
+        .await
+        .unwrap();
+
+    assert_eq!(live.requests().len(), 2);
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    // the file exists now, so nothing goes out anymore
+    let offline = MockAdapter::new();
+    let cassette = Cassette::open(&path, RecordMode::RecordOnce)
+        .unwrap()
+        .with_adapter(offline.adapter());
+    assert!(!cassette.is_recording());
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users/1").await.unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.data["name"], "Test");
+    assert_eq!(response.headers["content-type"], "application/json");
+    assert_eq!(response.url, "https://api.example.test/users/1");
+
+    let response = echo
+        .post("/users", Some(json!({ "name": "New" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status, 201);
+    assert_eq!(response.data["id"], 2);
+
+    assert!(offline.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_secrets_are_redacted() {
+    let path = cassette_path("redacted");
+    let live = MockAdapter::new();
+    live.on_post("/login").reply_with(
+        MockReply::json(200, &json!({ "token": "t0k3n", "user": "test" }))
+            .header("set-cookie", "session=abc")
+            .header("x-request-id", "req-1"),
+    );
+
+    let cassette = Cassette::open(&path, RecordMode::RecordOnce)
+        .unwrap()
+        .with_adapter(live.adapter())
+        .redact_header("x-request-id");
+    let mut headers = Headers::new();
+    headers.insert("Authorization: Bearer hunter2");
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            headers: Some(headers.clone()),
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+
+    let login = json!({ "user": "test", "password": "hunter2" });
+    let response = echo
+        .post("/login?api_key=k3y&v=1", Some(login.clone()))
+        .await
+        .unwrap();
+    // the caller still gets the real response
+    assert_eq!(response.data["token"], "t0k3n");
+
+    let written = std::fs::read_to_string(&path).unwrap();
+    for secret in ["hunter2", "k3y", "t0k3n", "session=abc", "req-1"] {
+        assert!(!written.contains(secret), "{secret} was written");
+    }
+    assert!(written.contains("v=1"));
+
+    // requests are redacted the same way before matching
+    let cassette = Cassette::open(&path, RecordMode::ReplayOnly).unwrap();
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            headers: Some(headers),
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+    let response = echo
+        .post("/login?api_key=other&v=1", Some(login))
+        .await
+        .unwrap();
+    assert_eq!(response.data["token"], "[REDACTED]");
+    assert_eq!(response.headers["set-cookie"], "[REDACTED]");
+}
+
+#[tokio::test]
+async fn test_only_secret_keys_are_redacted() {
+    let path = cassette_path("lookalikes");
+    let body = json!({
+        "max_tokens": 100,
+        "tokens_used": 42,
+        "next_page_token": "p2",
+        "session_count": 3,
+        "classname": "admin",
+        "apiKey": "k3y",
+        "pin_secret": 1234,
+        "session": { "id": "s-1", "active": true }
+    });
+    let live = MockAdapter::new();
+    live.on_get("/usage").reply(200, body.clone());
+
+    let cassette = Cassette::open(&path, RecordMode::RecordOnce)
+        .unwrap()
+        .with_adapter(live.adapter());
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+    echo.get_unknown("/usage").await.unwrap();
+
+    let cassette = Cassette::open(&path, RecordMode::ReplayOnly).unwrap();
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+    let response = echo.get_unknown("/usage").await.unwrap();
+
+    // look-alikes are kept as they are, secrets keep their JSON type
+    assert_eq!(
+        response.data,
+        json!({
+            "max_tokens": 100,
+            "tokens_used": 42,
+            "next_page_token": "p2",
+            "session_count": 3,
+            "classname": "admin",
+            "apiKey": "[REDACTED]",
+            "pin_secret": 0,
+            "session": { "id": "[REDACTED]", "active": true }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_form_bodies_are_redacted() {
+    let path = cassette_path("form");
+    let live = MockAdapter::new();
+    live.on_post("/oauth/token").reply_with(
+        MockReply::new(200)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body("access_token=t0k3n&expires_in=3600"),
+    );
+
+    let cassette = Cassette::open(&path, RecordMode::RecordOnce)
+        .unwrap()
+        .with_adapter(live.adapter());
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            transform_request: Some(vec![TransformRequest::new(|_, headers| {
+                headers.insert(
+                    "content-type",
+                    "application/x-www-form-urlencoded".parse().unwrap(),
+                );
+                Ok("grant_type=password&username=test&password=hunter2".into())
+            })]),
+            ..Default::default()
+        },
+    );
+
+    // the form response doesn't decode as JSON, it is recorded all the same
+    let err = echo
+        .request_unknown(RequestConfig {
+            url: Some("/oauth/token".to_string()),
+            method: reqwest::Method::POST,
+            data: Some(json!({})),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(err.decode_error().is_some());
+    // the caller still sent the real form
+    let sent = live.requests();
+    assert_eq!(
+        sent[0].body,
+        "grant_type=password&username=test&password=hunter2".as_bytes()
+    );
+
+    let interactions = Cassette::open(&path, RecordMode::ReplayOnly)
+        .unwrap()
+        .interactions();
+    assert_eq!(
+        interactions[0].request.body,
+        Some(CassetteBody::Text(
+            "grant_type=password&username=test&password=%5BREDACTED%5D".to_string()
+        ))
+    );
+    assert_eq!(
+        interactions[0].response.body,
+        Some(CassetteBody::Text(
+            "access_token=%5BREDACTED%5D&expires_in=3600".to_string()
+        ))
+    );
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(!written.contains("hunter2") && !written.contains("t0k3n"));
+}
+
+#[tokio::test]
+async fn test_replay_only_misses() {
+    let path = cassette_path("replay_only");
+    let err = Cassette::open(&path, RecordMode::ReplayOnly).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::NotSupported);
+
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(
+        &path,
+        r#"{"request":{"method":"GET","url":"https://api.example.test/users/1"},"response":{"status":200,"headers":[["content-type","application/json"]],"body":{"json":{"id":1}}}}"#,
+    )
+    .unwrap();
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(
+                Cassette::open(&path, RecordMode::ReplayOnly).unwrap(),
+            )),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users/1").await.unwrap();
+    assert_eq!(response.data["id"], 1);
+
+    let err = echo.get_unknown("/users/2").await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::NotSupported);
+    assert!(err.to_string().contains("no recording matches GET"));
+}
+
+#[tokio::test]
+async fn test_match_rules_and_order() {
+    let path = cassette_path("match_rules");
+    let live = MockAdapter::new();
+    live.on_post("/search").reply_sequence([
+        MockReply::json(200, &json!({ "page": 1 })),
+        MockReply::json(200, &json!({ "page": 2 })),
+        MockReply::json(200, &json!({ "page": 3 })),
+    ]);
+
+    let rules = MatchRules {
+        body: true,
+        ..Default::default()
+    };
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(
+                Cassette::open(&path, RecordMode::RecordOnce)
+                    .unwrap()
+                    .with_adapter(live.adapter())
+                    .match_on(rules.clone()),
+            )),
+            ..Default::default()
+        },
+    );
+    for query in ["a", "a", "b"] {
+        echo.post("/search", Some(json!({ "q": query })))
+            .await
+            .unwrap();
+    }
+
+    let cassette = Cassette::open(&path, RecordMode::ReplayOnly)
+        .unwrap()
+        .match_on(rules);
+    assert_eq!(
+        cassette.interactions()[2].request.body,
+        Some(CassetteBody::Json(json!({ "q": "b" })))
+    );
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(cassette)),
+            ..Default::default()
+        },
+    );
+
+    let page = |response: echo_http::Response<'_, serde_json::Value>| response.data["page"].clone();
+    let search = |query: &'static str| echo.post("/search", Some(json!({ "q": query })));
+    assert_eq!(page(search("b").await.unwrap()), 3);
+    assert_eq!(page(search("a").await.unwrap()), 1);
+    assert_eq!(page(search("a").await.unwrap()), 2);
+    // used up recordings keep answering with the last one
+    assert_eq!(page(search("a").await.unwrap()), 2);
+}
+
+#[tokio::test]
+async fn test_passthrough_writes_nothing() {
+    let path = cassette_path("passthrough");
+    let live = MockAdapter::new();
+    live.on_get("/users/1").reply(200, json!({ "id": 1 }));
+    let echo = echo_at(
+        API,
+        RequestConfig {
+            adapter: Some(Arc::new(
+                Cassette::open(&path, RecordMode::Passthrough)
+                    .unwrap()
+                    .with_adapter(live.adapter()),
+            )),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("/users/1").await.unwrap();
+
+    assert_eq!(live.requests().len(), 1);
+    assert!(!path.exists());
+}
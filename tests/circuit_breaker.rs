@@ -0,0 +1,227 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{
+    CacheStatus, CircuitBreaker, CircuitState, EchoError, ErrorCode, MemoryCache, RequestConfig,
+    SingleFlight,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod common;
+use common::{mock_echo, API};
+
+type Changes = Arc<Mutex<Vec<(String, CircuitState, CircuitState)>>>;
+
+fn recorded(breaker: CircuitBreaker) -> (Arc<CircuitBreaker>, Changes) {
+    let changes = Changes::default();
+    let recorded = changes.clone();
+    let breaker = Arc::new(breaker.on_state_change(move |key, from, to| {
+        recorded.lock().unwrap().push((key.to_string(), from, to));
+    }));
+    (breaker, changes)
+}
+
+fn change(key: &str, from: CircuitState, to: CircuitState) -> (String, CircuitState, CircuitState) {
+    (key.to_string(), from, to)
+}
+
+#[tokio::test]
+async fn test_opens_after_consecutive_failures() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://down.example.test/*").reply(503, ());
+    mock.on_get("https://up.example.test/*").reply(200, ());
+    let breaker = CircuitBreaker::new()
+        .consecutive_failures(3)
+        .cooldown(Duration::from_secs(60));
+    let (breaker, changes) = recorded(breaker);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..3 {
+        let response = echo.get_unknown("https://down.example.test/users").await;
+        assert_eq!(response.unwrap().status, 503);
+    }
+    let err = echo
+        .get_unknown("https://down.example.test/users")
+        .await
+        .unwrap_err();
+
+    let EchoError::CircuitOpen { key, retry_after } = err.kind() else {
+        panic!("expected CircuitOpen, got {err:?}");
+    };
+    assert_eq!(key, "down.example.test");
+    assert!(*retry_after > Duration::from_secs(59));
+    assert_eq!(err.code(), ErrorCode::CircuitOpen);
+    assert_eq!(
+        err.context().unwrap().url,
+        "https://down.example.test/users"
+    );
+    assert_eq!(breaker.state("down.example.test"), CircuitState::Open);
+
+    // other hosts are unaffected
+    echo.get_unknown("https://up.example.test/users")
+        .await
+        .unwrap();
+    assert_eq!(mock.requests().len(), 4);
+    assert_eq!(
+        *changes.lock().unwrap(),
+        [change(
+            "down.example.test",
+            CircuitState::Closed,
+            CircuitState::Open
+        )]
+    );
+
+    breaker.reset("down.example.test");
+    assert_eq!(breaker.state("down.example.test"), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_half_open_probes() {
+    let mock = MockAdapter::new();
+    mock.on_get("/flaky").reply_sequence([
+        MockReply::network_error(ErrorCode::ConnectionRefused),
+        MockReply::network_error(ErrorCode::ConnectionRefused),
+        MockReply::new(500),
+    ]);
+    mock.on_get("/flaky")
+        .reply_with(MockReply::new(200).delay(Duration::from_millis(30)));
+    let breaker = CircuitBreaker::new()
+        .consecutive_failures(2)
+        .cooldown(Duration::from_millis(40));
+    let (breaker, changes) = recorded(breaker);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        },
+    );
+    let url = "https://api.example.test/flaky";
+
+    for _ in 0..2 {
+        let err = echo.get_unknown(url).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::ConnectionRefused);
+    }
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Open);
+
+    // the probe fails, back to open
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(breaker.state("api.example.test"), CircuitState::HalfOpen);
+    assert_eq!(echo.get_unknown(url).await.unwrap().status, 500);
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Open);
+
+    // the probe succeeds, requests sent meanwhile still fail fast
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let (probe, other) = tokio::join!(echo.get_unknown(url), echo.get_unknown(url));
+    assert_eq!(probe.unwrap().status, 200);
+    assert_eq!(other.unwrap_err().code(), ErrorCode::CircuitOpen);
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Closed);
+    assert_eq!(echo.get_unknown(url).await.unwrap().status, 200);
+
+    let key = "api.example.test";
+    assert_eq!(
+        *changes.lock().unwrap(),
+        [
+            change(key, CircuitState::Closed, CircuitState::Open),
+            change(key, CircuitState::Open, CircuitState::HalfOpen),
+            change(key, CircuitState::HalfOpen, CircuitState::Open),
+            change(key, CircuitState::Open, CircuitState::HalfOpen),
+            change(key, CircuitState::HalfOpen, CircuitState::Closed),
+        ]
+    );
+    assert_eq!(mock.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn test_failure_rate() {
+    let mock = MockAdapter::new();
+    mock.on_get("/v1/orders").reply_sequence([
+        MockReply::new(200),
+        MockReply::new(502),
+        MockReply::new(404),
+        MockReply::new(504),
+    ]);
+    let breaker = Arc::new(
+        CircuitBreaker::new()
+            .consecutive_failures(10)
+            .failure_rate(0.5, 4)
+            .per_base_url(),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(format!("{API}/v1")),
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..3 {
+        echo.get_unknown("/orders").await.unwrap();
+    }
+    // 1 in 3 so far, the window isn't full either
+    assert_eq!(
+        breaker.state("https://api.example.test/v1"),
+        CircuitState::Closed
+    );
+    echo.get_unknown("/orders").await.unwrap();
+    assert_eq!(
+        breaker.state("https://api.example.test/v1"),
+        CircuitState::Open
+    );
+
+    let err = echo.get_unknown("/orders").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::CircuitOpen { .. }));
+    assert_eq!(mock.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_only_requests_that_go_out_count() {
+    let mock = MockAdapter::new();
+    mock.on_get("/countries")
+        .reply_with(MockReply::new(200).header("cache-control", "max-age=60"));
+    mock.on_get("/slow")
+        .reply_with(MockReply::new(503).delay(Duration::from_millis(50)));
+    mock.on_get("/down").reply(503, ());
+    let breaker = Arc::new(CircuitBreaker::new().consecutive_failures(2));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            circuit_breaker: Some(breaker.clone()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            single_flight: Some(Arc::new(SingleFlight::new())),
+            ..Default::default()
+        },
+    );
+
+    // three callers, one request, one failure
+    let (a, b, c) = tokio::join!(
+        echo.get_unknown("/slow"),
+        echo.get_unknown("/slow"),
+        echo.get_unknown("/slow"),
+    );
+    for response in [a, b, c] {
+        assert_eq!(response.unwrap().status, 503);
+    }
+    assert_eq!(mock.requests().len(), 1);
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Closed);
+
+    echo.get_unknown("/countries").await.unwrap();
+    echo.get_unknown("/down").await.unwrap();
+    echo.get_unknown("/down").await.unwrap();
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Open);
+
+    // the cache still answers what it has, the circuit is left alone
+    let cached = echo.get_unknown("/countries").await.unwrap();
+    assert_eq!(cached.cache, Some(CacheStatus::Hit));
+    assert_eq!(breaker.state("api.example.test"), CircuitState::Open);
+    let err = echo.get_unknown("/down").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::CircuitOpen { .. }));
+    assert_eq!(mock.requests().len(), 4);
+}
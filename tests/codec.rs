@@ -0,0 +1,232 @@
+use echo_http::codec::{erased_serde, Codec, CodecError, Visit};
+use echo_http::{JsonCodec, RequestConfig};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+mod common;
+use common::{echo_at, fixed_server, mirror_server};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+    tags: Vec<String>,
+}
+
+fn user() -> User {
+    User {
+        id: 1,
+        name: "Test".to_string(),
+        tags: vec!["a".to_string(), "b".to_string()],
+    }
+}
+
+// JSON with its own media type, to tell it apart from the built-in codec
+#[derive(Debug)]
+struct VendorJson;
+
+impl Codec for VendorJson {
+    fn content_type(&self) -> &str {
+        "application/vnd.example.v1"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode(&self, body: &[u8], visit: Visit<'_>) -> Result<(), CodecError> {
+        let mut deserializer = serde_json::Deserializer::from_slice(body);
+        visit(&mut <dyn erased_serde::Deserializer>::erase(
+            &mut deserializer,
+        ))?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_custom_codec() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(VendorJson)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.post("/users", Some(user())).await.unwrap();
+
+    assert_eq!(response.data, user());
+    assert_eq!(
+        response.headers["content-type"],
+        "application/vnd.example.v1"
+    );
+    assert_eq!(
+        response.headers["x-accept"],
+        "application/vnd.example.v1, application/json;q=0.9"
+    );
+}
+
+#[tokio::test]
+async fn test_json_responses_are_always_understood() {
+    let echo = echo_at(
+        &fixed_server(200, r#"{"id":1,"name":"Test","tags":["a","b"]}"#).await,
+        RequestConfig {
+            codec: Some(Arc::new(VendorJson)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get::<User>("/users/1").await.unwrap();
+    assert_eq!(response.data, user());
+}
+
+#[tokio::test]
+async fn test_per_request_codec() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(VendorJson)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users".to_string()),
+            method: reqwest::Method::POST,
+            data: Some(serde_json::to_value(user()).unwrap()),
+            codec: Some(Arc::new(JsonCodec)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers["content-type"], "application/json");
+    assert_eq!(response.data["name"], "Test");
+}
+
+#[tokio::test]
+async fn test_decode_errors_keep_the_path() {
+    let echo = echo_at(
+        &fixed_server(200, r#"{"id":1,"name":"Test","tags":["a",2]}"#).await,
+        RequestConfig {
+            codec: Some(Arc::new(VendorJson)),
+            ..Default::default()
+        },
+    );
+
+    let err = echo.get::<User>("/users/1").await.unwrap_err();
+    assert_eq!(err.decode_error().unwrap().path, "$.tags[1]");
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(echo_http::MsgPackCodec)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.post("/users", Some(user())).await.unwrap();
+
+    assert_eq!(response.data, user());
+    assert_eq!(response.headers["content-type"], "application/msgpack");
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack_decode_error() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(echo_http::MsgPackCodec)),
+            ..Default::default()
+        },
+    );
+
+    let err = echo
+        .request::<User>(RequestConfig {
+            url: Some("/users".to_string()),
+            method: reqwest::Method::POST,
+            data: Some(serde_json::json!({ "id": "1", "name": "Test", "tags": [] })),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.decode_error().unwrap().path, "$.id");
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn test_cbor() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(echo_http::CborCodec)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.post("/users", Some(user())).await.unwrap();
+
+    assert_eq!(response.data, user());
+    assert_eq!(response.headers["content-type"], "application/cbor");
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_byte_strings_and_integer_keys() {
+    use std::collections::HashMap;
+
+    // {1: h'0102', 2: h''}
+    let body = [0xa2, 0x01, 0x42, 0x01, 0x02, 0x02, 0x40];
+    let mut data = None;
+    echo_http::CborCodec
+        .decode(&body, &mut |deserializer| {
+            data = Some(erased_serde::deserialize::<HashMap<u8, Vec<u8>>>(
+                deserializer,
+            )?);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(data.unwrap(), HashMap::from([(1, vec![1, 2]), (2, vec![])]));
+}
+
+#[cfg(feature = "yaml")]
+#[tokio::test]
+async fn test_yaml() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(echo_http::YamlCodec)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.post("/users", Some(user())).await.unwrap();
+
+    assert_eq!(response.data, user());
+    assert_eq!(response.headers["content-type"], "application/yaml");
+}
+
+#[cfg(feature = "xml")]
+#[tokio::test]
+async fn test_xml() {
+    let echo = echo_at(
+        &mirror_server().await,
+        RequestConfig {
+            codec: Some(Arc::new(echo_http::XmlCodec::default())),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.post("/users", Some(user())).await.unwrap();
+
+    assert_eq!(response.data, user());
+    assert_eq!(response.headers["content-type"], "application/xml");
+}
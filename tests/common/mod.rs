@@ -0,0 +1,234 @@
+#![allow(dead_code)]
+
+use echo_http::mock::MockAdapter;
+use echo_http::{Echo, RequestConfig};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// accepts connections but never answers, so requests stay in flight until cancelled
+pub async fn silent_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let mut sockets = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            sockets.push(socket);
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+// reads one request, returning its head and body
+async fn read_request(socket: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut chunk).await.unwrap_or(0);
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..pos]).to_string();
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            while buf.len() < pos + 4 + length {
+                let n = socket.read(&mut chunk).await.unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            return Some((head, buf[pos + 4..].to_vec()));
+        }
+    }
+}
+
+// `{ "method": .., "path": .., "body": .. }` for the request
+fn echo_payload(head: &str, body: &[u8]) -> String {
+    let mut request_line = head.lines().next().unwrap_or("").split(' ');
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+    let authorization = head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case("authorization")
+            .then(|| value.trim().to_string())
+    });
+    serde_json::json!({
+        "method": method,
+        "path": path,
+        "body": String::from_utf8_lossy(body),
+        "authorization": authorization,
+    })
+    .to_string()
+}
+
+// answers every request with `{ "method": .., "path": .., "body": .. }`
+pub async fn echo_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let Some((head, body)) = read_request(&mut socket).await else {
+                    return;
+                };
+                let payload = echo_payload(&head, &body);
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+// `/redirect/{n}` redirects with `302` to `/redirect/{n - 1}`, `/see-other` with `303` to `/done`,
+// `/loop` to itself and `/away` to the echo server at `other`, everything else is echoed
+pub async fn redirect_server(other: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let other = other.clone();
+            tokio::spawn(async move {
+                let Some((head, body)) = read_request(&mut socket).await else {
+                    return;
+                };
+                let path = head.split(' ').nth(1).unwrap_or("").to_string();
+
+                let redirect = match path.as_str() {
+                    "/see-other" => Some((303, "/done".to_string())),
+                    "/loop" => Some((302, "/loop".to_string())),
+                    "/away" => Some((307, format!("{}/elsewhere", other))),
+                    path => path
+                        .strip_prefix("/redirect/")
+                        .and_then(|n| n.parse::<u32>().ok())
+                        .filter(|n| *n > 0)
+                        .map(|n| (302, format!("/redirect/{}", n - 1))),
+                };
+
+                let response = match redirect {
+                    Some((status, location)) => format!(
+                        "HTTP/1.1 {} Redirect\r\nlocation: {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                        status, location
+                    ),
+                    None => {
+                        let payload = echo_payload(&head, &body);
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                            payload.len(),
+                            payload
+                        )
+                    }
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+fn header<'h>(head: &'h str, name: &str) -> Option<&'h str> {
+    head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+// answers with the request body and content type, the request's `Accept` comes back as `x-accept`
+pub async fn mirror_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let Some((head, body)) = read_request(&mut socket).await else {
+                    return;
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: {}\r\nx-accept: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    header(&head, "content-type").unwrap_or("application/octet-stream"),
+                    header(&head, "accept").unwrap_or(""),
+                    body.len(),
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.write_all(&body).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+// answers every request with the same status and JSON body
+pub async fn fixed_server(status: u16, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut chunk = [0u8; 4096];
+                let _ = socket.read(&mut chunk).await;
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+pub const API: &str = "https://api.example.test";
+
+// an instance on `base_url`, configured with the rest of `config`
+pub fn echo_at(base_url: &str, config: RequestConfig<'static>) -> Echo<'static> {
+    Echo::configure(Some(RequestConfig {
+        base_url: Some(base_url.to_string()),
+        ..config
+    }))
+}
+
+// the blocking twin of `echo_at`
+#[cfg(feature = "blocking")]
+pub fn blocking_echo_at(
+    base_url: &str,
+    config: RequestConfig<'static>,
+) -> echo_http::blocking::Echo<'static> {
+    echo_http::blocking::Echo::configure(Some(RequestConfig {
+        base_url: Some(base_url.to_string()),
+        ..config
+    }))
+    .unwrap()
+}
+
+// an instance that sends everything to `mock`, configured with the rest of `config`
+pub fn mock_echo(mock: &MockAdapter, config: RequestConfig<'static>) -> Echo<'static> {
+    Echo::configure(Some(RequestConfig {
+        adapter: Some(mock.adapter()),
+        ..config
+    }))
+}
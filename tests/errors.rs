@@ -0,0 +1,193 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{Echo, EchoError, ErrorCode, RequestConfig};
+use reqwest::Method;
+
+mod common;
+use common::{echo_server, fixed_server, mock_echo, silent_server, API};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[allow(dead_code)]
+struct User {
+    id: u32,
+}
+
+#[tokio::test]
+async fn test_connection_refused() {
+    let echo = Echo::configure(None);
+    let err = echo
+        .get::<User>("http://127.0.0.1:1/users/1")
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::ConnectionRefused);
+    assert!(err.is_retryable());
+    assert!(matches!(err.kind(), EchoError::HttpError(_)));
+
+    let context = err.context().unwrap();
+    assert_eq!(context.method, Method::GET);
+    assert_eq!(context.url, "http://127.0.0.1:1/users/1");
+    assert_eq!(context.attempt, 1);
+    assert!(context.response.is_none());
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    let config = RequestConfig {
+        base_url: Some(silent_server().await),
+        timeout: Some(1),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let err = echo.get::<User>("/slow").await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::TimedOut);
+    assert_eq!(err.code().as_str(), "ETIMEDOUT");
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_bad_response_keeps_the_response() {
+    let config = RequestConfig {
+        base_url: Some(echo_server().await),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    // the echo server answers with an object that has no `id`
+    let err = echo.post::<User>("/users", None).await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert_eq!(err.status(), Some(200));
+    assert!(!err.is_retryable());
+
+    let context = err.context().unwrap();
+    assert_eq!(context.method, Method::POST);
+    assert!(context.url.ends_with("/users"));
+    assert_eq!(context.response.as_ref().unwrap().status_text, "OK");
+}
+
+#[tokio::test]
+async fn test_unknown_response_that_is_not_json() {
+    let config = RequestConfig {
+        base_url: Some(fixed_server(200, "<html>oops</html>").await),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let err = echo.get_unknown("/users").await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    let decode_error = err.decode_error().unwrap();
+    assert_eq!(decode_error.path, "$");
+    assert_eq!(decode_error.body_text(), "<html>oops</html>");
+}
+
+#[tokio::test]
+async fn test_invalid_url_summary() {
+    let echo = Echo::configure(None);
+    let err = echo.get::<User>("/users/1").await.unwrap_err();
+
+    let summary = serde_json::to_value(err.summary()).unwrap();
+
+    assert_eq!(summary["code"], "ERR_INVALID_URL");
+    assert_eq!(summary["method"], "GET");
+    assert_eq!(summary["url"], "/users/1");
+    assert_eq!(summary["attempt"], 1);
+    assert_eq!(summary["retryable"], false);
+    assert!(summary.get("status").is_none());
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[allow(dead_code)]
+struct Page {
+    data: Vec<User>,
+}
+
+#[tokio::test]
+async fn test_decode_error_details() {
+    let body = r#"{"data":[{"id":1},{"id":"two"}],"token":"abc123"}"#;
+    let echo = Echo::configure(None);
+    let url = format!("{}/users", fixed_server(200, body).await);
+
+    let err = echo.get::<Page>(&url).await.unwrap_err();
+    let decode = err.decode_error().unwrap();
+
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert_eq!(decode.path, "$.data[1].id");
+    assert!(decode.type_name.ends_with("Page"));
+    assert_eq!(decode.status, 200);
+    assert_eq!(decode.url, url);
+    assert!(decode.excerpt.contains("[REDACTED]"));
+    assert!(!decode.excerpt.contains("abc123"));
+    assert_eq!(decode.body(), body.as_bytes());
+    assert!(err.to_string().contains("$.data[1].id"));
+}
+
+#[tokio::test]
+async fn test_decode_error_redacts_bodies_that_are_not_json() {
+    let mock = MockAdapter::new();
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+    let bodies = [
+        ("/form", "user=ada&password=hunter2&page=1"),
+        ("/broken", r#"{"user": "ada", "api_key": "k-123", }"#),
+        ("/yaml", "user: ada\ntoken: abc def\n"),
+        (
+            "/xml",
+            r#"<user><name>ada</name><session id="1">s-1</session></user>"#,
+        ),
+        ("/header", "user ada\nAuthorization: Bearer sk-1"),
+    ];
+    for (path, body) in bodies {
+        mock.on_get(path).reply_with(
+            MockReply::new(200)
+                .header("Content-Type", "application/json")
+                .body(body),
+        );
+    }
+
+    for (path, _) in bodies {
+        let err = echo.get::<User>(path).await.unwrap_err();
+        let excerpt = &err.decode_error().unwrap().excerpt;
+        assert!(excerpt.contains("ada"), "{excerpt}");
+        assert!(excerpt.contains("[REDACTED]"), "{excerpt}");
+        for secret in ["hunter2", "k-123", "abc", "def", "s-1", "sk-1"] {
+            assert!(!excerpt.contains(secret), "{excerpt}");
+        }
+    }
+
+    // MessagePack `{"password": "x"}`, there's no telling keys from values
+    let binary = b"\x81\xa8password\xa1x".to_vec();
+    mock.on_get("/binary").reply_with(
+        MockReply::new(200)
+            .header("Content-Type", "application/json")
+            .body(binary.clone()),
+    );
+    let err = echo.get::<User>("/binary").await.unwrap_err();
+    let decode = err.decode_error().unwrap();
+    assert_eq!(decode.excerpt, "(12 bytes of binary data, not shown)");
+    assert_eq!(decode.body().as_ref(), binary.as_slice());
+}
+
+#[tokio::test]
+async fn test_decode_error_truncates_excerpt() {
+    let body: &'static str =
+        Box::leak(format!("[{}]", "1,".repeat(1000) + "\"x\"").into_boxed_str());
+    let echo = Echo::configure(None);
+    let url = fixed_server(200, body).await;
+
+    let err = echo.get::<Vec<u32>>(&url).await.unwrap_err();
+    let decode = err.decode_error().unwrap();
+
+    assert_eq!(decode.path, "$[1000]");
+    assert!(decode.excerpt.len() < body.len());
+    assert!(decode
+        .excerpt
+        .ends_with(&format!("({} bytes total)", body.len())));
+}
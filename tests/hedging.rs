@@ -0,0 +1,266 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{CacheStatus, Hedging, HedgingStats, MemoryCache, RequestConfig, RequestQueue};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+mod common;
+use common::{echo_at, mock_echo, API};
+
+fn reply_after(status: u16, millis: u64) -> MockReply {
+    MockReply::new(status).delay(Duration::from_millis(millis))
+}
+
+#[tokio::test]
+async fn test_slow_requests_are_hedged() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users")
+        .reply_sequence([reply_after(200, 300), reply_after(200, 10)]);
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(20)).max_extra_load(1.0));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    let started = Instant::now();
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.status, 200);
+    assert!(started.elapsed() < Duration::from_millis(200));
+    assert_eq!(mock.requests().len(), 2);
+    assert_eq!(
+        hedging.stats(),
+        HedgingStats {
+            requests: 1,
+            hedged: 1,
+            wins: 1,
+            over_budget: 0
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_fast_and_non_idempotent_requests_are_not_hedged() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users").reply_with(reply_after(200, 5));
+    let ada = serde_json::json!({ "name": "ada" });
+    mock.on_post("/users")
+        .reply_with(MockReply::json(201, &ada).delay(Duration::from_millis(60)));
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(30)).max_extra_load(1.0));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("/users").await.unwrap();
+    let created = echo.post("/users", Some(ada)).await.unwrap();
+    assert_eq!(created.status, 201);
+
+    assert_eq!(mock.requests().len(), 2);
+    assert_eq!(hedging.stats().requests, 1);
+    assert_eq!(hedging.stats().hedged, 0);
+}
+
+#[tokio::test]
+async fn test_first_successful_response_wins() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users")
+        .reply_sequence([reply_after(503, 30), reply_after(200, 40)]);
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(10)).max_extra_load(1.0));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    // the original answers first, but with a `503`
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(hedging.stats().wins, 1);
+}
+
+#[tokio::test]
+async fn test_extra_load_is_capped() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users").reply_with(reply_after(200, 30));
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(5)).max_extra_load(0.5));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..4 {
+        echo.get_unknown("/users").await.unwrap();
+    }
+
+    assert_eq!(
+        hedging.stats(),
+        HedgingStats {
+            requests: 4,
+            hedged: 2,
+            wins: 0,
+            over_budget: 2
+        }
+    );
+    assert_eq!(mock.requests().len(), 6);
+}
+
+#[tokio::test]
+async fn test_percentile_delay() {
+    let mock = MockAdapter::new();
+    let fast = (0..20).map(|_| reply_after(200, 5));
+    mock.on_get("/users")
+        .reply_sequence(fast.chain([reply_after(200, 500)]));
+    mock.on_get("/users").reply_with(reply_after(200, 5));
+    let hedging = Arc::new(Hedging::at_percentile(0.9, Duration::from_secs(5)).max_extra_load(1.0));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    // nothing observed yet, the fallback applies
+    for _ in 0..20 {
+        echo.get_unknown("/users").await.unwrap();
+    }
+    assert_eq!(hedging.stats().hedged, 0);
+
+    let started = Instant::now();
+    echo.get_unknown("/users").await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(300));
+    assert_eq!(hedging.stats().hedged, 1);
+    assert_eq!(hedging.stats().wins, 1);
+}
+
+#[tokio::test]
+async fn test_extra_load_is_measured_over_recent_requests() {
+    let mock = MockAdapter::new();
+    let fast = (0..8).map(|_| reply_after(200, 1));
+    mock.on_get("/users").reply_sequence(fast);
+    mock.on_get("/users").reply_with(reply_after(200, 60));
+    let hedging = Arc::new(
+        Hedging::after(Duration::from_millis(20))
+            .max_extra_load(0.5)
+            .window(4),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            ..Default::default()
+        },
+    );
+
+    // fast requests long ago don't pay for hedges now
+    for _ in 0..12 {
+        echo.get_unknown("/users").await.unwrap();
+    }
+
+    // two hedges for any four requests in a row
+    assert_eq!(hedging.stats().hedged, 2);
+    assert_eq!(hedging.stats().over_budget, 2);
+}
+
+#[tokio::test]
+async fn test_cached_responses_and_queue_slots() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users")
+        .reply_with(reply_after(200, 60).header("cache-control", "max-age=60"));
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(20)).max_extra_load(1.0));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            hedging: Some(hedging.clone()),
+            cache: Some(Arc::new(MemoryCache::new())),
+            queue: Some(Arc::new(RequestQueue::new(1))),
+            ..Default::default()
+        },
+    );
+
+    // the copy waits for a queue slot, the original holds the only one
+    echo.get_unknown("/users").await.unwrap();
+    assert_eq!(hedging.stats().hedged, 1);
+    assert_eq!(mock.requests().len(), 1);
+
+    // a cache hit is neither hedged nor timed
+    let cached = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(cached.cache, Some(CacheStatus::Hit));
+    assert_eq!(hedging.stats().requests, 1);
+    assert_eq!(mock.requests().len(), 1);
+}
+
+// never answers the first request, answers the next ones with headers right away
+// and their body 300ms later
+async fn slow_body_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let mut connections = 0;
+        while let Ok((mut socket, _)) = listener.accept().await {
+            connections += 1;
+            let first = connections == 1;
+            tokio::spawn(async move {
+                let mut chunk = [0u8; 4096];
+                let _ = socket.read(&mut chunk).await;
+                if first {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    return;
+                }
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n";
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.flush().await;
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                let _ = socket.write_all(b"{}").await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_hedged_copy_keeps_its_queue_slot_until_read() {
+    let queue = Arc::new(RequestQueue::new(4));
+    let hedging = Arc::new(Hedging::after(Duration::from_millis(20)).max_extra_load(1.0));
+    let echo = echo_at(
+        &slow_body_server().await,
+        RequestConfig {
+            hedging: Some(hedging.clone()),
+            queue: Some(queue.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (response, in_flight) = tokio::join!(echo.get_unknown("/users"), async {
+        // the copy's headers are in, its body isn't
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        queue.stats().in_flight
+    });
+
+    assert_eq!(response.unwrap().status, 200);
+    assert_eq!(hedging.stats().wins, 1);
+    // the original's slot and the copy's
+    assert_eq!(in_flight, 2);
+    assert_eq!(queue.stats().in_flight, 0);
+}
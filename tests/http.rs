@@ -1,5 +1,12 @@
-use echo_http::{Echo, Headers, RequestConfig};
+#![allow(clippy::field_reassign_with_default)]
+
+use echo_http::mock::MockAdapter;
+use echo_http::{Headers, RequestConfig};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+mod common;
+use common::mock_echo;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,9 +17,28 @@ struct Post {
     body: String,
 }
 
+// answers like jsonplaceholder.typicode.com, without the network
+fn jsonplaceholder() -> MockAdapter {
+    let mock = MockAdapter::new();
+    let post = json!({ "userId": 1, "id": 1, "title": "title", "body": "body" });
+
+    mock.on_get("/posts/1").reply(200, &post);
+    mock.on_get("/users/1")
+        .reply(200, json!({ "id": 1, "name": "Leanne Graham" }));
+    mock.on_post("/posts/").with_body(&post).reply(
+        201,
+        json!({ "userId": 1, "id": 101, "title": "title", "body": "body" }),
+    );
+    mock.on_post("/posts*").reply(201, json!({ "id": 101 }));
+    mock.on_put("/posts/1").with_body(&post).reply(200, &post);
+    mock.on_delete("/posts/1").reply(200, json!({}));
+    mock
+}
+
 #[tokio::test]
 async fn test_get() {
-    let echo = Echo::configure(None);
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, RequestConfig::default());
     let posts = echo
         .get::<Post>("https://jsonplaceholder.typicode.com/posts/1")
         .await
@@ -29,16 +55,22 @@ async fn test_get_unknown() {
     let mut config = RequestConfig::default();
     config.base_url = Some("https://jsonplaceholder.typicode.com/".to_string());
 
-    let echo = Echo::configure(Some(config));
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, config);
 
     let response = echo.get_unknown("/users/1").await.unwrap();
 
-    assert_eq!(response.status_text, "OK")
+    assert_eq!(response.status_text, "OK");
+    assert_eq!(
+        mock.requests()[0].url.as_str(),
+        "https://jsonplaceholder.typicode.com/users/1"
+    );
 }
 
 #[tokio::test]
 async fn test_post() {
-    let echo = Echo::configure(None);
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, RequestConfig::default());
     let new_post = Post {
         user_id: 1,
         id: 1,
@@ -61,14 +93,16 @@ async fn test_post() {
 // uses serde_json::Value && None
 #[tokio::test]
 async fn test_post_no_data() {
-    let echo = Echo::configure(None);
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, RequestConfig::default());
 
     let response = echo
         .post::<serde_json::Value>("https://jsonplaceholder.typicode.com/posts/", None)
         .await
         .unwrap();
     assert_eq!(response.status, 201);
-    assert_eq!(response.status_text, "Created")
+    assert_eq!(response.status_text, "Created");
+    assert!(mock.requests()[0].body.is_empty());
 }
 
 // simplified version of test_post_no_data
@@ -81,17 +115,23 @@ async fn test_post_no_data_2() {
     headers.insert("Content-Type: application/json");
     config.headers = Some(headers.clone());
 
-    let echo = Echo::configure(Some(config.clone()));
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, config.clone());
 
     let response = echo.post_no("posts").await.unwrap();
 
     assert_eq!(response.status, 201);
-    assert_eq!(response.status_text, "Created")
+    assert_eq!(response.status_text, "Created");
+    assert_eq!(
+        mock.requests()[0].headers["content-type"],
+        "application/json"
+    );
 }
 
 #[tokio::test]
 async fn test_put() {
-    let echo = Echo::configure(None);
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, RequestConfig::default());
 
     let new_post = Post {
         user_id: 1,
@@ -114,7 +154,8 @@ async fn test_put() {
 
 #[tokio::test]
 async fn test_delete() {
-    let echo = Echo::configure(None);
+    let mock = jsonplaceholder();
+    let echo = mock_echo(&mock, RequestConfig::default());
 
     let deleted = echo
         .delete("https://jsonplaceholder.typicode.com/posts/1")
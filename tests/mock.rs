@@ -0,0 +1,250 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{EchoError, ErrorCode, Headers, Params, RequestConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+mod common;
+use common::{mock_echo, API};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+}
+
+#[tokio::test]
+async fn test_reply_with_json() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users/*")
+        .reply(200, json!({ "id": 1, "name": "Test" }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get::<User>("/users/1").await.unwrap();
+
+    assert_eq!(
+        response.data,
+        User {
+            id: 1,
+            name: "Test".to_string()
+        }
+    );
+    assert_eq!(response.headers["content-type"], "application/json");
+    assert_eq!(response.url, "https://api.example.test/users/1");
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_matches_query_headers_and_body() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users?page=2")
+        .with_header("X-Api-Key", "secret")
+        .reply(200, json!([{ "id": 3, "name": "Page 2" }]));
+    mock.on_post("/users")
+        .with_body(json!({ "id": 4, "name": "New" }))
+        .reply(201, json!({ "id": 4, "name": "New" }));
+    mock.on_any().reply(418, json!({ "error": "unexpected" }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let mut headers = Headers::new();
+    headers.insert("x-api-key: secret");
+    let mut params = Params::new();
+    params.insert("page", 2).unwrap();
+    params.insert("per_page", 10).unwrap();
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users".to_string()),
+            params: Some(params),
+            headers: Some(headers),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.data[0]["id"], 3);
+
+    // the header is missing, so this falls through to `on_any`
+    let response = echo.get_unknown("/users?page=2").await.unwrap();
+    assert_eq!(response.status, 418);
+
+    let user = User {
+        id: 4,
+        name: "New".to_string(),
+    };
+    let response = echo.post("/users", Some(user)).await.unwrap();
+    assert_eq!(response.status, 201);
+
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_reply_sequence_then_fall_through() {
+    let mock = MockAdapter::new();
+    mock.on_get("/health").reply_sequence([
+        MockReply::network_error(ErrorCode::ConnectionReset),
+        MockReply::new(503).header("retry-after", "1"),
+    ]);
+    mock.on_get("/health").reply(200, json!({ "ok": true }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let err = echo.get_unknown("/health").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::Network { .. }));
+    assert_eq!(err.code(), ErrorCode::ConnectionReset);
+    assert!(err.is_retryable());
+    assert_eq!(
+        err.context().unwrap().url,
+        "https://api.example.test/health"
+    );
+
+    let response = echo.get_unknown("/health").await.unwrap();
+    assert_eq!(response.status, 503);
+    assert_eq!(response.headers["retry-after"], "1");
+
+    let response = echo.get_unknown("/health").await.unwrap();
+    assert_eq!(response.data["ok"], true);
+
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_delay_and_timeout() {
+    let mock = MockAdapter::new();
+    mock.on_get("/slow")
+        .reply_with(MockReply::json(200, &json!({})).delay(Duration::from_millis(1500)));
+    mock.on_get("/fast")
+        .reply_with(MockReply::json(200, &json!({})).delay(Duration::from_millis(20)));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            timeout: Some(1),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/fast").await.unwrap();
+    assert!(response.timings.total >= Duration::from_millis(20));
+
+    let err = echo.get_unknown("/slow").await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::TimedOut);
+}
+
+#[tokio::test]
+async fn test_records_every_request() {
+    let mock = MockAdapter::new();
+    mock.on_post("/users").reply(201, json!({ "id": 1 }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    echo.post("/users", Some(json!({ "name": "Test" })))
+        .await
+        .unwrap();
+    let err = echo.get_unknown("/missing").await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::Network);
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].method, reqwest::Method::POST);
+    assert_eq!(requests[0].headers["content-type"], "application/json");
+    assert_eq!(
+        requests[0].json::<Value>().unwrap(),
+        json!({ "name": "Test" })
+    );
+    assert_eq!(requests[1].url.as_str(), "https://api.example.test/missing");
+    assert!(requests[1].body.is_empty());
+
+    mock.reset();
+    assert!(mock.requests().is_empty());
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_patch_and_head() {
+    let mock = MockAdapter::new();
+    mock.on_patch("/users/1")
+        .with_body(json!({ "name": "New" }))
+        .reply(200, json!({ "id": 1, "name": "New" }));
+    mock.on_head("/users/1")
+        .reply_with(MockReply::new(200).header("etag", "\"v2\""));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let response = echo
+        .patch("/users/1", Some(json!({ "name": "New" })))
+        .await
+        .unwrap();
+    assert_eq!(response.data["name"], "New");
+
+    let response = echo.head("/users/1").await.unwrap();
+    assert_eq!(response.headers["etag"], "\"v2\"");
+
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_invalid_reply_fails_the_request() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users/1")
+        .reply_with(MockReply::new(200).header("not a header", "x"));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let err = echo.get_unknown("/users/1").await.unwrap_err();
+
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert!(err.to_string().contains("invalid mock reply"));
+}
+
+#[tokio::test]
+#[should_panic(expected = "expected DELETE /users/1 (called 0 times)")]
+async fn test_verify_reports_unmet_expectations() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users/1").times(2).reply(200, json!({}));
+    mock.on_delete("/users/1").reply(204, ());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("/users/1").await.unwrap();
+    echo.get_unknown("/users/1").await.unwrap();
+    // `times(2)` is used up, so the third call matches nothing
+    assert!(echo.get_unknown("/users/1").await.is_err());
+
+    mock.verify();
+}
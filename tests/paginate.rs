@@ -0,0 +1,334 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{
+    Cursor, ErrorCode, LinkHeader, NextPage, Offset, PageNumber, PageState, PageStrategy, Params,
+    RequestConfig, ResponseUnknown,
+};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+mod common;
+use common::{mock_echo, API};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Repo {
+    id: u64,
+}
+
+fn request(url: &str, params: &[(&str, &str)]) -> RequestConfig<'static> {
+    let mut query = Params::new();
+    for (key, value) in params {
+        query.insert(key, value).unwrap();
+    }
+    RequestConfig {
+        url: Some(url.to_string()),
+        params: (!params.is_empty()).then_some(query),
+        ..Default::default()
+    }
+}
+
+fn queries(mock: &MockAdapter) -> Vec<String> {
+    mock.requests()
+        .iter()
+        .map(|request| request.url.query().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_link_header_is_followed() {
+    let mock = MockAdapter::new();
+    mock.on_get("/repos").with_query([("page", "2")]).reply_with(
+        MockReply::json(200, &json!([{ "id": 3 }])).header(
+            "Link",
+            r#"<https://api.example.test/repos?page=1>; rel="prev first", <https://other.example.test/repos?page=3&t=a,b>; rel="next""#,
+        ),
+    );
+    mock.on_get("https://other.example.test/repos")
+        .reply(200, json!([{ "id": 4 }]));
+    mock.on_get("/repos").reply_with(
+        MockReply::json(200, &json!([{ "id": 1 }, { "id": 2 }]))
+            .header("Link", r#"</repos?page=2>; title="a, b"; rel=next"#),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let repos: Vec<Repo> = echo
+        .paginate(request("/repos", &[("sort", "name")]), LinkHeader)
+        .items::<Repo>()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    let ids: Vec<u64> = repos.iter().map(|repo| repo.id).collect();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    let urls: Vec<String> = mock
+        .requests()
+        .iter()
+        .map(|request| request.url.to_string())
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "https://api.example.test/repos?sort=name",
+            "https://api.example.test/repos?page=2",
+            "https://other.example.test/repos?page=3&t=a,b",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_a_next_link_to_the_same_page_ends_the_walk() {
+    let mock = MockAdapter::new();
+    mock.on_get("/repos")
+        .with_query([("page", "2")])
+        .reply_with(
+            MockReply::json(200, &json!([{ "id": 2 }]))
+                .header("Link", r#"</repos?page=2>; rel="next""#),
+        );
+    mock.on_get("/repos").reply_with(
+        MockReply::json(200, &json!([{ "id": 1 }]))
+            .header("Link", r#"</repos?page=2>; rel="next""#),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let ids: Vec<u64> = echo
+        .paginate(request("/repos", &[]), LinkHeader)
+        .items::<Repo>()
+        .map(|repo| repo.unwrap().id)
+        .collect()
+        .await;
+
+    assert_eq!(ids, [1, 2]);
+    assert_eq!(queries(&mock), ["", "page=2"]);
+}
+
+#[tokio::test]
+async fn test_cursor_until_it_runs_out() {
+    let mock = MockAdapter::new();
+    mock.on_get("/events").with_query([("cursor", "c2")]).reply(
+        200,
+        json!({ "data": [{ "id": 3 }], "meta": { "next": "" } }),
+    );
+    mock.on_get("/events").reply(
+        200,
+        json!({ "data": [{ "id": 1 }, { "id": 2 }], "meta": { "next": "c2" } }),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let pages: Vec<ResponseUnknown> = echo
+        .paginate(
+            request("/events", &[("limit", "2")]),
+            Cursor::new("/meta/next", "cursor"),
+        )
+        .items_at("/data")
+        .pages()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].pointer("/data/0/id"), Some(&json!(3)));
+    assert_eq!(queries(&mock), ["limit=2", "limit=2&cursor=c2"]);
+}
+
+#[tokio::test]
+async fn test_page_numbers_stop_at_a_short_page() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users")
+        .with_query([("page", "0")])
+        .reply(200, json!([{ "id": 1 }, { "id": 2 }]));
+    mock.on_get("/users")
+        .with_query([("page", "1")])
+        .reply(200, json!([{ "id": 3 }]));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let ids: Vec<Value> = echo
+        .paginate(
+            request("/users", &[]),
+            PageNumber::new("page")
+                .starting_at(0)
+                .per_page("per_page", 2),
+        )
+        .items::<Value>()
+        .map(|user| user.unwrap()["id"].clone())
+        .collect()
+        .await;
+
+    assert_eq!(ids, [json!(1), json!(2), json!(3)]);
+    assert_eq!(queries(&mock), ["page=0&per_page=2", "page=1&per_page=2"]);
+}
+
+#[tokio::test]
+async fn test_offsets_and_limits() {
+    let mock = MockAdapter::new();
+    mock.on_get("/logs")
+        .reply(200, json!({ "items": [{ "id": 1 }, { "id": 2 }] }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+    let logs = || request("/logs", &[]);
+
+    let pages = echo
+        .paginate(logs(), Offset::new("offset", "limit", 2))
+        .items_at("/items")
+        .max_pages(3)
+        .pages()
+        .count()
+        .await;
+    assert_eq!(pages, 3);
+    assert_eq!(
+        queries(&mock),
+        ["offset=0&limit=2", "offset=2&limit=2", "offset=4&limit=2"]
+    );
+
+    mock.reset();
+    mock.on_get("/logs")
+        .reply(200, json!({ "items": [{ "id": 1 }, { "id": 2 }] }));
+    let items = echo
+        .paginate(logs(), Offset::new("offset", "limit", 2))
+        .items_at("/items")
+        .max_items(3)
+        .items::<Repo>()
+        .count()
+        .await;
+    // the second page reached the limit, no third one
+    assert_eq!(items, 3);
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_a_failed_page_ends_the_stream() {
+    let mock = MockAdapter::new();
+    mock.on_get("/users")
+        .with_query([("page", "2")])
+        .reply(503, json!({ "error": "down" }));
+    mock.on_get("/users")
+        .with_query([("page", "1")])
+        .reply(200, json!([{ "id": 1 }]));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let users: Vec<Result<Repo, _>> = echo
+        .paginate(request("/users", &[]), PageNumber::new("page"))
+        .items::<Repo>()
+        .collect()
+        .await;
+
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].as_ref().unwrap(), &Repo { id: 1 });
+    let err = users[1].as_ref().unwrap_err();
+    assert_eq!(err.status(), Some(503));
+    assert_eq!(
+        err.context().unwrap().url,
+        "https://api.example.test/users?page=2"
+    );
+
+    mock.reset();
+    mock.on_get("/users")
+        .network_error(ErrorCode::ConnectionReset);
+    let mut pages = std::pin::pin!(echo
+        .paginate(request("/users", &[]), PageNumber::new("page"))
+        .pages());
+    let err = pages.next().await.unwrap().unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ConnectionReset);
+    assert!(pages.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_an_item_that_does_not_decode_ends_the_stream() {
+    let mock = MockAdapter::new();
+    mock.on_get("/repos")
+        .reply(200, json!([{ "id": 1 }, { "id": "two" }, { "id": 3 }]));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let repos: Vec<Result<Repo, _>> = echo
+        .paginate(request("/repos", &[]), LinkHeader)
+        .items::<Repo>()
+        .collect()
+        .await;
+
+    assert_eq!(repos.len(), 2);
+    assert_eq!(repos[0].as_ref().unwrap(), &Repo { id: 1 });
+    let err = repos[1].as_ref().unwrap_err();
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert_eq!(err.decode_error().unwrap().path, "$[1].id");
+}
+
+/// `{ "results": [..], "next_page_url": ".." }`
+struct NextPageUrl;
+
+impl PageStrategy for NextPageUrl {
+    fn next_page(&self, page: &ResponseUnknown<'_>, state: &PageState) -> Option<NextPage> {
+        assert_eq!(state.items, 1);
+        let url = page.pointer("/next_page_url")?.as_str()?;
+        Some(NextPage::Url(url.to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_custom_strategy() {
+    let mock = MockAdapter::new();
+    mock.on_get("/search").reply(
+        200,
+        json!({ "results": [{ "id": 1 }], "next_page_url": "/search/2" }),
+    );
+    mock.on_get("/search/2")
+        .reply(200, json!({ "results": [{ "id": 2 }] }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            ..Default::default()
+        },
+    );
+
+    let repos: Vec<Repo> = echo
+        .paginate(request("/search", &[("q", "echo")]), NextPageUrl)
+        .items_at("/results")
+        .items::<Repo>()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(repos, [Repo { id: 1 }, Repo { id: 2 }]);
+    assert_eq!(mock.requests()[1].url.path(), "/search/2");
+    assert_eq!(queries(&mock), ["q=echo", ""]);
+}
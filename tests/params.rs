@@ -0,0 +1,275 @@
+use echo_http::{
+    ArrayFormat, Echo, EchoError, Params, ParamsSerializer, RequestConfig, UrlErrorReason,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+mod common;
+use common::echo_server;
+
+#[derive(Serialize)]
+struct Filter {
+    name: String,
+    tags: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Search {
+    q: String,
+    page: u32,
+    missing: Option<u32>,
+    filter: Filter,
+}
+
+fn serializer(array_format: ArrayFormat) -> ParamsSerializer {
+    ParamsSerializer {
+        array_format,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_array_formats() {
+    let mut params = Params::new();
+    params.append("id", 1).unwrap();
+    params.append("id", 2).unwrap();
+
+    assert_eq!(
+        params.to_query_string(&serializer(ArrayFormat::Repeat)),
+        "id=1&id=2"
+    );
+    assert_eq!(
+        params.to_query_string(&serializer(ArrayFormat::Brackets)),
+        "id[]=1&id[]=2"
+    );
+    assert_eq!(
+        params.to_query_string(&serializer(ArrayFormat::Indices)),
+        "id[0]=1&id[1]=2"
+    );
+    assert_eq!(
+        params.to_query_string(&serializer(ArrayFormat::Comma)),
+        "id=1,2"
+    );
+}
+
+#[test]
+fn test_commas_inside_comma_array_values_are_escaped() {
+    let split = Params::try_from(vec![("tag", "a"), ("tag", "b"), ("tag", "c")]).unwrap();
+    let joined = Params::try_from(vec![("tag", "a,b"), ("tag", "c")]).unwrap();
+    let comma = serializer(ArrayFormat::Comma);
+
+    assert_eq!(split.to_query_string(&comma), "tag=a,b,c");
+    assert_eq!(joined.to_query_string(&comma), "tag=a%2Cb,c");
+    // a lone value has nothing to be confused with
+    let single = Params::try_from(vec![("tag", "a,b")]).unwrap();
+    assert_eq!(single.to_query_string(&comma), "tag=a,b");
+}
+
+#[test]
+fn test_from_serialize_keeps_order_and_nests() {
+    let search = Search {
+        q: "hello world".to_string(),
+        page: 2,
+        missing: None,
+        filter: Filter {
+            name: "a&b".to_string(),
+            tags: vec!["x", "y"],
+        },
+    };
+
+    let params = Params::from_serialize(&search).unwrap();
+
+    assert_eq!(
+        params.to_query_string(&ParamsSerializer::default()),
+        "q=hello%20world&page=2&filter[name]=a%26b&filter[tags][]=x&filter[tags][]=y"
+    );
+}
+
+#[test]
+fn test_from_serialize_rejects_non_objects() {
+    assert!(Params::from_serialize(&vec![1, 2]).is_err());
+
+    let unserializable = std::collections::BTreeMap::from([((1, 2), "x")]);
+    let err = Params::from_serialize(&unserializable).unwrap_err();
+    assert!(matches!(err, EchoError::InvalidParams(_)));
+}
+
+#[test]
+fn test_unserializable_values_are_rejected() {
+    // JSON object keys must be strings
+    let unserializable = std::collections::BTreeMap::from([((1, 2), "x")]);
+    let mut params = Params::new();
+
+    let err = params.insert("point", &unserializable).unwrap_err();
+    assert!(matches!(err, EchoError::InvalidParams(ref reason) if reason.contains("`point`")));
+    assert!(params.append("point", &unserializable).is_err());
+    assert!(Params::try_from(vec![("point", unserializable)]).is_err());
+    assert!(params.is_empty());
+}
+
+#[test]
+fn test_custom_serializer() {
+    let params = Params::try_from(vec![("a", 1), ("b", 2)]).unwrap();
+    let serializer = ParamsSerializer {
+        serialize: Some(Arc::new(|params: &Params| {
+            params.params.keys().cloned().collect::<Vec<_>>().join(";")
+        })),
+        ..Default::default()
+    };
+
+    assert_eq!(params.to_query_string(&serializer), "a;b");
+}
+
+#[tokio::test]
+async fn test_request_params_merge_with_instance_params() {
+    let mut instance_params = Params::new();
+    instance_params.insert("api_key", "abc").unwrap();
+    instance_params.insert("page", 1).unwrap();
+
+    let config = RequestConfig {
+        base_url: Some(echo_server().await),
+        params: Some(instance_params),
+        params_serializer: Some(serializer(ArrayFormat::Repeat)),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let mut request_params = Params::new();
+    request_params.insert("page", 3).unwrap();
+    request_params.insert("id", vec![1, 2]).unwrap();
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/search".to_string()),
+            params: Some(request_params),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.data["path"],
+        "/search?api_key=abc&page=3&id=1&id=2"
+    );
+}
+
+#[tokio::test]
+async fn test_path_params() {
+    let config = RequestConfig {
+        base_url: Some(echo_server().await),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let mut path_params = Params::new();
+    path_params.insert("id", "john doe").unwrap();
+    path_params.insert("post_id", 42).unwrap();
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users/{id}/posts/{post_id}".to_string()),
+            path_params: Some(path_params),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.data["path"], "/users/john%20doe/posts/42");
+    assert!(response.request.ends_with("/users/john%20doe/posts/42"));
+}
+
+#[tokio::test]
+async fn test_path_params_with_the_shorthand_methods() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(echo_server().await),
+        ..Default::default()
+    }));
+    let mut path_params = Params::new();
+    path_params.insert("id", "john doe").unwrap();
+
+    let url = path_params.render("/users/{id}?q={id}").unwrap();
+    assert_eq!(url, "/users/john%20doe?q={id}");
+    let response = echo.get_unknown(&url).await.unwrap();
+    assert_eq!(response.data["path"], "/users/john%20doe?q={id}");
+
+    let reason = |err: EchoError| match err.kind() {
+        EchoError::UrlError { reason, .. } => reason.clone(),
+        other => panic!("unexpected error: {:?}", other),
+    };
+    assert_eq!(
+        reason(path_params.render("/users").unwrap_err()),
+        UrlErrorReason::UnusedPathParams(vec!["id".to_string()])
+    );
+    // a placeholder is never sent as is
+    assert_eq!(
+        reason(echo.get_unknown("/users/{id}").await.unwrap_err()),
+        UrlErrorReason::MissingPathParams(vec!["id".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn test_missing_path_param_is_an_error() {
+    let mut path_params = Params::new();
+    path_params.insert("post_id", 1).unwrap();
+    let echo = Echo::configure(Some(RequestConfig {
+        path_params: Some(path_params),
+        ..Default::default()
+    }));
+
+    let result = echo
+        .get::<serde_json::Value>("http://127.0.0.1:1/users/{id}")
+        .await;
+
+    match result.as_ref().map_err(EchoError::kind) {
+        Err(EchoError::UrlError { url, reason }) => {
+            assert_eq!(url, "http://127.0.0.1:1/users/{id}");
+            assert_eq!(
+                *reason,
+                UrlErrorReason::MissingPathParams(vec!["id".to_string()])
+            );
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_instance_path_params_are_shared() {
+    let mut path_params = Params::new();
+    path_params.insert("version", "v2").unwrap();
+    let config = RequestConfig {
+        base_url: Some(echo_server().await),
+        path_params: Some(path_params),
+        ..Default::default()
+    };
+    let echo = Echo::configure(Some(config));
+
+    let versioned = echo
+        .get_unknown("/{version}/users?q={version}")
+        .await
+        .unwrap();
+    assert_eq!(versioned.data["path"], "/v2/users?q={version}");
+
+    // the instance's path params don't have to be used by every request
+    let unrelated = echo.get_unknown("/health").await.unwrap();
+    assert_eq!(unrelated.data["path"], "/health");
+
+    let mut path_params = Params::new();
+    path_params.insert("id", 1).unwrap();
+    let err = echo
+        .request_unknown(RequestConfig {
+            url: Some("/{version}/users".to_string()),
+            path_params: Some(path_params),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    match err.kind() {
+        EchoError::UrlError { reason, .. } => {
+            assert_eq!(
+                *reason,
+                UrlErrorReason::UnusedPathParams(vec!["id".to_string()])
+            );
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
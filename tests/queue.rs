@@ -0,0 +1,163 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{EchoError, ErrorCode, Priority, RequestConfig, RequestQueue};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::mock_echo;
+
+fn slow_mock(delay: u64) -> MockAdapter {
+    let mock = MockAdapter::new();
+    mock.on_any()
+        .reply_with(MockReply::new(200).delay(Duration::from_millis(delay)));
+    mock
+}
+
+fn with_priority(url: &str, priority: Priority) -> RequestConfig<'static> {
+    RequestConfig {
+        url: Some(url.to_string()),
+        priority: Some(priority),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_higher_priorities_go_first() {
+    let mock = slow_mock(30);
+    let queue = Arc::new(RequestQueue::new(1));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            queue: Some(queue.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (first, low, normal, high) = tokio::join!(
+        echo.get_unknown("https://api.example.test/first"),
+        echo.request_unknown(with_priority("https://api.example.test/low", Priority::Low)),
+        echo.get_unknown("https://api.example.test/normal"),
+        echo.request_unknown(with_priority(
+            "https://api.example.test/high",
+            Priority::High
+        )),
+    );
+
+    let paths: Vec<String> = mock
+        .requests()
+        .iter()
+        .map(|request| request.url.path()[1..].to_string())
+        .collect();
+    assert_eq!(paths, ["first", "high", "normal", "low"]);
+
+    assert_eq!(first.unwrap().timings.queued, Duration::ZERO);
+    assert!(high.unwrap().timings.queued >= Duration::from_millis(25));
+    assert!(normal.unwrap().timings.queued >= Duration::from_millis(55));
+    assert!(low.unwrap().timings.queued >= Duration::from_millis(85));
+
+    let stats = queue.stats();
+    assert_eq!((stats.in_flight, stats.queued), (0, 0));
+    assert_eq!(stats.sent, 4);
+    assert!(stats.longest_wait >= Duration::from_millis(85));
+    assert!(stats.total_wait >= Duration::from_millis(165));
+}
+
+#[tokio::test]
+async fn test_max_per_host() {
+    let mock = slow_mock(50);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            queue: Some(Arc::new(RequestQueue::default().max_per_host(1))),
+            ..Default::default()
+        },
+    );
+
+    let started = Instant::now();
+    let (a1, b1, a2, b2) = tokio::join!(
+        echo.get_unknown("https://a.example.test/1"),
+        echo.get_unknown("https://b.example.test/1"),
+        echo.get_unknown("https://a.example.test/2"),
+        echo.get_unknown("https://b.example.test/2"),
+    );
+    let elapsed = started.elapsed();
+
+    // the hosts don't wait for each other
+    assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(150), "{elapsed:?}");
+    assert_eq!(a1.unwrap().timings.queued, Duration::ZERO);
+    assert_eq!(b1.unwrap().timings.queued, Duration::ZERO);
+    assert!(a2.unwrap().timings.queued >= Duration::from_millis(45));
+    assert!(b2.unwrap().timings.queued >= Duration::from_millis(45));
+}
+
+#[test]
+#[should_panic(expected = "at least one request in flight")]
+fn test_zero_limits_are_rejected() {
+    RequestQueue::new(0);
+}
+
+#[test]
+#[should_panic(expected = "at least one request in flight")]
+fn test_zero_per_host_limits_are_rejected() {
+    RequestQueue::default().max_per_host(0);
+}
+
+#[tokio::test]
+async fn test_max_wait() {
+    let mock = slow_mock(100);
+    let queue = Arc::new(RequestQueue::new(1).max_wait(Duration::from_millis(20)));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            queue: Some(queue.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (first, second) = tokio::join!(echo.get_unknown("https://api.example.test/first"), async {
+        // lets the first one take the slot
+        tokio::task::yield_now().await;
+        let stats = queue.stats();
+        assert_eq!((stats.in_flight, stats.queued), (1, 0));
+        echo.get_unknown("https://api.example.test/second").await
+    });
+
+    first.unwrap();
+    let err = second.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::QueueTimeout(_)), "{err:?}");
+    assert_eq!(err.code(), ErrorCode::TimedOut);
+    assert_eq!(queue.stats().timed_out, 1);
+    assert_eq!(queue.stats().sent, 1);
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_dropped_requests_leave_the_queue() {
+    let mock = slow_mock(50);
+    let queue = Arc::new(RequestQueue::new(1));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            queue: Some(queue.clone()),
+            ..Default::default()
+        },
+    );
+
+    let stats = tokio::select! {
+        _ = echo.get_unknown("https://api.example.test/first") => unreachable!("still in flight"),
+        _ = echo.get_unknown("https://api.example.test/waiting") => unreachable!("the slot is taken"),
+        stats = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            queue.stats()
+        } => stats,
+    };
+
+    assert_eq!((stats.in_flight, stats.queued), (1, 1));
+    assert!(stats.oldest_waiting >= Duration::from_millis(10));
+    let stats = queue.stats();
+    assert_eq!((stats.in_flight, stats.queued), (0, 0));
+    echo.get_unknown("https://api.example.test/after")
+        .await
+        .unwrap();
+}
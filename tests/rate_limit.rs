@@ -0,0 +1,202 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{EchoError, ErrorCode, RateLimit, RateLimiter, RequestConfig};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::mock_echo;
+
+fn ok_mock() -> MockAdapter {
+    let mock = MockAdapter::new();
+    mock.on_any().reply(200, ());
+    mock
+}
+
+#[tokio::test]
+async fn test_fail_fast_per_host() {
+    let mock = ok_mock();
+    let limiter = Arc::new(RateLimiter::new(RateLimit::per_minute(2)).fail_fast());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            rate_limit: Some(limiter.clone()),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("https://a.example.test/1").await.unwrap();
+    echo.get_unknown("https://a.example.test/2").await.unwrap();
+    let err = echo
+        .get_unknown("https://a.example.test/3")
+        .await
+        .unwrap_err();
+
+    let EchoError::RateLimited { key, retry_after } = err.kind() else {
+        panic!("expected RateLimited, got {err:?}");
+    };
+    assert_eq!(key, "a.example.test");
+    assert!(*retry_after > Duration::from_secs(25), "{retry_after:?}");
+    assert_eq!(err.code(), ErrorCode::RateLimited);
+    assert!(err.is_retryable());
+    assert_eq!(limiter.available("a.example.test"), 0);
+
+    // other hosts have a bucket of their own
+    echo.get_unknown("https://b.example.test:8080/1")
+        .await
+        .unwrap();
+    assert_eq!(limiter.available("b.example.test:8080"), 1);
+    assert_eq!(mock.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_waits_for_tokens() {
+    let mock = ok_mock();
+    let limiter = RateLimiter::new(RateLimit::per_second(20).leaky()).per_instance();
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            rate_limit: Some(Arc::new(limiter)),
+            ..Default::default()
+        },
+    );
+
+    let started = Instant::now();
+    let (a1, b1, a2, b2) = tokio::join!(
+        echo.get_unknown("https://a.example.test"),
+        echo.get_unknown("https://b.example.test"),
+        echo.get_unknown("https://a.example.test"),
+        echo.get_unknown("https://b.example.test"),
+    );
+    for response in [a1, b1, a2, b2] {
+        response.unwrap();
+    }
+
+    // the first goes right away, then one every 50ms
+    assert!(started.elapsed() >= Duration::from_millis(140));
+    assert_eq!(mock.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_cancelled_requests_give_their_token_back() {
+    let mock = ok_mock();
+    let limiter = Arc::new(RateLimiter::new(RateLimit::per_second(10).leaky()).per_instance());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            rate_limit: Some(limiter.clone()),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("https://a.example.test").await.unwrap();
+    let waiting = echo.get_unknown("https://a.example.test");
+    tokio::time::timeout(Duration::from_millis(20), waiting)
+        .await
+        .unwrap_err();
+
+    // the token it waited for is back, a full bucket 100ms after the first request
+    tokio::time::sleep(Duration::from_millis(130)).await;
+    assert_eq!(limiter.available("*"), 1);
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "non-zero duration")]
+fn test_zero_limits_are_rejected() {
+    RateLimiter::new(RateLimit::new(5, Duration::ZERO));
+}
+
+#[tokio::test]
+async fn test_burst_and_custom_keys() {
+    let mock = ok_mock();
+    let limiter = Arc::new(
+        RateLimiter::new(RateLimit::per_minute(60).burst(3))
+            .fail_fast()
+            .key_by(|request| {
+                let tenant = request.url().path_segments().and_then(|mut s| s.next());
+                tenant.unwrap_or_default().to_string()
+            }),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            rate_limit: Some(limiter.clone()),
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..3 {
+        echo.get_unknown("https://api.example.test/acme/users")
+            .await
+            .unwrap();
+    }
+    assert!(echo
+        .get_unknown("https://api.example.test/acme/users")
+        .await
+        .is_err());
+    echo.get_unknown("https://api.example.test/globex/users")
+        .await
+        .unwrap();
+
+    assert_eq!(limiter.available("acme"), 0);
+    assert_eq!(limiter.available("globex"), 2);
+    assert_eq!(limiter.limit().burst, 3);
+}
+
+#[tokio::test]
+async fn test_adaptive_limits() {
+    let mock = MockAdapter::new();
+    mock.on_get("/throttled")
+        .reply_with(MockReply::new(429).header("retry-after", "30"));
+    mock.on_get("/exhausted").reply_with(
+        MockReply::new(200)
+            .header("x-ratelimit-remaining", "0")
+            .header("x-ratelimit-reset", "120"),
+    );
+    mock.on_get("/remaining")
+        .reply_with(MockReply::new(200).header("ratelimit-remaining", "2"));
+    let limiter = Arc::new(
+        RateLimiter::new(RateLimit::per_minute(100))
+            .fail_fast()
+            .adaptive(),
+    );
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            rate_limit: Some(limiter.clone()),
+            ..Default::default()
+        },
+    );
+
+    echo.get_unknown("https://a.example.test/remaining")
+        .await
+        .unwrap();
+    assert_eq!(limiter.available("a.example.test"), 2);
+
+    let response = echo
+        .get_unknown("https://b.example.test/throttled")
+        .await
+        .unwrap();
+    assert_eq!(response.status, 429);
+    let err = echo
+        .get_unknown("https://b.example.test/throttled")
+        .await
+        .unwrap_err();
+    let EchoError::RateLimited { retry_after, .. } = err.kind() else {
+        panic!("expected RateLimited, got {err:?}");
+    };
+    assert!(*retry_after > Duration::from_secs(29), "{retry_after:?}");
+
+    echo.get_unknown("https://c.example.test/exhausted")
+        .await
+        .unwrap();
+    let err = echo
+        .get_unknown("https://c.example.test/exhausted")
+        .await
+        .unwrap_err();
+    let EchoError::RateLimited { retry_after, .. } = err.kind() else {
+        panic!("expected RateLimited, got {err:?}");
+    };
+    assert!(*retry_after > Duration::from_secs(119), "{retry_after:?}");
+    assert_eq!(mock.requests().len(), 3);
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::field_reassign_with_default, clippy::unnecessary_operation)]
+
 use echo_http::{request_config::ResponseType, Headers, RequestConfig};
 use reqwest::Method;
 
@@ -11,6 +13,7 @@ fn test_default() {
     assert_eq!(config.headers, None);
     assert_eq!(config.params, None);
     assert_eq!(config.data, None);
+    assert!(config.cancel_token.is_none());
     assert_eq!(config.response_type, ResponseType::Json);
 }
 
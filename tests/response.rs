@@ -1,18 +1,24 @@
-use echo_http::request_config::ResponseType;
-use echo_http::{Headers, RequestConfig, Response, ResponseUnknown};
+use echo_http::mock::MockAdapter;
+use echo_http::{
+    BaseUrls, Echo, EchoError, ErrorCode, Headers, RequestConfig, RequestInfo, Response,
+    ResponseUnknown, Strategy,
+};
 use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::{echo_server, mock_echo, redirect_server};
 
 #[test]
 fn test_response_creation() {
     let mut headers = Headers::new();
     headers.insert("Content-Type: application/json");
 
-    let mut config = RequestConfig::default();
-    config.base_url = Some("https://api.example.com".to_string());
-    config.timeout = Some(30);
-    config.headers = Some(headers.clone());
-    config.params = None;
-    config.response_type = ResponseType::Json;
+    let config = RequestInfo {
+        base_url: Some("https://api.example.com".to_string()),
+        headers: Some(headers.clone()),
+        ..Default::default()
+    };
 
     let response = Response {
         data: json!({ "message": "success" }),
@@ -21,6 +27,7 @@ fn test_response_creation() {
         headers: headers.into(),
         config,
         request: "https://api.example.com/test".to_string(),
+        ..Default::default()
     };
 
     assert_eq!(response.status, 200);
@@ -34,12 +41,11 @@ fn test_response_unknown() {
     let mut headers = Headers::new();
     headers.insert("Content-Type: application/json");
 
-    let mut config = RequestConfig::default();
-    config.base_url = Some("https://api.example.com".to_string());
-    config.timeout = Some(30);
-    config.headers = Some(headers.clone());
-    config.params = None;
-    config.response_type = ResponseType::Json;
+    let config = RequestInfo {
+        base_url: Some("https://api.example.com".to_string()),
+        headers: Some(headers.clone()),
+        ..Default::default()
+    };
 
     let response = Response {
         data: json!({ "id": 123, "name": "Test" }),
@@ -48,6 +54,7 @@ fn test_response_unknown() {
         headers: headers.into(),
         config,
         request: "https://api.example.com/users".to_string(),
+        ..Default::default()
     };
 
     let response_unknown = ResponseUnknown { inner: response };
@@ -57,3 +64,312 @@ fn test_response_unknown() {
     assert_eq!(response_unknown.request, "https://api.example.com/users");
     assert_eq!(response_unknown.data["id"], 123);
 }
+
+fn unknown(data: serde_json::Value) -> ResponseUnknown<'static> {
+    ResponseUnknown {
+        inner: Response {
+            data,
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: Headers::new().into(),
+            config: RequestInfo::default(),
+            request: "https://api.example.com/users".to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+}
+
+#[test]
+fn test_response_unknown_into_typed() {
+    let response = unknown(json!({ "id": 1, "name": "Test" }));
+
+    let typed = response.json::<User>().unwrap();
+    assert_eq!(typed.status, 200);
+    assert_eq!(typed.request, "https://api.example.com/users");
+    assert_eq!(typed.data.name, "Test");
+
+    let typed = response.into_typed::<User>().unwrap();
+    assert_eq!(
+        typed.data,
+        User {
+            id: 1,
+            name: "Test".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_response_unknown_into_typed_reports_the_path() {
+    let response = unknown(json!([{ "id": 1, "name": "a" }, { "id": "2", "name": "b" }]));
+
+    let err = response.into_typed::<Vec<User>>().unwrap_err();
+    let decode_error = err.decode_error().unwrap();
+    assert_eq!(decode_error.path, "$[1].id");
+    assert_eq!(decode_error.url, "https://api.example.com/users");
+}
+
+#[test]
+fn test_response_unknown_pointer() {
+    let response = unknown(json!({ "data": [{ "id": 7, "tags": ["a"] }], "meta": {} }));
+
+    assert_eq!(response.pointer("/data/0/id"), Some(&json!(7)));
+    assert_eq!(response.pointer_as::<u32>("/data/0/id").unwrap(), 7);
+    assert_eq!(
+        response.pointer_as::<Vec<String>>("/data/0/tags").unwrap(),
+        vec!["a".to_string()]
+    );
+    assert_eq!(
+        response.pointer_as::<Option<String>>("/meta/next").unwrap(),
+        None
+    );
+
+    let err = response.pointer_as::<String>("/data/0/id").unwrap_err();
+    assert_eq!(err.decode_error().unwrap().path, "$.data[0].id");
+}
+
+#[test]
+fn test_response_unknown_deref_mut() {
+    let mut response = unknown(json!({ "id": 1 }));
+    response.data["id"] = json!(2);
+    response.status = 201;
+
+    let inner = response.into_inner();
+    assert_eq!(inner.status, 201);
+    assert_eq!(inner.data["id"], 2);
+}
+
+async fn redirecting_echo() -> Echo<'static> {
+    Echo::configure(Some(RequestConfig {
+        base_url: Some(redirect_server(echo_server().await).await),
+        ..Default::default()
+    }))
+}
+
+#[tokio::test]
+async fn test_response_metadata() {
+    let echo = redirecting_echo().await;
+
+    let response = echo.get_unknown("/users?page=2").await.unwrap();
+
+    assert!(response.url.ends_with("/users?page=2"));
+    assert_eq!(response.url, response.request);
+    assert!(response.redirects.is_empty());
+    assert_eq!(response.version, reqwest::Version::HTTP_11);
+    assert!(response.remote_addr.unwrap().ip().is_loopback());
+    assert_eq!(
+        response.content_length,
+        Some(response.data.to_string().len() as u64)
+    );
+    assert!(response.timings.ttfb <= response.timings.total);
+    assert!(response.timings.retries.is_zero());
+}
+
+#[tokio::test]
+async fn test_redirect_chain() {
+    let echo = redirecting_echo().await;
+
+    let response = echo.get::<serde_json::Value>("/redirect/3").await.unwrap();
+
+    assert!(response.request.ends_with("/redirect/3"));
+    assert!(response.url.ends_with("/redirect/0"));
+    assert_eq!(response.data["path"], "/redirect/0");
+
+    let hops: Vec<_> = response
+        .redirects
+        .iter()
+        .map(|redirect| {
+            (
+                redirect.status,
+                redirect.location.rsplit('/').next().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(hops, vec![(302, "2"), (302, "1"), (302, "0")]);
+}
+
+#[tokio::test]
+async fn test_see_other_switches_to_get() {
+    let echo = redirecting_echo().await;
+
+    let response = echo
+        .post::<serde_json::Value>("/see-other", Some(json!({ "name": "test" })))
+        .await
+        .unwrap();
+
+    assert_eq!(response.data["method"], "GET");
+    assert_eq!(response.data["path"], "/done");
+    assert_eq!(response.data["body"], "");
+}
+
+#[tokio::test]
+async fn test_cross_origin_redirect_drops_credentials() {
+    let echo = redirecting_echo().await;
+    let mut headers = Headers::new();
+    headers.insert("Authorization: Bearer secret");
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/away".to_string()),
+            method: reqwest::Method::PUT,
+            headers: Some(headers),
+            data: Some(json!({ "name": "test" })),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // 307 keeps the method and body
+    assert_eq!(response.data["method"], "PUT");
+    assert_eq!(response.data["path"], "/elsewhere");
+    assert_eq!(response.data["body"], r#"{"name":"test"}"#);
+    assert_eq!(response.data["authorization"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_max_redirects() {
+    let echo = redirecting_echo().await;
+
+    let err = echo.get_unknown("/loop").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::TooManyRedirects(10)));
+    assert_eq!(err.code(), ErrorCode::TooManyRedirects);
+    assert_eq!(err.status(), Some(302));
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/redirect/1".to_string()),
+            max_redirects: Some(0),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.status, 302);
+    assert!(response.redirects.is_empty());
+}
+
+#[test]
+fn test_response_map_and_into_parts() {
+    let mut headers = Headers::new();
+    headers.insert_many(vec![
+        "Content-Type: application/json",
+        "Set-Cookie: sid=secret",
+    ]);
+    let response = Response {
+        data: json!({ "items": [1, 2, 3] }),
+        status: 200,
+        headers: headers.into(),
+        url: "https://api.example.com/items".to_string(),
+        ..Default::default()
+    };
+
+    let response = response.map(|data| data["items"].as_array().unwrap().len());
+    assert_eq!(response.data, 3);
+    assert_eq!(response.url, "https://api.example.com/items");
+
+    let (meta, data) = response.into_parts();
+    assert_eq!(data, 3);
+    assert_eq!(meta.status, 200);
+
+    let meta = serde_json::to_value(&meta).unwrap();
+    assert_eq!(meta["method"], "GET");
+    assert_eq!(meta["version"], "HTTP/1.1");
+    assert_eq!(meta["timings"]["total_ms"], 0.0);
+    assert_eq!(meta["url"], "https://api.example.com/items");
+    // a summary meant for logs
+    assert_eq!(meta["headers"]["set-cookie"], "[REDACTED]");
+    assert_eq!(meta["headers"]["content-type"], "application/json");
+}
+
+#[test]
+fn test_response_status_helpers() {
+    let response = |status| Response {
+        data: (),
+        status,
+        ..Default::default()
+    };
+
+    assert!(response(204).is_success());
+    assert!(response(404).is_client_error());
+    assert!(response(503).is_server_error());
+    assert!(!response(302).is_success());
+
+    assert!(response(201).error_for_status().is_ok());
+
+    let err = response(404).error_for_status().unwrap_err();
+    assert!(matches!(err.kind(), EchoError::Status(404)));
+    assert_eq!(err.status(), Some(404));
+    assert_eq!(err.code(), ErrorCode::BadRequest);
+    assert!(response(503).error_for_status().unwrap_err().is_retryable());
+}
+
+#[tokio::test]
+async fn test_response_config_is_redacted() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(echo_server().await),
+        ..Default::default()
+    }));
+    let mut headers = Headers::new();
+    headers.insert_many(vec![
+        "Authorization: Bearer secret",
+        "X-Api-Key: key",
+        "Accept: */*",
+    ]);
+    let mut params = echo_http::Params::new();
+    params.insert("access_token", "secret").unwrap();
+    params.insert("page", 2).unwrap();
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users".to_string()),
+            method: reqwest::Method::POST,
+            headers: Some(headers),
+            params: Some(params),
+            data: Some(json!({ "password": "hunter2" })),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // the request itself went out untouched
+    assert_eq!(response.data["authorization"], "Bearer secret");
+    assert_eq!(response.data["path"], "/users?access_token=secret&page=2");
+
+    let config = &response.config;
+    let headers = &config.headers.as_ref().unwrap().headers;
+    assert_eq!(headers["Authorization"], "[REDACTED]");
+    assert_eq!(headers["X-Api-Key"], "[REDACTED]");
+    assert_eq!(headers["Accept"], "*/*");
+    let params = &config.params.as_ref().unwrap().params;
+    assert_eq!(params["access_token"], "[REDACTED]");
+    assert_eq!(params["page"], 2);
+    assert_eq!(config.method, reqwest::Method::POST);
+    assert_eq!(config.url.as_deref(), Some("/users"));
+}
+
+#[tokio::test]
+async fn test_error_for_status_keeps_the_attempt() {
+    let mock = MockAdapter::new();
+    mock.on_get("https://eu.example.test/*")
+        .network_error(ErrorCode::ConnectionRefused);
+    mock.on_get("https://us.example.test/*").reply(503, ());
+    let base_urls = BaseUrls::new(["https://eu.example.test", "https://us.example.test"])
+        .strategy(Strategy::Failover);
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_urls: Some(Arc::new(base_urls)),
+            ..Default::default()
+        },
+    );
+
+    let response = echo.get_unknown("/users").await.unwrap();
+    assert_eq!(response.attempt, 2);
+
+    let err = response.inner.error_for_status().unwrap_err();
+    assert_eq!(err.context().unwrap().attempt, 2);
+}
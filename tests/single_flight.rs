@@ -0,0 +1,204 @@
+use echo_http::mock::{MockAdapter, MockReply};
+use echo_http::{ErrorCode, Headers, RequestConfig, SingleFlight, SingleFlightStats};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::{mock_echo, API};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Settings {
+    region: String,
+}
+
+fn settings_mock() -> MockAdapter {
+    let mock = MockAdapter::new();
+    mock.on_get("/config").reply_with(
+        MockReply::json(200, &json!({ "region": "eu" })).delay(Duration::from_millis(30)),
+    );
+    mock
+}
+
+fn request(url: &str, header: Option<&'static str>) -> RequestConfig<'static> {
+    let headers = header.map(|header| {
+        let mut headers = Headers::new();
+        headers.insert(header);
+        headers
+    });
+    RequestConfig {
+        url: Some(url.to_string()),
+        headers,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_identical_requests_share_a_call() {
+    let mock = settings_mock();
+    let single_flight = Arc::new(SingleFlight::new());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            single_flight: Some(single_flight.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (typed, value, unknown) = tokio::join!(
+        echo.get::<Settings>("/config"),
+        echo.get::<Value>("/config"),
+        echo.get_unknown("/config"),
+    );
+
+    assert_eq!(
+        typed.unwrap().data,
+        Settings {
+            region: "eu".to_string()
+        }
+    );
+    assert_eq!(value.unwrap().data, json!({ "region": "eu" }));
+    let unknown = unknown.unwrap();
+    assert_eq!(unknown.status, 200);
+    assert_eq!(unknown.url, "https://api.example.test/config");
+    assert_eq!(mock.requests().len(), 1);
+    assert_eq!(
+        single_flight.stats(),
+        SingleFlightStats {
+            in_flight: 0,
+            sent: 1,
+            shared: 2
+        }
+    );
+
+    // done, the next one goes out again
+    echo.get::<Settings>("/config").await.unwrap();
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_different_requests_are_sent() {
+    let mock = settings_mock();
+    mock.on_post("/config").reply(204, ());
+    let single_flight = Arc::new(SingleFlight::new());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            single_flight: Some(single_flight.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (ada, grace, query, post, again) = tokio::join!(
+        echo.request_unknown(request("/config", Some("Authorization: Bearer ada"))),
+        echo.request_unknown(request("/config", Some("Authorization: Bearer grace"))),
+        echo.request_unknown(request("/config?v=2", None)),
+        echo.post_no("/config"),
+        echo.request_unknown(request("/config", Some("Authorization: Bearer ada"))),
+    );
+
+    for response in [ada, grace, query, again] {
+        assert_eq!(response.unwrap().status, 200);
+    }
+    assert_eq!(post.unwrap().status, 204);
+    assert_eq!(mock.requests().len(), 4);
+    assert_eq!(single_flight.stats().shared, 1);
+}
+
+#[tokio::test]
+async fn test_custom_dedupe_key() {
+    let mock = settings_mock();
+    mock.on_get("/config/eu")
+        .reply(200, json!({ "region": "eu" }));
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            single_flight: Some(Arc::new(SingleFlight::new())),
+            ..Default::default()
+        },
+    );
+    let keyed = |url: &str| RequestConfig {
+        dedupe_key: Some("settings".to_string()),
+        ..request(url, None)
+    };
+
+    let (first, second) = tokio::join!(
+        echo.request::<Settings>(keyed("/config")),
+        echo.request::<Settings>(keyed("/config/eu")),
+    );
+
+    assert_eq!(first.unwrap().data, second.unwrap().data);
+    assert_eq!(mock.requests().len(), 1);
+    assert_eq!(mock.requests()[0].url.path(), "/config");
+}
+
+#[tokio::test]
+async fn test_dedupe_key_is_per_request() {
+    let mock = settings_mock();
+    mock.on_get("/config/eu")
+        .reply(200, json!({ "region": "eu" }));
+    let single_flight = Arc::new(SingleFlight::new());
+    let mut echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            single_flight: Some(single_flight.clone()),
+            ..Default::default()
+        },
+    );
+    echo.config.dedupe_key = Some("settings".to_string());
+
+    let (first, second) = tokio::join!(
+        echo.request::<Settings>(request("/config", None)),
+        echo.request::<Settings>(request("/config/eu", None)),
+    );
+
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(mock.requests().len(), 2);
+    assert_eq!(single_flight.stats().shared, 0);
+
+    // the shorthand methods ignore it too
+    let (first, second) = tokio::join!(
+        echo.get::<Settings>("/config"),
+        echo.get::<Settings>("/config/eu"),
+    );
+
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(mock.requests().len(), 4);
+    assert_eq!(mock.requests()[3].url.path(), "/config/eu");
+    assert_eq!(single_flight.stats().shared, 0);
+}
+
+#[tokio::test]
+async fn test_errors_are_not_shared() {
+    let mock = MockAdapter::new();
+    mock.on_get("/config").reply_sequence([
+        MockReply::network_error(ErrorCode::ConnectionReset).delay(Duration::from_millis(30)),
+        MockReply::json(200, &json!({ "region": "eu" })),
+    ]);
+    let single_flight = Arc::new(SingleFlight::new());
+    let echo = mock_echo(
+        &mock,
+        RequestConfig {
+            base_url: Some(API.to_string()),
+            single_flight: Some(single_flight.clone()),
+            ..Default::default()
+        },
+    );
+
+    let (failed, follower) = tokio::join!(
+        echo.get::<Settings>("/config"),
+        echo.get::<Settings>("/config"),
+    );
+
+    assert_eq!(failed.unwrap_err().code(), ErrorCode::ConnectionReset);
+    assert_eq!(follower.unwrap().data.region, "eu");
+    assert_eq!(mock.requests().len(), 2);
+    assert_eq!(single_flight.stats().in_flight, 0);
+}
@@ -0,0 +1,169 @@
+use bytes::Bytes;
+use echo_http::{Echo, EchoError, ErrorCode, RequestConfig, TransformRequest, TransformResponse};
+use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+mod common;
+use common::{echo_server, fixed_server, mirror_server};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: u32,
+    name: String,
+}
+
+fn envelope() -> TransformRequest {
+    TransformRequest::new(|body, headers| {
+        let data: Value = serde_json::from_slice(&body)?;
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/vnd.envelope+json"),
+        );
+        Ok(serde_json::to_vec(&json!({ "data": data }))?.into())
+    })
+}
+
+fn unwrap_envelope() -> TransformResponse {
+    TransformResponse::new(|body, headers| {
+        let mut envelope: Value = serde_json::from_slice(&body)?;
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Ok(serde_json::to_vec(&envelope["data"].take())?.into())
+    })
+}
+
+fn snake_to_camel() -> TransformResponse {
+    fn camel(key: &str) -> String {
+        let mut parts = key.split('_');
+        let first = parts.next().unwrap_or("").to_string();
+        parts.fold(first, |mut camel, part| {
+            let mut chars = part.chars();
+            if let Some(head) = chars.next() {
+                camel.extend(head.to_uppercase());
+                camel.push_str(chars.as_str());
+            }
+            camel
+        })
+    }
+
+    TransformResponse::new(|body, _headers| {
+        let value: Value = serde_json::from_slice(&body)?;
+        let value = match value {
+            Value::Object(map) => map
+                .into_iter()
+                .map(|(key, value)| (camel(&key), value))
+                .collect(),
+            other => other,
+        };
+        Ok(Bytes::from(serde_json::to_vec(&value)?))
+    })
+}
+
+#[tokio::test]
+async fn test_request_and_response_transforms() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(mirror_server().await),
+        transform_request: Some(vec![envelope()]),
+        transform_response: Some(vec![unwrap_envelope()]),
+        ..Default::default()
+    }));
+
+    let user = User {
+        id: 1,
+        name: "Test".to_string(),
+    };
+    let response = echo.post("/users", Some(user)).await.unwrap();
+
+    assert_eq!(
+        response.data,
+        User {
+            id: 1,
+            name: "Test".to_string()
+        }
+    );
+    // the response transform rewrote the mirrored content type
+    assert_eq!(response.headers[CONTENT_TYPE], "application/json");
+}
+
+#[tokio::test]
+async fn test_transforms_run_in_order() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(mirror_server().await),
+        ..Default::default()
+    }));
+
+    let response = echo
+        .request_unknown(RequestConfig {
+            url: Some("/users".to_string()),
+            method: reqwest::Method::POST,
+            data: Some(json!({ "user_name": "test" })),
+            transform_request: Some(vec![envelope(), envelope()]),
+            transform_response: Some(vec![unwrap_envelope(), unwrap_envelope(), snake_to_camel()]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.data, json!({ "userName": "test" }));
+}
+
+#[tokio::test]
+async fn test_response_transform_on_unknown_and_error_bodies() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(fixed_server(404, r#"{"error_code":"missing"}"#).await),
+        transform_response: Some(vec![snake_to_camel()]),
+        ..Default::default()
+    }));
+
+    let response = echo.get_unknown("/users/1").await.unwrap();
+    assert_eq!(response.data["errorCode"], "missing");
+
+    let err = echo
+        .get_with_error::<User, Value>("/users/1")
+        .await
+        .unwrap_err();
+    assert_eq!(err.api_data::<Value>().unwrap()["errorCode"], "missing");
+}
+
+#[tokio::test]
+async fn test_request_transform_without_body() {
+    let sign = TransformRequest::new(|body, headers| {
+        assert!(body.is_empty());
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Signature abc"));
+        Ok(body)
+    });
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(echo_server().await),
+        transform_request: Some(vec![sign]),
+        ..Default::default()
+    }));
+
+    let response = echo.get_unknown("/users").await.unwrap();
+
+    assert_eq!(response.data["authorization"], "Signature abc");
+    assert_eq!(response.data["body"], "");
+}
+
+#[tokio::test]
+async fn test_transform_errors() {
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(echo_server().await),
+        transform_request: Some(vec![TransformRequest::new(|_, _| Err("no key".into()))]),
+        ..Default::default()
+    }));
+
+    let err = echo.get_unknown("/users").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::RequestTransformError(_)));
+    assert_eq!(err.code(), ErrorCode::BadRequest);
+
+    let echo = Echo::configure(Some(RequestConfig {
+        base_url: Some(echo_server().await),
+        transform_response: Some(vec![TransformResponse::new(|_, _| Err("bad".into()))]),
+        ..Default::default()
+    }));
+
+    let err = echo.get_unknown("/users").await.unwrap_err();
+    assert!(matches!(err.kind(), EchoError::ResponseTransformError(_)));
+    assert_eq!(err.code(), ErrorCode::BadResponse);
+    assert_eq!(err.status(), Some(200));
+}